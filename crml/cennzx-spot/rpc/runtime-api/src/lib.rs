@@ -19,25 +19,171 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use codec::{Codec, Decode, Encode};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
 use sp_arithmetic::traits::BaseArithmetic;
-use sp_runtime::RuntimeDebug;
+use sp_runtime::{DispatchError, RuntimeDebug};
+use sp_std::prelude::*;
 
 /// A result of querying the exchange
 #[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
 pub enum CennzxSpotResult<Balance> {
+	/// The exchange returned successfully.
+	Success(Balance),
+	/// There was an issue querying the exchange
+	Error(CennzxSpotError),
+}
+
+/// A result of querying the exchange, as returned by version 1 of `CennzxSpotApi`
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum CennzxSpotResultV1<Balance> {
 	/// The exchange returned successfully.
 	Success(Balance),
 	/// There was an issue querying the exchange
 	Error,
 }
 
+impl<Balance> From<CennzxSpotResultV1<Balance>> for CennzxSpotResult<Balance> {
+	/// Version 1 results do not give the reason a query failed
+	fn from(result: CennzxSpotResultV1<Balance>) -> Self {
+		match result {
+			CennzxSpotResultV1::Success(balance) => CennzxSpotResult::Success(balance),
+			CennzxSpotResultV1::Error => CennzxSpotResult::Error(CennzxSpotError::Other),
+		}
+	}
+}
+
+/// The reason a query of the exchange failed
+#[derive(Eq, PartialEq, Clone, Copy, Encode, Decode, RuntimeDebug)]
+pub enum CennzxSpotError {
+	/// The exchange holds no liquidity
+	EmptyExchangePool,
+	/// The exchange does not hold enough of the asset to buy
+	InsufficientExchangePoolReserve,
+	/// An asset cannot be traded for itself
+	AssetCannotSwapForItself,
+	/// The asset is not tradeable on the exchange
+	InvalidAssetId,
+	/// The path is not a route through existing exchanges
+	InvalidExchangePath,
+	/// The amount is too large to price
+	Overflow,
+	/// The exchange reserves cannot price the amount
+	DivideByZero,
+	/// Not enough price history is held for the window
+	InsufficientPriceHistory,
+	/// The window is not valid
+	InvalidPriceWindow,
+	/// Any other failure
+	Other,
+}
+
+impl From<DispatchError> for CennzxSpotError {
+	/// Identify a CENNZX-Spot module error by name
+	fn from(error: DispatchError) -> Self {
+		match error {
+			DispatchError::Module {
+				message: Some(message), ..
+			} => match message {
+				"EmptyExchangePool" => CennzxSpotError::EmptyExchangePool,
+				"InsufficientExchangePoolReserve" => CennzxSpotError::InsufficientExchangePoolReserve,
+				"AssetCannotSwapForItself" => CennzxSpotError::AssetCannotSwapForItself,
+				"InvalidAssetId" => CennzxSpotError::InvalidAssetId,
+				"InvalidExchangePath" => CennzxSpotError::InvalidExchangePath,
+				"Overflow" => CennzxSpotError::Overflow,
+				"DivideByZero" => CennzxSpotError::DivideByZero,
+				"InsufficientPriceHistory" => CennzxSpotError::InsufficientPriceHistory,
+				"InvalidPriceWindow" => CennzxSpotError::InvalidPriceWindow,
+				_ => CennzxSpotError::Other,
+			},
+			_ => CennzxSpotError::Other,
+		}
+	}
+}
+
+/// The state of an exchange pool
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ExchangeInfo<AssetId, Balance, AccountId, BlockNumber> {
+	/// The trade asset of the exchange
+	pub asset_id: AssetId,
+	/// The account holding the exchange reserves
+	pub exchange_address: AccountId,
+	/// The core asset held by the exchange
+	pub core_reserve: Balance,
+	/// The trade asset held by the exchange
+	pub asset_reserve: Balance,
+	/// The liquidity held by all investors in the exchange
+	pub total_liquidity: Balance,
+	/// The trading fee rate of the exchange in parts per million
+	pub fee_rate: u128,
+	/// The block the exchange was created in
+	pub created_at: BlockNumber,
+}
+
+/// A quote for a trade through the exchanges
+/// Prices are given as the price of one asset bought in the asset sold, scaled by 10^18
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct TradeQuote<AssetId, Balance> {
+	/// The assets traded through, from the asset sold to the asset bought
+	pub route: Vec<AssetId>,
+	/// The amount of the asset sold
+	pub amount_in: Balance,
+	/// The amount of the asset bought
+	pub amount_out: Balance,
+	/// The trading fees paid, in the asset sold
+	pub fee: Balance,
+	/// The price before the trade
+	pub spot_price_before: Balance,
+	/// The price after the trade
+	pub spot_price_after: Balance,
+	/// The average price paid, including fees
+	pub execution_price: Balance,
+	/// How much the average price paid, excluding fees, exceeds the price before the trade in parts per million
+	pub price_impact: u128,
+}
+
+/// The cost and trading fee earnings of the liquidity held by an account in an exchange
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct FeeEarnings<Balance> {
+	/// The core asset deposited for the liquidity held
+	pub core_cost: Balance,
+	/// The trade asset deposited for the liquidity held
+	pub asset_cost: Balance,
+	/// Core asset fees earned by the liquidity held
+	pub unrealised_core_fees: Balance,
+	/// Trade asset fees earned by the liquidity held
+	pub unrealised_asset_fees: Balance,
+	/// Core asset fees withdrawn with liquidity
+	pub realised_core_fees: Balance,
+	/// Trade asset fees withdrawn with liquidity
+	pub realised_asset_fees: Balance,
+}
+
 sp_api::decl_runtime_apis! {
 	/// The RPC API to interact with CENNZX Spot Exchange
-	pub trait CennzxSpotApi<AssetId, Balance, AccountId> where
+	///
+	/// Version 2 gives the reason a query failed, and adds every method but `buy_price`, `sell_price`,
+	/// `liquidity_value` and `liquidity_price`.
+	#[api_version(2)]
+	pub trait CennzxSpotApi<AssetId, Balance, AccountId, BlockNumber> where
 		AssetId: Codec,
 		Balance: Codec + BaseArithmetic,
 		AccountId: Codec,
+		BlockNumber: Codec,
 	{
+		/// Query how much `asset_to_buy` will be given in exchange for `amount` of `asset_to_sell`
+		#[changed_in(2)]
+		fn buy_price(
+			asset_to_buy: AssetId,
+			amount: Balance,
+			asset_to_sell: AssetId,
+		) -> CennzxSpotResultV1<Balance>;
 		/// Query how much `asset_to_buy` will be given in exchange for `amount` of `asset_to_sell`
 		fn buy_price(
 			asset_to_buy: AssetId,
@@ -45,10 +191,29 @@ sp_api::decl_runtime_apis! {
 			asset_to_sell: AssetId,
 		) -> CennzxSpotResult<Balance>;
 		/// Query how much `asset_to_sell` is required to buy `amount` of `asset_to_buy`
+		#[changed_in(2)]
 		fn sell_price(
 			asset_to_sell: AssetId,
 			amount: Balance,
 			asset_to_buy: AssetId,
+		) -> CennzxSpotResultV1<Balance>;
+		/// Query how much `asset_to_sell` is required to buy `amount` of `asset_to_buy`
+		fn sell_price(
+			asset_to_sell: AssetId,
+			amount: Balance,
+			asset_to_buy: AssetId,
+		) -> CennzxSpotResult<Balance>;
+		/// Query how much of the first asset in `path` is required to buy `amount` of the last asset in `path`
+		/// when trading through each exchange along `path`
+		fn buy_price_via_path(
+			path: Vec<AssetId>,
+			amount: Balance,
+		) -> CennzxSpotResult<Balance>;
+		/// Query how much of the last asset in `path` will be given in exchange for `amount` of the first
+		/// asset in `path` when trading through each exchange along `path`
+		fn sell_price_via_path(
+			path: Vec<AssetId>,
+			amount: Balance,
 		) -> CennzxSpotResult<Balance>;
 		/// Query the value of liquidity in the exchange for `asset_id` for `account`
 		/// Returns (liquidity_volume, core_value, asset_value)
@@ -62,5 +227,37 @@ sp_api::decl_runtime_apis! {
 			asset_id: AssetId,
 			liquidity_to_buy: Balance,
 		) -> (Balance, Balance);
+		/// Query the time weighted average price of the exchange for `asset_id` over the last `window` blocks
+		/// Returns prices scaled by 10^18 as (core_price, asset_price)
+		fn twap(
+			asset_id: AssetId,
+			window: BlockNumber,
+		) -> CennzxSpotResult<(Balance, Balance)>;
+		/// Query the protocol fee charged when selling `amount` of `asset_to_sell` for `asset_to_buy`
+		/// The fee is given in `asset_to_sell`
+		fn protocol_fee(
+			asset_to_sell: AssetId,
+			amount: Balance,
+			asset_to_buy: AssetId,
+		) -> CennzxSpotResult<Balance>;
+		/// Query a quote for buying `amount` of `asset_to_buy` with `asset_to_sell`
+		fn buy_quote(
+			asset_to_buy: AssetId,
+			amount: Balance,
+			asset_to_sell: AssetId,
+		) -> CennzxSpotResult<TradeQuote<AssetId, Balance>>;
+		/// Query a quote for selling `amount` of `asset_to_sell` for `asset_to_buy`
+		fn sell_quote(
+			asset_to_sell: AssetId,
+			amount: Balance,
+			asset_to_buy: AssetId,
+		) -> CennzxSpotResult<TradeQuote<AssetId, Balance>>;
+		/// Query the cost and trading fee earnings of the liquidity held by `account` in the exchange for `asset_id`
+		fn fee_earnings(
+			account: AccountId,
+			asset_id: AssetId,
+		) -> FeeEarnings<Balance>;
+		/// Query the state of every exchange holding liquidity
+		fn exchanges() -> Vec<ExchangeInfo<AssetId, Balance, AccountId, BlockNumber>>;
 	}
 }
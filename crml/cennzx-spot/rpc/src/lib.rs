@@ -16,40 +16,209 @@
 
 //! Node-specific RPC methods for interaction with CENNZX.
 
-use std::{convert::TryInto, sync::Arc};
+use std::sync::Arc;
 
 use codec::Codec;
 use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
 use jsonrpc_derive::rpc;
-use sp_api::ProvideRuntimeApi;
-use sp_arithmetic::traits::{BaseArithmetic, SaturatedConversion};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_arithmetic::traits::{BaseArithmetic, CheckedAdd, SaturatedConversion, Zero};
 use sp_blockchain::HeaderBackend;
-use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, NumberFor},
+};
 
 pub use self::gen_client::Client as CennzxSpotClient;
 pub use crml_cennzx_spot_rpc_runtime_api::{
-	self as runtime_api, CennzxSpotApi as CennzxSpotRuntimeApi, CennzxSpotResult,
+	self as runtime_api, CennzxSpotApi as CennzxSpotRuntimeApi, CennzxSpotError, CennzxSpotResult, CennzxSpotResultV1,
+	ExchangeInfo, FeeEarnings, TradeQuote,
 };
 
+/// A balance which serializes as a decimal string.
+/// JSON numbers only hold 64 bits, so larger balances would otherwise be truncated or rejected.
+/// Deserializes from a decimal string or a number.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub struct WrappedBalance(pub u128);
+
+impl Serialize for WrappedBalance {
+	fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		serializer.serialize_str(&self.0.to_string())
+	}
+}
+
+impl<'de> Deserialize<'de> for WrappedBalance {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+		#[derive(Deserialize)]
+		#[serde(untagged)]
+		enum StringOrNumber {
+			String(String),
+			Number(u64),
+		}
+
+		match StringOrNumber::deserialize(deserializer)? {
+			StringOrNumber::String(value) => value.parse::<u128>().map(WrappedBalance).map_err(de::Error::custom),
+			StringOrNumber::Number(value) => Ok(WrappedBalance(value.into())),
+		}
+	}
+}
+
+impl WrappedBalance {
+	fn from_balance<Balance: BaseArithmetic>(balance: Balance) -> Self {
+		WrappedBalance(balance.saturated_into())
+	}
+}
+
+/// The most blocks `cennzx_poolHistory` samples in one query
+const MAX_POOL_HISTORY_SAMPLES: u64 = 1_000;
+
+/// The state of an exchange pool at a block
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolState<BlockNumber, BlockHash> {
+	/// The block sampled
+	pub block_number: BlockNumber,
+	/// The hash of the block sampled
+	pub block_hash: BlockHash,
+	/// The core asset held by the exchange
+	pub core_reserve: WrappedBalance,
+	/// The trade asset held by the exchange
+	pub asset_reserve: WrappedBalance,
+	/// The liquidity held by all investors in the exchange
+	pub total_liquidity: WrappedBalance,
+}
+
 /// Contracts RPC methods.
 #[rpc]
-pub trait CennzxSpotApi<AssetId, Balance, AccountId> {
+pub trait CennzxSpotApi<BlockHash, AssetId, Balance, AccountId, BlockNumber> {
 	#[rpc(name = "cennzx_buyPrice")]
-	// TODO: prefer to return Result<Balance>, however Serde JSON library only allows u64.
-	//  - change to Result<Balance> once https://github.com/serde-rs/serde/pull/1679 is merged
-	fn buy_price(&self, asset_to_buy: AssetId, amount_to_buy: Balance, asset_to_pay: AssetId) -> Result<u64>;
+	fn buy_price(
+		&self,
+		asset_to_buy: AssetId,
+		amount_to_buy: Balance,
+		asset_to_pay: AssetId,
+		at: Option<BlockHash>,
+	) -> Result<WrappedBalance>;
 
 	#[rpc(name = "cennzx_sellPrice")]
-	// TODO: change to Result<Balance> once https://github.com/serde-rs/serde/pull/1679 is merged
-	fn sell_price(&self, asset_to_sell: AssetId, amount_to_buy: Balance, asset_to_payout: AssetId) -> Result<u64>;
+	fn sell_price(
+		&self,
+		asset_to_sell: AssetId,
+		amount_to_buy: Balance,
+		asset_to_payout: AssetId,
+		at: Option<BlockHash>,
+	) -> Result<WrappedBalance>;
+
+	#[rpc(name = "cennzx_buyPriceViaPath")]
+	fn buy_price_via_path(
+		&self,
+		path: Vec<AssetId>,
+		amount_to_buy: Balance,
+		at: Option<BlockHash>,
+	) -> Result<WrappedBalance>;
+
+	#[rpc(name = "cennzx_sellPriceViaPath")]
+	fn sell_price_via_path(
+		&self,
+		path: Vec<AssetId>,
+		amount_to_sell: Balance,
+		at: Option<BlockHash>,
+	) -> Result<WrappedBalance>;
 
 	#[rpc(name = "cennzx_liquidityValue")]
-	// TODO: change to Result<Balance> once https://github.com/serde-rs/serde/pull/1679 is merged
-	fn liquidity_value(&self, account_id: AccountId, asset_id: AssetId) -> Result<(u64, u64, u64)>;
+	fn liquidity_value(
+		&self,
+		account_id: AccountId,
+		asset_id: AssetId,
+		at: Option<BlockHash>,
+	) -> Result<(WrappedBalance, WrappedBalance, WrappedBalance)>;
 
 	#[rpc(name = "cennzx_liquidityPrice")]
-	// TODO: change to Result<Balance> once https://github.com/serde-rs/serde/pull/1679 is merged
-	fn liquidity_price(&self, asset_id: AssetId, liquidity_to_buy: Balance) -> Result<(u64, u64)>;
+	fn liquidity_price(
+		&self,
+		asset_id: AssetId,
+		liquidity_to_buy: Balance,
+		at: Option<BlockHash>,
+	) -> Result<(WrappedBalance, WrappedBalance)>;
+
+	#[rpc(name = "cennzx_twap")]
+	fn twap(
+		&self,
+		asset_id: AssetId,
+		window: BlockNumber,
+		at: Option<BlockHash>,
+	) -> Result<(WrappedBalance, WrappedBalance)>;
+
+	#[rpc(name = "cennzx_protocolFee")]
+	fn protocol_fee(
+		&self,
+		asset_to_sell: AssetId,
+		amount_to_sell: Balance,
+		asset_to_buy: AssetId,
+		at: Option<BlockHash>,
+	) -> Result<WrappedBalance>;
+
+	#[rpc(name = "cennzx_buyQuote")]
+	fn buy_quote(
+		&self,
+		asset_to_buy: AssetId,
+		amount_to_buy: Balance,
+		asset_to_pay: AssetId,
+		at: Option<BlockHash>,
+	) -> Result<TradeQuote<AssetId, WrappedBalance>>;
+
+	#[rpc(name = "cennzx_sellQuote")]
+	fn sell_quote(
+		&self,
+		asset_to_sell: AssetId,
+		amount_to_sell: Balance,
+		asset_to_payout: AssetId,
+		at: Option<BlockHash>,
+	) -> Result<TradeQuote<AssetId, WrappedBalance>>;
+
+	#[rpc(name = "cennzx_feeEarnings")]
+	fn fee_earnings(
+		&self,
+		account_id: AccountId,
+		asset_id: AssetId,
+		at: Option<BlockHash>,
+	) -> Result<FeeEarnings<WrappedBalance>>;
+
+	#[rpc(name = "cennzx_exchanges")]
+	fn exchanges(
+		&self,
+		at: Option<BlockHash>,
+	) -> Result<Vec<ExchangeInfo<AssetId, WrappedBalance, AccountId, BlockNumber>>>;
+
+	/// Sample the reserves and liquidity of the exchange for `asset_id` every `step` blocks,
+	/// from `from_block` up to `to_block` or the best block
+	/// Blocks are read from the canonical chain, so there is no `at` parameter
+	/// Blocks from before the runtime could report its exchanges are not sampled
+	#[rpc(name = "cennzx_poolHistory")]
+	fn pool_history(
+		&self,
+		asset_id: AssetId,
+		from_block: BlockNumber,
+		to_block: BlockNumber,
+		step: BlockNumber,
+	) -> Result<Vec<PoolState<BlockNumber, BlockHash>>>;
+}
+
+/// A trade quote with its balances wrapped for serialization
+fn wrap_quote<AssetId, Balance: BaseArithmetic>(
+	quote: TradeQuote<AssetId, Balance>,
+) -> TradeQuote<AssetId, WrappedBalance> {
+	TradeQuote {
+		route: quote.route,
+		amount_in: WrappedBalance::from_balance(quote.amount_in),
+		amount_out: WrappedBalance::from_balance(quote.amount_out),
+		fee: WrappedBalance::from_balance(quote.fee),
+		spot_price_before: WrappedBalance::from_balance(quote.spot_price_before),
+		spot_price_after: WrappedBalance::from_balance(quote.spot_price_after),
+		execution_price: WrappedBalance::from_balance(quote.execution_price),
+		price_impact: quote.price_impact,
+	}
 }
 
 /// An implementation of CENNZX Spot Exchange specific RPC methods.
@@ -72,8 +241,8 @@ impl<C, T> CennzxSpot<C, T> {
 pub enum Error {
 	/// The call to runtime failed.
 	Runtime,
+	/// The exchange could not satisfy the query, the reason is given in the error data.
 	CannotExchange,
-	PriceOverflow,
 }
 
 impl From<Error> for i64 {
@@ -81,80 +250,165 @@ impl From<Error> for i64 {
 		match e {
 			Error::Runtime => 1,
 			Error::CannotExchange => 2,
-			Error::PriceOverflow => 3,
 		}
 	}
 }
 
-impl<C, Block, AssetId, Balance, AccountId> CennzxSpotApi<AssetId, Balance, AccountId> for CennzxSpot<C, Block>
+/// An RPC error for a query the exchange could not satisfy, carrying the reason as its data
+fn exchange_error(message: &str, error: CennzxSpotError) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::CannotExchange.into()),
+		message: message.into(),
+		data: Some(format!("{:?}", error).into()),
+	}
+}
+
+impl<C, Block> CennzxSpot<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block>,
+{
+	/// Whether the runtime at block `at` has a version of the CENNZX-Spot runtime API matching `predicate`
+	fn has_api_with<AssetId, Balance, AccountId, BlockNumber, P: Fn(u32) -> bool>(
+		&self,
+		at: &BlockId<Block>,
+		predicate: P,
+	) -> Result<bool>
+	where
+		C::Api: CennzxSpotRuntimeApi<Block, AssetId, Balance, AccountId, BlockNumber>,
+	{
+		self.client
+			.runtime_api()
+			.has_api_with::<dyn CennzxSpotRuntimeApi<Block, AssetId, Balance, AccountId, BlockNumber, Error = ()>, _>(
+				at, predicate,
+			)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::Runtime.into()),
+				message: "Unable to query runtime API version.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+	}
+}
+
+impl<C, Block, AssetId, Balance, AccountId, BlockNumber>
+	CennzxSpotApi<<Block as BlockT>::Hash, AssetId, Balance, AccountId, BlockNumber> for CennzxSpot<C, Block>
 where
 	Block: BlockT,
 	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
-	C::Api: CennzxSpotRuntimeApi<Block, AssetId, Balance, AccountId>,
-	AssetId: Codec,
+	C::Api: CennzxSpotRuntimeApi<Block, AssetId, Balance, AccountId, BlockNumber>,
+	AssetId: Codec + PartialEq,
 	Balance: Codec + BaseArithmetic,
 	AccountId: Codec,
+	BlockNumber: Codec + BaseArithmetic + Copy + Into<NumberFor<Block>>,
 {
-	fn buy_price(&self, asset_to_buy: AssetId, amount_to_buy: Balance, asset_to_pay: AssetId) -> Result<u64> {
+	fn buy_price(
+		&self,
+		asset_to_buy: AssetId,
+		amount_to_buy: Balance,
+		asset_to_pay: AssetId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<WrappedBalance> {
 		let api = self.client.runtime_api();
-		let best = self.client.info().best_hash;
-		let at = BlockId::hash(best);
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
 
-		let result = api
-			.buy_price(&at, asset_to_buy, amount_to_buy, asset_to_pay)
-			.map_err(|e| RpcError {
-				code: ErrorCode::ServerError(Error::Runtime.into()),
-				message: "Unable to query buy price.".into(),
-				data: Some(format!("{:?}", e).into()),
-			})?;
+		// Version 1 of the runtime API does not give the reason a query failed
+		#[allow(deprecated)]
+		let result = if self.has_api_with::<AssetId, Balance, AccountId, BlockNumber, _>(&at, |v| v < 2)? {
+			api.buy_price_before_version_2(&at, asset_to_buy, amount_to_buy, asset_to_pay)
+				.map(CennzxSpotResult::from)
+		} else {
+			api.buy_price(&at, asset_to_buy, amount_to_buy, asset_to_pay)
+		}
+		.map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::Runtime.into()),
+			message: "Unable to query buy price.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?;
 		match result {
-			CennzxSpotResult::Success(price) => {
-				TryInto::<u64>::try_into(price.saturated_into::<u128>()).map_err(|e| RpcError {
-					code: ErrorCode::ServerError(Error::PriceOverflow.into()),
-					message: "Price too large.".into(),
-					data: Some(format!("{:?}", e).into()),
-				})
-			}
-			CennzxSpotResult::Error => Err(RpcError {
-				code: ErrorCode::ServerError(Error::CannotExchange.into()),
-				message: "Cannot exchange for requested amount.".into(),
-				data: Some("".into()),
-			}),
+			CennzxSpotResult::Success(price) => Ok(WrappedBalance::from_balance(price)),
+			CennzxSpotResult::Error(e) => Err(exchange_error("Cannot exchange for requested amount.", e)),
 		}
 	}
 
-	fn sell_price(&self, asset_to_sell: AssetId, amount_to_sell: Balance, asset_to_payout: AssetId) -> Result<u64> {
+	fn sell_price(
+		&self,
+		asset_to_sell: AssetId,
+		amount_to_sell: Balance,
+		asset_to_payout: AssetId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<WrappedBalance> {
 		let api = self.client.runtime_api();
-		let best = self.client.info().best_hash;
-		let at = BlockId::hash(best);
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		// Version 1 of the runtime API does not give the reason a query failed
+		#[allow(deprecated)]
+		let result = if self.has_api_with::<AssetId, Balance, AccountId, BlockNumber, _>(&at, |v| v < 2)? {
+			api.sell_price_before_version_2(&at, asset_to_sell, amount_to_sell, asset_to_payout)
+				.map(CennzxSpotResult::from)
+		} else {
+			api.sell_price(&at, asset_to_sell, amount_to_sell, asset_to_payout)
+		}
+		.map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::Runtime.into()),
+			message: "Unable to query sell price.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?;
+		match result {
+			CennzxSpotResult::Success(price) => Ok(WrappedBalance::from_balance(price)),
+			CennzxSpotResult::Error(e) => Err(exchange_error("Cannot exchange by requested amount.", e)),
+		}
+	}
+
+	fn buy_price_via_path(
+		&self,
+		path: Vec<AssetId>,
+		amount_to_buy: Balance,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<WrappedBalance> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let result = api.buy_price_via_path(&at, path, amount_to_buy).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::Runtime.into()),
+			message: "Unable to query buy price.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?;
+		match result {
+			CennzxSpotResult::Success(price) => Ok(WrappedBalance::from_balance(price)),
+			CennzxSpotResult::Error(e) => Err(exchange_error("Cannot exchange for requested amount.", e)),
+		}
+	}
+
+	fn sell_price_via_path(
+		&self,
+		path: Vec<AssetId>,
+		amount_to_sell: Balance,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<WrappedBalance> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
 
 		let result = api
-			.sell_price(&at, asset_to_sell, amount_to_sell, asset_to_payout)
+			.sell_price_via_path(&at, path, amount_to_sell)
 			.map_err(|e| RpcError {
 				code: ErrorCode::ServerError(Error::Runtime.into()),
 				message: "Unable to query sell price.".into(),
 				data: Some(format!("{:?}", e).into()),
 			})?;
 		match result {
-			CennzxSpotResult::Success(price) => {
-				TryInto::<u64>::try_into(price.saturated_into::<u128>()).map_err(|e| RpcError {
-					code: ErrorCode::ServerError(Error::PriceOverflow.into()),
-					message: "Price too large.".into(),
-					data: Some(format!("{:?}", e).into()),
-				})
-			}
-			CennzxSpotResult::Error => Err(RpcError {
-				code: ErrorCode::ServerError(Error::CannotExchange.into()),
-				message: "Cannot exchange by requested amount.".into(),
-				data: Some("".into()),
-			}),
+			CennzxSpotResult::Success(price) => Ok(WrappedBalance::from_balance(price)),
+			CennzxSpotResult::Error(e) => Err(exchange_error("Cannot exchange by requested amount.", e)),
 		}
 	}
 
-	fn liquidity_value(&self, account: AccountId, asset_id: AssetId) -> Result<(u64, u64, u64)> {
+	fn liquidity_value(
+		&self,
+		account: AccountId,
+		asset_id: AssetId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<(WrappedBalance, WrappedBalance, WrappedBalance)> {
 		let api = self.client.runtime_api();
-		let best = self.client.info().best_hash;
-		let at = BlockId::hash(best);
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
 
 		let result = api.liquidity_value(&at, account, asset_id).map_err(|e| RpcError {
 			code: ErrorCode::ServerError(Error::Runtime.into()),
@@ -162,28 +416,21 @@ where
 			data: Some(format!("{:?}", e).into()),
 		})?;
 
-		let liquidity = TryInto::<u64>::try_into(result.0.saturated_into::<u128>()).map_err(|e| RpcError {
-			code: ErrorCode::ServerError(Error::PriceOverflow.into()),
-			message: "Liquidity too large.".into(),
-			data: Some(format!("{:?}", e).into()),
-		})?;
-		let core = TryInto::<u64>::try_into(result.1.saturated_into::<u128>()).map_err(|e| RpcError {
-			code: ErrorCode::ServerError(Error::PriceOverflow.into()),
-			message: "Core asset too large.".into(),
-			data: Some(format!("{:?}", e).into()),
-		})?;
-		let asset = TryInto::<u64>::try_into(result.2.saturated_into::<u128>()).map_err(|e| RpcError {
-			code: ErrorCode::ServerError(Error::PriceOverflow.into()),
-			message: "Trade asset too large.".into(),
-			data: Some(format!("{:?}", e).into()),
-		})?;
-		Ok((liquidity, core, asset))
+		Ok((
+			WrappedBalance::from_balance(result.0),
+			WrappedBalance::from_balance(result.1),
+			WrappedBalance::from_balance(result.2),
+		))
 	}
 
-	fn liquidity_price(&self, asset_id: AssetId, liquidity_to_buy: Balance) -> Result<(u64, u64)> {
+	fn liquidity_price(
+		&self,
+		asset_id: AssetId,
+		liquidity_to_buy: Balance,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<(WrappedBalance, WrappedBalance)> {
 		let api = self.client.runtime_api();
-		let best = self.client.info().best_hash;
-		let at = BlockId::hash(best);
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
 
 		let result = api
 			.liquidity_price(&at, asset_id, liquidity_to_buy)
@@ -193,16 +440,223 @@ where
 				data: Some(format!("{:?}", e).into()),
 			})?;
 
-		let core = TryInto::<u64>::try_into(result.0.saturated_into::<u128>()).map_err(|e| RpcError {
-			code: ErrorCode::ServerError(Error::PriceOverflow.into()),
-			message: "Core asset too large.".into(),
+		Ok((
+			WrappedBalance::from_balance(result.0),
+			WrappedBalance::from_balance(result.1),
+		))
+	}
+
+	fn twap(
+		&self,
+		asset_id: AssetId,
+		window: BlockNumber,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<(WrappedBalance, WrappedBalance)> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let result = api.twap(&at, asset_id, window).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::Runtime.into()),
+			message: "Unable to query time weighted average price.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?;
+		match result {
+			CennzxSpotResult::Success((core_price, asset_price)) => Ok((
+				WrappedBalance::from_balance(core_price),
+				WrappedBalance::from_balance(asset_price),
+			)),
+			CennzxSpotResult::Error(e) => Err(exchange_error("Cannot price requested window.", e)),
+		}
+	}
+
+	fn protocol_fee(
+		&self,
+		asset_to_sell: AssetId,
+		amount_to_sell: Balance,
+		asset_to_buy: AssetId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<WrappedBalance> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let result = api
+			.protocol_fee(&at, asset_to_sell, amount_to_sell, asset_to_buy)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::Runtime.into()),
+				message: "Unable to query protocol fee.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?;
+		match result {
+			CennzxSpotResult::Success(fee) => Ok(WrappedBalance::from_balance(fee)),
+			CennzxSpotResult::Error(e) => Err(exchange_error("Cannot exchange by requested amount.", e)),
+		}
+	}
+
+	fn buy_quote(
+		&self,
+		asset_to_buy: AssetId,
+		amount_to_buy: Balance,
+		asset_to_pay: AssetId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<TradeQuote<AssetId, WrappedBalance>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let result = api
+			.buy_quote(&at, asset_to_buy, amount_to_buy, asset_to_pay)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::Runtime.into()),
+				message: "Unable to query buy quote.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?;
+		match result {
+			CennzxSpotResult::Success(quote) => Ok(wrap_quote(quote)),
+			CennzxSpotResult::Error(e) => Err(exchange_error("Cannot exchange for requested amount.", e)),
+		}
+	}
+
+	fn sell_quote(
+		&self,
+		asset_to_sell: AssetId,
+		amount_to_sell: Balance,
+		asset_to_payout: AssetId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<TradeQuote<AssetId, WrappedBalance>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let result = api
+			.sell_quote(&at, asset_to_sell, amount_to_sell, asset_to_payout)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::Runtime.into()),
+				message: "Unable to query sell quote.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?;
+		match result {
+			CennzxSpotResult::Success(quote) => Ok(wrap_quote(quote)),
+			CennzxSpotResult::Error(e) => Err(exchange_error("Cannot exchange by requested amount.", e)),
+		}
+	}
+
+	fn fee_earnings(
+		&self,
+		account: AccountId,
+		asset_id: AssetId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<FeeEarnings<WrappedBalance>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let result = api.fee_earnings(&at, account, asset_id).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::Runtime.into()),
+			message: "Unable to query fee earnings.".into(),
 			data: Some(format!("{:?}", e).into()),
 		})?;
-		let asset = TryInto::<u64>::try_into(result.1.saturated_into::<u128>()).map_err(|e| RpcError {
-			code: ErrorCode::ServerError(Error::PriceOverflow.into()),
-			message: "Trade asset too large.".into(),
+
+		Ok(FeeEarnings {
+			core_cost: WrappedBalance::from_balance(result.core_cost),
+			asset_cost: WrappedBalance::from_balance(result.asset_cost),
+			unrealised_core_fees: WrappedBalance::from_balance(result.unrealised_core_fees),
+			unrealised_asset_fees: WrappedBalance::from_balance(result.unrealised_asset_fees),
+			realised_core_fees: WrappedBalance::from_balance(result.realised_core_fees),
+			realised_asset_fees: WrappedBalance::from_balance(result.realised_asset_fees),
+		})
+	}
+
+	fn exchanges(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<ExchangeInfo<AssetId, WrappedBalance, AccountId, BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let result = api.exchanges(&at).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::Runtime.into()),
+			message: "Unable to query exchanges.".into(),
 			data: Some(format!("{:?}", e).into()),
 		})?;
-		Ok((core, asset))
+
+		Ok(result
+			.into_iter()
+			.map(|exchange| ExchangeInfo {
+				asset_id: exchange.asset_id,
+				exchange_address: exchange.exchange_address,
+				core_reserve: WrappedBalance::from_balance(exchange.core_reserve),
+				asset_reserve: WrappedBalance::from_balance(exchange.asset_reserve),
+				total_liquidity: WrappedBalance::from_balance(exchange.total_liquidity),
+				fee_rate: exchange.fee_rate,
+				created_at: exchange.created_at,
+			})
+			.collect())
+	}
+
+	fn pool_history(
+		&self,
+		asset_id: AssetId,
+		from_block: BlockNumber,
+		to_block: BlockNumber,
+		step: BlockNumber,
+	) -> Result<Vec<PoolState<BlockNumber, <Block as BlockT>::Hash>>> {
+		if step.is_zero() || from_block > to_block {
+			return Err(RpcError::invalid_params(
+				"`step` must be greater than zero and `from_block` no later than `to_block`.",
+			));
+		}
+		let samples = ((to_block - from_block) / step)
+			.saturated_into::<u64>()
+			.saturating_add(1);
+		if samples > MAX_POOL_HISTORY_SAMPLES {
+			return Err(RpcError::invalid_params(format!(
+				"At most {} blocks can be sampled.",
+				MAX_POOL_HISTORY_SAMPLES
+			)));
+		}
+
+		let api = self.client.runtime_api();
+		let mut history = Vec::new();
+		let mut block_number = from_block;
+		loop {
+			let block_hash = self.client.hash(block_number.into()).map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::Runtime.into()),
+				message: "Unable to query block hash.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?;
+			// Blocks after the best block are not sampled
+			let block_hash = match block_hash {
+				Some(block_hash) => block_hash,
+				None => break,
+			};
+
+			// Runtimes before version 2 of the API can't list their exchanges, so their blocks are skipped
+			let at = BlockId::hash(block_hash);
+			if self.has_api_with::<AssetId, Balance, AccountId, BlockNumber, _>(&at, |v| v >= 2)? {
+				let exchanges = api.exchanges(&at).map_err(|e| RpcError {
+					code: ErrorCode::ServerError(Error::Runtime.into()),
+					message: "Unable to query exchanges.".into(),
+					data: Some(format!("{:?}", e).into()),
+				})?;
+				// An exchange without liquidity is not listed
+				let (core_reserve, asset_reserve, total_liquidity) = exchanges
+					.into_iter()
+					.find(|exchange| exchange.asset_id == asset_id)
+					.map_or((Zero::zero(), Zero::zero(), Zero::zero()), |exchange| {
+						(exchange.core_reserve, exchange.asset_reserve, exchange.total_liquidity)
+					});
+				history.push(PoolState {
+					block_number,
+					block_hash,
+					core_reserve: WrappedBalance::from_balance(core_reserve),
+					asset_reserve: WrappedBalance::from_balance(asset_reserve),
+					total_liquidity: WrappedBalance::from_balance(total_liquidity),
+				});
+			}
+
+			match block_number.checked_add(&step) {
+				Some(next) if next <= to_block => block_number = next,
+				_ => break,
+			}
+		}
+
+		Ok(history)
 	}
 }
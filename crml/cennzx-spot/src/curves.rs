@@ -0,0 +1,349 @@
+/* Copyright 2019-2020 Centrality Investments Limited
+*
+* Licensed under the LGPL, Version 3.0 (the "License");
+* you may not use this file except in compliance with the License.
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific language governing permissions and
+* limitations under the License.
+* You may obtain a copy of the License at the root of this project source code,
+* or at:
+*     https://centrality.ai/licenses/gplv3.txt
+*     https://centrality.ai/licenses/lgplv3.txt
+*/
+//!
+//! CENNZX-SPOT Pricing curves
+//!
+use crate::types::{HighPrecisionUnsigned, PRICE_SCALE};
+use codec::{Decode, Encode};
+
+/// The largest amplification coefficient a `StableSwap` curve may use
+pub const MAX_AMPLIFICATION: u32 = 1_000_000;
+
+/// The most rounds of Newton's method used to solve the `StableSwap` invariant
+const MAX_ITERATIONS: usize = 255;
+
+fn hp(value: u128) -> HighPrecisionUnsigned {
+	HighPrecisionUnsigned::from(value)
+}
+
+/// The invariant an exchange keeps between its reserves when trading.
+/// Amounts exclude trading fees, which are charged by the exchange on top.
+/// Functions return `None` if the amount can't be calculated without overflowing.
+pub trait PricingCurve {
+	/// Returns the amount of the buy asset paid out for `sell_amount`, rounded down
+	fn sell_amount_out(
+		&self,
+		sell_amount: HighPrecisionUnsigned,
+		sell_reserve: HighPrecisionUnsigned,
+		buy_reserve: HighPrecisionUnsigned,
+	) -> Option<HighPrecisionUnsigned>;
+
+	/// Returns the amount of the sell asset required for `buy_amount`, rounded down.
+	/// `buy_amount` must be less than `buy_reserve`.
+	fn buy_amount_in(
+		&self,
+		buy_amount: HighPrecisionUnsigned,
+		sell_reserve: HighPrecisionUnsigned,
+		buy_reserve: HighPrecisionUnsigned,
+	) -> Option<HighPrecisionUnsigned>;
+
+	/// Returns the marginal price of one base asset in quote asset as a `(numerator, denominator)` pair
+	fn marginal_price(
+		&self,
+		base_reserve: HighPrecisionUnsigned,
+		quote_reserve: HighPrecisionUnsigned,
+	) -> Option<(HighPrecisionUnsigned, HighPrecisionUnsigned)>;
+}
+
+/// The constant product invariant `x * y = k`
+pub struct ConstantProduct;
+
+impl PricingCurve for ConstantProduct {
+	fn sell_amount_out(
+		&self,
+		sell_amount: HighPrecisionUnsigned,
+		sell_reserve: HighPrecisionUnsigned,
+		buy_reserve: HighPrecisionUnsigned,
+	) -> Option<HighPrecisionUnsigned> {
+		buy_reserve
+			.saturating_mul(sell_amount)
+			.checked_div(sell_amount.checked_add(sell_reserve)?)
+	}
+
+	fn buy_amount_in(
+		&self,
+		buy_amount: HighPrecisionUnsigned,
+		sell_reserve: HighPrecisionUnsigned,
+		buy_reserve: HighPrecisionUnsigned,
+	) -> Option<HighPrecisionUnsigned> {
+		sell_reserve
+			.saturating_mul(buy_amount)
+			.checked_div(buy_reserve.checked_sub(buy_amount)?)
+	}
+
+	fn marginal_price(
+		&self,
+		base_reserve: HighPrecisionUnsigned,
+		quote_reserve: HighPrecisionUnsigned,
+	) -> Option<(HighPrecisionUnsigned, HighPrecisionUnsigned)> {
+		Some((quote_reserve, base_reserve))
+	}
+}
+
+/// The StableSwap invariant for two assets, `4A(x + y) + D = 4AD + D^3 / 4xy`
+/// It trades close to one for one while the reserves are balanced, with slippage growing as they move apart.
+/// A larger `amplification` (A) keeps the price flatter for longer.
+pub struct StableSwap {
+	pub amplification: u32,
+}
+
+impl StableSwap {
+	/// `A * n^n` with n = 2 assets
+	fn amplification_hp(&self) -> Option<HighPrecisionUnsigned> {
+		if self.amplification == 0 {
+			return None;
+		}
+		Some(HighPrecisionUnsigned::from(self.amplification) * hp(4))
+	}
+
+	/// Solve the invariant D for reserves `x` and `y`
+	fn invariant(&self, x: HighPrecisionUnsigned, y: HighPrecisionUnsigned) -> Option<HighPrecisionUnsigned> {
+		let sum = x.checked_add(y)?;
+		if x.is_zero() || y.is_zero() {
+			return Some(sum);
+		}
+		let ann = self.amplification_hp()?;
+		let mut d = sum;
+		for _ in 0..MAX_ITERATIONS {
+			// D^3 / 4xy
+			let d_p = d
+				.checked_mul(d)?
+				.checked_div(x * hp(2))?
+				.checked_mul(d)?
+				.checked_div(y * hp(2))?;
+			let previous = d;
+			let numerator = ann.checked_mul(sum)?.checked_add(d_p * hp(2))?.checked_mul(d)?;
+			let denominator = (ann - hp(1)).checked_mul(d)?.checked_add(d_p.checked_mul(hp(3))?)?;
+			d = numerator.checked_div(denominator)?;
+			if d.max(previous) - d.min(previous) <= hp(1) {
+				return Some(d);
+			}
+		}
+		None
+	}
+
+	/// Solve the reserve of one asset given the reserve `x` of the other and the invariant `d`
+	fn reserve_for(&self, x: HighPrecisionUnsigned, d: HighPrecisionUnsigned) -> Option<HighPrecisionUnsigned> {
+		let ann = self.amplification_hp()?;
+		let c = d
+			.checked_mul(d)?
+			.checked_div(x * hp(2))?
+			.checked_mul(d)?
+			.checked_div(ann * hp(2))?;
+		let b = x.checked_add(d / ann)?;
+		let mut y = d;
+		for _ in 0..MAX_ITERATIONS {
+			let previous = y;
+			let numerator = y.checked_mul(y)?.checked_add(c)?;
+			let denominator = (y * hp(2)).checked_add(b)?.checked_sub(d)?;
+			y = numerator.checked_div(denominator)?;
+			if y.max(previous) - y.min(previous) <= hp(1) {
+				return Some(y);
+			}
+		}
+		None
+	}
+
+	/// `D^3 / 4a^2b` scaled by `PRICE_SCALE`
+	fn price_term(
+		d: HighPrecisionUnsigned,
+		a: HighPrecisionUnsigned,
+		b: HighPrecisionUnsigned,
+	) -> Option<HighPrecisionUnsigned> {
+		d.saturating_mul(hp(PRICE_SCALE))
+			.checked_div(a * hp(2))?
+			.saturating_mul(d)
+			.checked_div(a * hp(2))?
+			.saturating_mul(d)
+			.checked_div(b)
+	}
+}
+
+impl PricingCurve for StableSwap {
+	fn sell_amount_out(
+		&self,
+		sell_amount: HighPrecisionUnsigned,
+		sell_reserve: HighPrecisionUnsigned,
+		buy_reserve: HighPrecisionUnsigned,
+	) -> Option<HighPrecisionUnsigned> {
+		let d = self.invariant(sell_reserve, buy_reserve)?;
+		let new_buy_reserve = self.reserve_for(sell_reserve.checked_add(sell_amount)?, d)?;
+		// Round against the trader
+		Some(buy_reserve.saturating_sub(new_buy_reserve).saturating_sub(hp(1)))
+	}
+
+	fn buy_amount_in(
+		&self,
+		buy_amount: HighPrecisionUnsigned,
+		sell_reserve: HighPrecisionUnsigned,
+		buy_reserve: HighPrecisionUnsigned,
+	) -> Option<HighPrecisionUnsigned> {
+		let d = self.invariant(sell_reserve, buy_reserve)?;
+		let new_sell_reserve = self.reserve_for(buy_reserve.checked_sub(buy_amount)?, d)?;
+		Some(new_sell_reserve.saturating_sub(sell_reserve))
+	}
+
+	fn marginal_price(
+		&self,
+		base_reserve: HighPrecisionUnsigned,
+		quote_reserve: HighPrecisionUnsigned,
+	) -> Option<(HighPrecisionUnsigned, HighPrecisionUnsigned)> {
+		let d = self.invariant(base_reserve, quote_reserve)?;
+		let ann_scaled = self.amplification_hp()?.checked_mul(hp(PRICE_SCALE))?;
+		// The ratio of the invariant's partial derivatives in each reserve
+		let numerator = ann_scaled.checked_add(Self::price_term(d, base_reserve, quote_reserve)?)?;
+		let denominator = ann_scaled.checked_add(Self::price_term(d, quote_reserve, base_reserve)?)?;
+		Some((numerator, denominator))
+	}
+}
+
+/// The pricing curve used by an exchange
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curve {
+	/// `x * y = k`
+	ConstantProduct,
+	/// The StableSwap invariant with the given amplification coefficient
+	StableSwap(u32),
+}
+
+impl Default for Curve {
+	fn default() -> Self {
+		Curve::ConstantProduct
+	}
+}
+
+impl Curve {
+	/// Whether the curve's parameters are within their allowed ranges
+	pub fn is_valid(&self) -> bool {
+		match self {
+			Curve::ConstantProduct => true,
+			Curve::StableSwap(amplification) => *amplification > 0 && *amplification <= MAX_AMPLIFICATION,
+		}
+	}
+}
+
+impl PricingCurve for Curve {
+	fn sell_amount_out(
+		&self,
+		sell_amount: HighPrecisionUnsigned,
+		sell_reserve: HighPrecisionUnsigned,
+		buy_reserve: HighPrecisionUnsigned,
+	) -> Option<HighPrecisionUnsigned> {
+		match self {
+			Curve::ConstantProduct => ConstantProduct.sell_amount_out(sell_amount, sell_reserve, buy_reserve),
+			Curve::StableSwap(amplification) => StableSwap {
+				amplification: *amplification,
+			}
+			.sell_amount_out(sell_amount, sell_reserve, buy_reserve),
+		}
+	}
+
+	fn buy_amount_in(
+		&self,
+		buy_amount: HighPrecisionUnsigned,
+		sell_reserve: HighPrecisionUnsigned,
+		buy_reserve: HighPrecisionUnsigned,
+	) -> Option<HighPrecisionUnsigned> {
+		match self {
+			Curve::ConstantProduct => ConstantProduct.buy_amount_in(buy_amount, sell_reserve, buy_reserve),
+			Curve::StableSwap(amplification) => StableSwap {
+				amplification: *amplification,
+			}
+			.buy_amount_in(buy_amount, sell_reserve, buy_reserve),
+		}
+	}
+
+	fn marginal_price(
+		&self,
+		base_reserve: HighPrecisionUnsigned,
+		quote_reserve: HighPrecisionUnsigned,
+	) -> Option<(HighPrecisionUnsigned, HighPrecisionUnsigned)> {
+		match self {
+			Curve::ConstantProduct => ConstantProduct.marginal_price(base_reserve, quote_reserve),
+			Curve::StableSwap(amplification) => StableSwap {
+				amplification: *amplification,
+			}
+			.marginal_price(base_reserve, quote_reserve),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn constant_product_prices() {
+		assert_eq!(
+			ConstantProduct.sell_amount_out(hp(100), hp(1_000), hp(1_000)),
+			Some(hp(90))
+		);
+		assert_eq!(
+			ConstantProduct.buy_amount_in(hp(100), hp(1_000), hp(1_000)),
+			Some(hp(111))
+		);
+		assert_eq!(
+			ConstantProduct.marginal_price(hp(1_000), hp(2_000)),
+			Some((hp(2_000), hp(1_000)))
+		);
+	}
+
+	#[test]
+	fn stable_swap_has_less_slippage_than_constant_product() {
+		let curve = StableSwap { amplification: 100 };
+		let out = curve
+			.sell_amount_out(hp(100_000), hp(1_000_000), hp(1_000_000))
+			.unwrap();
+		assert!(
+			out > ConstantProduct
+				.sell_amount_out(hp(100_000), hp(1_000_000), hp(1_000_000))
+				.unwrap()
+		);
+		assert!(out < hp(100_000));
+		assert!(out > hp(99_900));
+
+		let amount_in = curve.buy_amount_in(hp(100_000), hp(1_000_000), hp(1_000_000)).unwrap();
+		assert!(
+			amount_in
+				< ConstantProduct
+					.buy_amount_in(hp(100_000), hp(1_000_000), hp(1_000_000))
+					.unwrap()
+		);
+		assert!(amount_in > hp(100_000));
+		assert!(amount_in < hp(100_100));
+	}
+
+	#[test]
+	fn stable_swap_marginal_price() {
+		let curve = StableSwap { amplification: 100 };
+		// Balanced reserves trade one for one
+		let (numerator, denominator) = curve.marginal_price(hp(1_000_000), hp(1_000_000)).unwrap();
+		assert_eq!(numerator, denominator);
+
+		// The scarce asset is worth more
+		let (numerator, denominator) = curve.marginal_price(hp(500_000), hp(1_500_000)).unwrap();
+		assert!(numerator > denominator);
+	}
+
+	#[test]
+	fn stable_swap_requires_amplification() {
+		let curve = StableSwap { amplification: 0 };
+		assert_eq!(curve.sell_amount_out(hp(100), hp(1_000), hp(1_000)), None);
+		assert!(!Curve::StableSwap(0).is_valid());
+		assert!(!Curve::StableSwap(MAX_AMPLIFICATION + 1).is_valid());
+		assert!(Curve::StableSwap(MAX_AMPLIFICATION).is_valid());
+		assert!(Curve::ConstantProduct.is_valid());
+	}
+}
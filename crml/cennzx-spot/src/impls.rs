@@ -17,7 +17,10 @@
 //!
 use super::Trait;
 use crate::{Error, Module};
-use cennznet_primitives::{traits::BuyFeeAsset, types::FeeExchange};
+use cennznet_primitives::{
+	traits::BuyFeeAsset,
+	types::{FeeExchange, FeePaymentError},
+};
 use frame_support::{dispatch::DispatchError, StorageMap};
 use sp_core::crypto::{UncheckedFrom, UncheckedInto};
 use sp_runtime::traits::Hash;
@@ -78,6 +81,69 @@ impl<T: Trait> BuyFeeAsset for Module<T> {
 			exchange_op.max_payment(),
 		)
 	}
+
+	/// Use the CENNZX-Spot exchange to price buying fee asset
+	fn buy_fee_asset_price(
+		amount: Self::Balance,
+		exchange_op: &Self::FeeExchange,
+	) -> Result<Self::Balance, DispatchError> {
+		let fee_exchange_asset_id = exchange_op.asset_id();
+		ensure!(
+			<pallet_generic_asset::TotalIssuance<T>>::contains_key(&fee_exchange_asset_id),
+			Error::<T>::InvalidAssetId,
+		);
+
+		let fee_asset_id = <pallet_generic_asset::Module<T>>::spending_asset_id();
+		let amount_to_sell = Self::get_buy_price(fee_asset_id, amount, fee_exchange_asset_id)?;
+		ensure!(
+			amount_to_sell <= exchange_op.max_payment(),
+			Error::<T>::MaximumSellRequirementNotMet
+		);
+
+		Ok(amount_to_sell)
+	}
+
+	/// Classify CENNZX-Spot errors buying fee asset by their `Error` variant
+	fn fee_payment_error(error: DispatchError) -> FeePaymentError {
+		let reasons: [(DispatchError, FeePaymentError); 11] = [
+			(
+				Error::<T>::InsufficientBalance.into(),
+				FeePaymentError::InsufficientBalance,
+			),
+			(
+				Error::<T>::InsufficientExchangePoolReserve.into(),
+				FeePaymentError::InsufficientExchangePoolReserve,
+			),
+			(Error::<T>::CannotTradeZero.into(), FeePaymentError::CannotTradeZero),
+			(
+				Error::<T>::MinimumBuyRequirementNotMet.into(),
+				FeePaymentError::MinimumBuyRequirementNotMet,
+			),
+			(
+				Error::<T>::AssetCannotSwapForItself.into(),
+				FeePaymentError::AssetCannotSwapForItself,
+			),
+			(
+				Error::<T>::MaximumSellRequirementNotMet.into(),
+				FeePaymentError::MaximumSellRequirementNotMet,
+			),
+			(Error::<T>::InvalidAssetId.into(), FeePaymentError::InvalidAssetId),
+			(
+				Error::<T>::InsufficientLiquidity.into(),
+				FeePaymentError::InsufficientLiquidity,
+			),
+			(Error::<T>::EmptyExchangePool.into(), FeePaymentError::EmptyExchangePool),
+			(Error::<T>::ExchangePaused.into(), FeePaymentError::ExchangePaused),
+			(
+				Error::<T>::PriceMovementLimitExceeded.into(),
+				FeePaymentError::PriceMovementLimitExceeded,
+			),
+		];
+		reasons
+			.iter()
+			.find(|(reason_error, _)| *reason_error == error)
+			.map_or(FeePaymentError::Unknown, |(_, reason)| *reason)
+	}
 }
 
 #[cfg(test)]
@@ -86,7 +152,7 @@ pub(crate) mod impl_tests {
 	use crate::{
 		mock::{self, FEE_ASSET_ID, TRADE_ASSET_A_ID},
 		mock::{CennzXSpot, ExtBuilder, Test},
-		Error,
+		Curve, Error,
 	};
 	use frame_support::traits::Currency;
 	use sp_core::H256;
@@ -165,6 +231,146 @@ pub(crate) mod impl_tests {
 		});
 	}
 
+	#[test]
+	fn buy_fee_asset_price() {
+		ExtBuilder::default().build().execute_with(|| {
+			with_exchange!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+			with_exchange!(CoreAssetCurrency => 10_000, FeeAssetCurrency => 10_000);
+
+			// Same price as `buy_fee_asset` pays, without trading
+			assert_ok!(
+				<CennzXSpot as BuyFeeAsset>::buy_fee_asset_price(
+					510,
+					&TestFeeExchange::new_v1(TRADE_ASSET_A_ID, 2_000_000)
+				),
+				571
+			);
+			assert_exchange_balance_eq!(
+				CoreAssetCurrency => 10_000,
+				TradeAssetCurrencyA => 10_000
+			);
+
+			assert_err!(
+				<CennzXSpot as BuyFeeAsset>::buy_fee_asset_price(510, &TestFeeExchange::new_v1(TRADE_ASSET_A_ID, 570)),
+				Error::<Test>::MaximumSellRequirementNotMet
+			);
+			assert_err!(
+				<CennzXSpot as BuyFeeAsset>::buy_fee_asset_price(510, &TestFeeExchange::new_v1(100, 2_000_000)),
+				Error::<Test>::InvalidAssetId
+			);
+		});
+	}
+
+	#[test]
+	fn fee_payment_error() {
+		ExtBuilder::default().build().execute_with(|| {
+			let user = with_account!(CoreAssetCurrency => 0, TradeAssetCurrencyA => 10);
+
+			let error = <CennzXSpot as BuyFeeAsset>::buy_fee_asset_price(510, &TestFeeExchange::new_v1(100, 2_000_000))
+				.unwrap_err();
+			assert_eq!(
+				<CennzXSpot as BuyFeeAsset>::fee_payment_error(error),
+				FeePaymentError::InvalidAssetId
+			);
+			assert_eq!(
+				<CennzXSpot as BuyFeeAsset>::fee_payment_error(Error::<Test>::MaximumSellRequirementNotMet.into()),
+				FeePaymentError::MaximumSellRequirementNotMet
+			);
+
+			let error = <CennzXSpot as BuyFeeAsset>::buy_fee_asset(
+				&user,
+				51,
+				&TestFeeExchange::new_v1(TRADE_ASSET_A_ID, 2_000_000),
+			)
+			.unwrap_err();
+			assert_eq!(error, Error::<Test>::EmptyExchangePool.into());
+			assert_eq!(
+				<CennzXSpot as BuyFeeAsset>::fee_payment_error(error),
+				FeePaymentError::EmptyExchangePool
+			);
+			assert_eq!(
+				<CennzXSpot as BuyFeeAsset>::fee_payment_error(Error::<Test>::ExchangePaused.into()),
+				FeePaymentError::ExchangePaused
+			);
+			assert_eq!(
+				<CennzXSpot as BuyFeeAsset>::fee_payment_error(Error::<Test>::PriceMovementLimitExceeded.into()),
+				FeePaymentError::PriceMovementLimitExceeded
+			);
+
+			// Errors other than fee payment errors are unknown
+			assert_eq!(
+				<CennzXSpot as BuyFeeAsset>::fee_payment_error(Error::<Test>::Overflow.into()),
+				FeePaymentError::Unknown
+			);
+			assert_eq!(
+				<CennzXSpot as BuyFeeAsset>::fee_payment_error(DispatchError::Other("InsufficientBalance")),
+				FeePaymentError::Unknown
+			);
+		});
+	}
+
+	#[test]
+	fn buy_fee_asset_with_stable_swap_exchanges() {
+		ExtBuilder::default().build().execute_with(|| {
+			with_exchange!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+			with_exchange!(CoreAssetCurrency => 10_000, FeeAssetCurrency => 10_000);
+			let user = with_account!(CoreAssetCurrency => 0, TradeAssetCurrencyA => 1_000);
+			assert_ok!(CennzXSpot::set_exchange_curve(
+				mock::Origin::ROOT,
+				TRADE_ASSET_A_ID,
+				Curve::StableSwap(100)
+			));
+			assert_ok!(CennzXSpot::set_exchange_curve(
+				mock::Origin::ROOT,
+				FEE_ASSET_ID,
+				Curve::StableSwap(100)
+			));
+
+			// 571 with constant product exchanges
+			assert_ok!(
+				<CennzXSpot as BuyFeeAsset>::buy_fee_asset(
+					&user,
+					510,
+					&TestFeeExchange::new_v1(TRADE_ASSET_A_ID, 2_000_000)
+				),
+				514
+			);
+
+			assert_exchange_balance_eq!(
+				CoreAssetCurrency => 10_000 - 512,
+				TradeAssetCurrencyA => 10_000 + 514
+			);
+			assert_exchange_balance_eq!(
+				CoreAssetCurrency => 10_000 + 512,
+				FeeAssetCurrency => 10_000 - 510
+			);
+			assert_balance_eq!(user, FeeAssetCurrency => 510);
+			assert_balance_eq!(user, TradeAssetCurrencyA => 1_000 - 514);
+		});
+	}
+
+	#[test]
+	fn buy_fee_asset_from_paused_exchange() {
+		ExtBuilder::default().build().execute_with(|| {
+			with_exchange!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+			with_exchange!(CoreAssetCurrency => 10_000, FeeAssetCurrency => 10_000);
+			let user = with_account!(CoreAssetCurrency => 0, TradeAssetCurrencyA => 1_000);
+			assert_ok!(CennzXSpot::pause_exchange(mock::Origin::ROOT, TRADE_ASSET_A_ID));
+
+			assert_err!(
+				<CennzXSpot as BuyFeeAsset>::buy_fee_asset(
+					&user,
+					510,
+					&TestFeeExchange::new_v1(TRADE_ASSET_A_ID, 2_000_000)
+				),
+				Error::<Test>::ExchangePaused
+			);
+
+			assert_balance_eq!(user, FeeAssetCurrency => 0);
+			assert_balance_eq!(user, TradeAssetCurrencyA => 1_000);
+		});
+	}
+
 	#[test]
 	fn buy_fee_asset_insufficient_trade_asset() {
 		ExtBuilder::default().build().execute_with(|| {
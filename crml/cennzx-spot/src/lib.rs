@@ -22,21 +22,32 @@ mod mock;
 #[macro_use]
 mod tests;
 
+mod curves;
 mod impls;
 mod types;
+pub use curves::{ConstantProduct, Curve, PricingCurve, StableSwap, MAX_AMPLIFICATION};
 pub use impls::{ExchangeAddressFor, ExchangeAddressGenerator};
-pub use types::{FeeRate, HighPrecisionUnsigned, LowPrecisionUnsigned, PerMillion, PerThousand};
+pub use types::{
+	FeeRate, HighPrecisionUnsigned, LimitOrder, LiquidityPosition, LowPrecisionUnsigned, PerMillion, PerThousand,
+	PriceObservation, TradeInstruction, PRICE_SCALE,
+};
 
 #[macro_use]
 extern crate frame_support;
 
 use core::convert::TryFrom;
-use frame_support::{dispatch::Dispatchable, sp_runtime::traits::Saturating, Parameter, StorageDoubleMap};
-use frame_system::{ensure_root, ensure_signed};
+use frame_support::{
+	dispatch::Dispatchable,
+	sp_runtime::traits::Saturating,
+	traits::{EnsureOrigin, Get, WithdrawReason},
+	weights::{DispatchClass, FunctionOf, GetDispatchInfo, SimpleDispatchInfo, Weight},
+	Parameter, StorageDoubleMap,
+};
+use frame_system::{ensure_root, ensure_signed, RawOrigin};
 use pallet_generic_asset;
-use sp_runtime::traits::{One, Zero};
+use sp_runtime::traits::{CheckedAdd, CheckedSub, One, SaturatedConversion, Zero};
 use sp_runtime::{DispatchError, DispatchResult};
-use sp_std::prelude::*;
+use sp_std::{collections::btree_map::BTreeMap, prelude::*};
 
 // (core_asset_id, asset_id)
 pub type ExchangeKey<T> = (
@@ -44,6 +55,31 @@ pub type ExchangeKey<T> = (
 	<T as pallet_generic_asset::Trait>::AssetId,
 );
 
+/// Identifies a limit order
+pub type OrderId = u64;
+
+/// The weight of checking a limit order for a fill or expiry, filling it with a trade at worst
+pub const LIMIT_ORDER_WEIGHT: Weight = 500_000;
+
+/// The weight of checking and making each trade in a batch of trades
+pub const BATCH_TRADE_WEIGHT: Weight = 200_000;
+
+/// The weight of a flash swap, excluding the call it dispatches
+pub const FLASH_SWAP_WEIGHT: Weight = 300_000;
+
+pub type LimitOrderOf<T> = LimitOrder<
+	<T as frame_system::Trait>::AccountId,
+	<T as pallet_generic_asset::Trait>::AssetId,
+	<T as pallet_generic_asset::Trait>::Balance,
+	<T as frame_system::Trait>::BlockNumber,
+>;
+
+pub type TradeInstructionOf<T> = TradeInstruction<
+	<T as frame_system::Trait>::AccountId,
+	<T as pallet_generic_asset::Trait>::AssetId,
+	<T as pallet_generic_asset::Trait>::Balance,
+>;
+
 /// Represents the value of an amount of liquidity in an exchange
 /// Liqudity is always traded for a combination of `core_asset` and `trade_asset`
 ///
@@ -66,13 +102,99 @@ pub struct LiquidityPrice<Balance> {
 	pub asset: Balance,
 }
 
+/// Represents a single sided deposit of liquidity into an exchange
+/// Part of the deposit is sold into the exchange and the rest is added with the proceeds
+///
+/// `swap_amount` represents the balance of the deposited asset sold into the exchange
+/// `swap_proceeds` represents the balance of the other asset bought by the sale
+/// `core` represents the balance of `core_asset` added to the exchange
+/// `asset` represents the balance of `trade_asset` added to the exchange
+/// `liquidity` represents the volume of liquidity minted
+pub struct SingleSidedDeposit<Balance> {
+	pub swap_amount: Balance,
+	pub swap_proceeds: Balance,
+	pub core: Balance,
+	pub asset: Balance,
+	pub liquidity: Balance,
+}
+
+/// Represents a quote for a trade through the exchanges
+/// Prices are given as the price of one asset bought in the asset sold, scaled by `PRICE_SCALE`
+///
+/// `route` represents the assets traded through, from the asset sold to the asset bought
+/// `amount_in` represents the balance of the asset sold
+/// `amount_out` represents the balance of the asset bought
+/// `fee` represents the trading fees paid, in the asset sold
+/// `spot_price_before` represents the price before the trade
+/// `spot_price_after` represents the price after the trade
+/// `execution_price` represents the average price paid, including fees
+/// `price_impact` represents how much the average price paid, excluding fees, exceeds `spot_price_before`
+#[derive(Debug, PartialEq)]
+pub struct TradeQuote<AssetId, Balance> {
+	pub route: Vec<AssetId>,
+	pub amount_in: Balance,
+	pub amount_out: Balance,
+	pub fee: Balance,
+	pub spot_price_before: Balance,
+	pub spot_price_after: Balance,
+	pub execution_price: Balance,
+	pub price_impact: FeeRate<PerMillion>,
+}
+
+/// Represents the time weighted average price of an exchange, scaled by `PRICE_SCALE`
+/// Prices are always given between `core_asset` and `trade_asset`
+///
+/// `core` represents the price of one `core_asset` in `trade_asset`
+/// `asset` represents the price of one `trade_asset` in `core_asset`
+#[derive(Debug, PartialEq)]
+pub struct ExchangePrice<Balance> {
+	pub core: Balance,
+	pub asset: Balance,
+}
+
+/// Represents the state of an exchange pool
+///
+/// `asset_id` represents the trade asset of the exchange
+/// `exchange_address` represents the account holding the exchange reserves
+/// `core_reserve` represents the balance of `core_asset` held by the exchange
+/// `asset_reserve` represents the balance of `trade_asset` held by the exchange
+/// `total_liquidity` represents the liquidity held by all investors in the exchange
+/// `fee_rate` represents the trading fee rate of the exchange
+/// `created_at` represents the block the exchange was created in
+pub struct ExchangeInfo<AccountId, AssetId, Balance, BlockNumber> {
+	pub asset_id: AssetId,
+	pub exchange_address: AccountId,
+	pub core_reserve: Balance,
+	pub asset_reserve: Balance,
+	pub total_liquidity: Balance,
+	pub fee_rate: FeeRate<PerMillion>,
+	pub created_at: BlockNumber,
+}
+
 pub trait Trait: frame_system::Trait + pallet_generic_asset::Trait {
-	type Call: Parameter + Dispatchable<Origin = <Self as frame_system::Trait>::Origin>;
+	type Call: Parameter + Dispatchable<Origin = <Self as frame_system::Trait>::Origin> + GetDispatchInfo;
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
 	/// A function type to get an exchange address given the asset ID pair.
 	type ExchangeAddressGenerator: ExchangeAddressFor<Self::AssetId, Self::AccountId>;
 	type BalanceToUnsignedInt: From<<Self as pallet_generic_asset::Trait>::Balance> + Into<LowPrecisionUnsigned>;
 	type UnsignedIntToBalance: From<LowPrecisionUnsigned> + Into<<Self as pallet_generic_asset::Trait>::Balance>;
+	/// The maximum number of price observations kept for each exchange by the price oracle.
+	/// It bounds the longest window a time weighted average price can be queried over.
+	type MaxPriceObservations: Get<u32>;
+	/// The maximum number of limit orders open at once.
+	type MaxOpenOrders: Get<u32>;
+	/// The maximum number of limit orders open at once for each account.
+	type MaxOpenOrdersPerAccount: Get<u32>;
+	/// The maximum number of limit orders checked for a fill or expiry each block.
+	type MaxOrdersPerBlock: Get<u32>;
+	/// The maximum number of trades in a batch of trades.
+	type MaxBatchTrades: Get<u32>;
+	/// The account receiving the protocol share of trading fees.
+	type ProtocolFeeRecipient: Get<Self::AccountId>;
+	/// The origin which may pause and resume trading on an exchange, in addition to root.
+	type PauseOrigin: EnsureOrigin<Self::Origin>;
+	/// The liquidity locked in an exchange forever by its first deposit, so it can never be emptied.
+	type MinimumLiquidity: Get<<Self as pallet_generic_asset::Trait>::Balance>;
 }
 
 decl_error! {
@@ -95,6 +217,27 @@ decl_error! {
 		InvalidAssetId,
 		Overflow,
 		DivideByZero,
+		InvalidExchangePath,
+		TradeTransferFailed,
+		InsufficientPriceHistory,
+		InvalidPriceWindow,
+		TradeExpired,
+		TooManyOpenOrders,
+		OrderNotFound,
+		NotOrderOwner,
+		InvalidProtocolFeeShare,
+		InvalidBatchSize,
+		ExchangePaused,
+		PriceMovementLimitExceeded,
+		InsufficientInitialLiquidity,
+		MinimumReserveRequirementNotMet,
+		InvalidPricingCurve,
+		TooManyOpenOrdersForAccount,
+		UnsupportedPricingCurve,
+		ExchangeNotEmpty,
+		InvalidFeeRate,
+		FlashSwapInProgress,
+		FlashSwapInvariantBroken,
 	}
 }
 
@@ -196,14 +339,14 @@ decl_module! {
 			);
 			let exchange_key = (core_asset_id, asset_id);
 			let total_liquidity = <TotalLiquidity<T>>::get(&exchange_key);
-			let exchange_address = T::ExchangeAddressGenerator::exchange_address_for(asset_id);
-			let core_asset_reserve = <pallet_generic_asset::Module<T>>::free_balance(&core_asset_id, &exchange_address);
+			let (core_asset_reserve, trade_asset_reserve) = Self::get_exchange_reserves(&asset_id);
 
 			let (trade_asset_amount, liquidity_minted) = if total_liquidity.is_zero() || core_asset_reserve.is_zero() {
-				// new exchange pool
-				(max_asset_amount, core_amount)
+				// new exchange pool, the first deposit pays for the liquidity locked in the exchange
+				let locked_liquidity = if total_liquidity.is_zero() { T::MinimumLiquidity::get() } else { Zero::zero() };
+				ensure!(core_amount > locked_liquidity, Error::<T>::InsufficientInitialLiquidity);
+				(max_asset_amount, core_amount - locked_liquidity)
 			} else {
-				let trade_asset_reserve = <pallet_generic_asset::Module<T>>::free_balance(&asset_id, &exchange_address);
 				let trade_asset_amount = core_amount * trade_asset_reserve / core_asset_reserve + One::one();
 				let liquidity_minted = core_amount * total_liquidity / core_asset_reserve;
 
@@ -218,11 +361,7 @@ decl_module! {
 				Error::<T>::MaximumTradeAssetRequirementNotMet
 			);
 
-			<pallet_generic_asset::Module<T>>::make_transfer(&core_asset_id, &from_account, &exchange_address, core_amount)?;
-			<pallet_generic_asset::Module<T>>::make_transfer(&asset_id, &from_account, &exchange_address, trade_asset_amount)?;
-
-			Self::mint_liquidity(&exchange_key, &from_account, liquidity_minted);
-			Self::deposit_event(RawEvent::AddLiquidity(from_account, core_amount, asset_id, trade_asset_amount));
+			Self::deposit_liquidity(&from_account, asset_id, core_amount, trade_asset_amount, liquidity_minted)?;
 		}
 
 		/// Burn exchange assets to withdraw core asset and trade asset at current ratio
@@ -263,155 +402,1290 @@ decl_module! {
 				withdraw_value.asset >= min_asset_withdraw,
 				Error::<T>::MinimumTradeAssetRequirementNotMet
 			);
-			let exchange_address = T::ExchangeAddressGenerator::exchange_address_for(asset_id);
-			<pallet_generic_asset::Module<T>>::make_transfer(&core_asset_id, &exchange_address, &from_account, withdraw_value.core)?;
-			<pallet_generic_asset::Module<T>>::make_transfer(&asset_id, &exchange_address, &from_account, withdraw_value.asset)?;
-			Self::burn_liquidity(&exchange_key, &from_account, liquidity_to_withdraw);
-			Self::deposit_event(RawEvent::RemoveLiquidity(from_account, withdraw_value.core, asset_id, withdraw_value.asset));
-			Ok(())
+			let (core_reserve, asset_reserve) = Self::get_exchange_reserves(&asset_id);
+			Self::ensure_minimum_reserve(
+				core_reserve.saturating_sub(withdraw_value.core),
+				asset_reserve.saturating_sub(withdraw_value.asset),
+			)?;
+			Self::withdraw_liquidity(&from_account, asset_id, liquidity_to_withdraw, &withdraw_value)
 		}
 
 		/// Set the spot exchange wide fee rate (root only)
 		pub fn set_fee_rate(origin, new_fee_rate: FeeRate<PerMillion>) -> DispatchResult {
 			ensure_root(origin)?;
+			Self::ensure_valid_fee_rate(new_fee_rate)?;
 			DefaultFeeRate::mutate(|fee_rate| *fee_rate = new_fee_rate);
 			Ok(())
 		}
-	}
-}
 
-decl_event!(
-	pub enum Event<T>
-	where
-		<T as frame_system::Trait>::AccountId,
-		<T as pallet_generic_asset::Trait>::AssetId,
-		<T as pallet_generic_asset::Trait>::Balance
-	{
-		/// Provider, core asset amount, trade asset id, trade asset amount
-		AddLiquidity(AccountId, Balance, AssetId, Balance),
-		/// Provider, core asset amount, trade asset id, trade asset amount
-		RemoveLiquidity(AccountId, Balance, AssetId, Balance),
-		/// AssetSold, AssetBought, Buyer, SoldAmount, BoughtAmount
-		AssetPurchase(AssetId, AssetId, AccountId, Balance, Balance),
-	}
-);
+		//
+		// Calls added since are appended below, so the call indices above stay the same
+		//
 
-decl_storage! {
-	trait Store for Module<T: Trait> as CennzxSpot {
-		/// AssetId of Core Asset
-		pub CoreAssetId get(core_asset_id) config(): T::AssetId;
-		/// Default Trading fee rate
-		pub DefaultFeeRate get(fee_rate) config(): FeeRate<PerMillion>;
-		/// Total liquidity holdings of all investers in an exchange.
-		/// ie/ total_liquidity(exchange) == sum(liquidity_balance(exchange, user)) at all times
-		pub TotalLiquidity get(total_liquidity): map hasher(twox_64_concat) ExchangeKey<T> => T::Balance;
+		/// Buy `asset_to_buy` with `asset_to_sell`, failing after block `valid_until`.
+		/// User specifies an exact `buy_amount` and a `maximum_sell` amount.
+		///
+		/// `recipient` - Account to receive `buy_amount`, defaults to `origin` if None
+		/// `asset_to_sell` - asset ID to sell
+		/// `asset_to_buy` - asset ID to buy
+		/// `buy_amount` - The amount `asset_to_buy` to purchase
+		/// `maximum_sell` - Maximum `asset_to_sell` to pay
+		/// `valid_until` - The last block the trade may execute in
+		pub fn buy_asset_with_deadline(
+			origin,
+			recipient: Option<T::AccountId>,
+			#[compact] asset_to_sell: T::AssetId,
+			#[compact] asset_to_buy: T::AssetId,
+			#[compact] buy_amount: T::Balance,
+			#[compact] maximum_sell: T::Balance,
+			valid_until: T::BlockNumber
+		) -> DispatchResult {
+			let trader = ensure_signed(origin)?;
+			Self::ensure_not_expired(Some(valid_until))?;
+			let _ = Self::execute_buy(
+				&trader,
+				&recipient.unwrap_or_else(|| trader.clone()),
+				&asset_to_sell,
+				&asset_to_buy,
+				buy_amount,
+				maximum_sell,
+			)?;
+			Ok(())
+		}
 
-		/// Liquidity holdings of a user in an exchange pool.
-		/// Key: `(core_asset_id, trade_asset_id), account_id`
-		pub LiquidityBalance get(liquidity_balance): double_map hasher(twox_64_concat) ExchangeKey<T>, hasher(blake2_128_concat) T::AccountId => T::Balance;
-	}
-}
+		/// Sell `asset_to_sell` for `asset_to_buy`, failing after block `valid_until`.
+		/// User specifies an exact `sell_amount` and a `minimum_buy` amount.
+		///
+		/// `recipient` - Account to receive `buy_amount`, defaults to `origin` if None
+		/// `asset_to_sell` - asset ID to sell
+		/// `asset_to_buy` - asset ID to buy
+		/// `sell_amount` - The amount `asset_to_buy` to purchase
+		/// `minimum_buy` - Maximum `asset_to_sell` to pay
+		/// `valid_until` - The last block the trade may execute in
+		pub fn sell_asset_with_deadline(
+			origin,
+			recipient: Option<T::AccountId>,
+			#[compact] asset_to_sell: T::AssetId,
+			#[compact] asset_to_buy: T::AssetId,
+			#[compact] sell_amount: T::Balance,
+			#[compact] minimum_buy: T::Balance,
+			valid_until: T::BlockNumber
+		) -> DispatchResult {
+			let trader = ensure_signed(origin)?;
+			Self::ensure_not_expired(Some(valid_until))?;
+			let _ = Self::execute_sell(
+				&trader,
+				&recipient.unwrap_or_else(|| trader.clone()),
+				&asset_to_sell,
+				&asset_to_buy,
+				sell_amount,
+				minimum_buy
+			)?;
+			Ok(())
+		}
 
-// The main implementation block for the module.
-impl<T: Trait> Module<T> {
-	//
-	// Liquidity
-	//
+		/// Buy the last asset in `path` with the first asset in `path`, trading through each exchange along `path`.
+		/// User specifies an exact `buy_amount` and a `maximum_sell` amount for the whole route.
+		///
+		/// `recipient` - Account to receive `buy_amount`, defaults to `origin` if None
+		/// `path` - The assets to trade through e.g. `[asset_to_sell, core_asset, asset_to_buy]`
+		/// `buy_amount` - The amount of the last asset in `path` to purchase
+		/// `maximum_sell` - Maximum amount of the first asset in `path` to pay
+		/// `valid_until` - The last block the trade may execute in, never expires if None
+		pub fn buy_asset_via_path(
+			origin,
+			recipient: Option<T::AccountId>,
+			path: Vec<T::AssetId>,
+			#[compact] buy_amount: T::Balance,
+			#[compact] maximum_sell: T::Balance,
+			valid_until: Option<T::BlockNumber>
+		) -> DispatchResult {
+			let trader = ensure_signed(origin)?;
+			Self::ensure_not_expired(valid_until)?;
+			let _ = Self::execute_buy_via_path(
+				&trader,
+				&recipient.unwrap_or_else(|| trader.clone()),
+				&path,
+				buy_amount,
+				maximum_sell,
+			)?;
+			Ok(())
+		}
 
-	/// Mint liquidity holdings for a user in a specified exchange
-	fn mint_liquidity(exchange_key: &ExchangeKey<T>, who: &T::AccountId, increase: T::Balance) {
-		let balance = <LiquidityBalance<T>>::get(exchange_key, who);
-		let new_balance = balance.saturating_add(increase);
-		<LiquidityBalance<T>>::insert(exchange_key, who, new_balance);
-		<TotalLiquidity<T>>::mutate(exchange_key, |balance| *balance = balance.saturating_add(increase));
-	}
+		/// Sell the first asset in `path` for the last asset in `path`, trading through each exchange along `path`.
+		/// User specifies an exact `sell_amount` and a `minimum_buy` amount for the whole route.
+		///
+		/// `recipient` - Account to receive the bought asset, defaults to `origin` if None
+		/// `path` - The assets to trade through e.g. `[asset_to_sell, core_asset, asset_to_buy]`
+		/// `sell_amount` - The amount of the first asset in `path` to sell
+		/// `minimum_buy` - Minimum amount of the last asset in `path` to receive
+		/// `valid_until` - The last block the trade may execute in, never expires if None
+		pub fn sell_asset_via_path(
+			origin,
+			recipient: Option<T::AccountId>,
+			path: Vec<T::AssetId>,
+			#[compact] sell_amount: T::Balance,
+			#[compact] minimum_buy: T::Balance,
+			valid_until: Option<T::BlockNumber>
+		) -> DispatchResult {
+			let trader = ensure_signed(origin)?;
+			Self::ensure_not_expired(valid_until)?;
+			let _ = Self::execute_sell_via_path(
+				&trader,
+				&recipient.unwrap_or_else(|| trader.clone()),
+				&path,
+				sell_amount,
+				minimum_buy,
+			)?;
+			Ok(())
+		}
 
-	/// Burn liquidity holdings from a user in a specified exchange
-	fn burn_liquidity(exchange_key: &ExchangeKey<T>, who: &T::AccountId, decrease: T::Balance) {
-		let balance = <LiquidityBalance<T>>::get(exchange_key, who);
-		let decrease = decrease.min(balance);
-		let new_balance = balance - decrease;
-		<LiquidityBalance<T>>::insert(exchange_key, who, new_balance);
-		<TotalLiquidity<T>>::mutate(exchange_key, |balance| *balance = balance.saturating_sub(decrease));
-	}
+		/// Make several buys and sells, all or nothing.
+		/// Every trade is priced and checked against its limit, the exchanges and the trader's balances, at the
+		/// prices and balances left by the trades before it, before any trade is made. The trades are then made
+		/// at exactly the amounts checked.
+		///
+		/// `trades` - The trades to make, in order
+		/// `valid_until` - The last block the trades may execute in, never expires if None
+		#[weight = FunctionOf(
+			|(trades, _): (&Vec<TradeInstructionOf<T>>, &Option<T::BlockNumber>)|
+				BATCH_TRADE_WEIGHT.saturating_mul(trades.len() as Weight),
+			DispatchClass::Normal,
+			true
+		)]
+		pub fn batch_trade(
+			origin,
+			trades: Vec<TradeInstructionOf<T>>,
+			valid_until: Option<T::BlockNumber>
+		) -> DispatchResult {
+			let trader = ensure_signed(origin)?;
+			Self::ensure_not_expired(valid_until)?;
+			ensure!(
+				!trades.is_empty() && trades.len() <= T::MaxBatchTrades::get() as usize,
+				Error::<T>::InvalidBatchSize
+			);
+			let simulated_trades = Self::simulate_batch_trade(&trader, &trades)?;
 
-	/// The Price of Liquidity for a particular `asset_id` exchange
-	///
-	/// The price includes
-	///   * a required amount of core asset
-	///   * a required amount of `asset_id`
-	///
-	/// Note: if the exchange does not exist, the cost in `asset` is 1, because the investor
-	///       determines the exchange rate
-	pub fn liquidity_price(asset_id: T::AssetId, liquidity_to_buy: T::Balance) -> LiquidityPrice<T::Balance> {
-		let core_asset_id = Self::core_asset_id();
-		let exchange_key = (core_asset_id, asset_id);
-		let total_liquidity = <TotalLiquidity<T>>::get(&exchange_key);
-		let exchange_address = T::ExchangeAddressGenerator::exchange_address_for(asset_id);
-		let core_reserve = <pallet_generic_asset::Module<T>>::free_balance(&core_asset_id, &exchange_address);
+			let mut results = Vec::with_capacity(simulated_trades.len());
+			for (recipient, path, amounts) in simulated_trades {
+				Self::execute_trade_amounts(&trader, &recipient, &path, &amounts)?;
+				results.push((amounts[0], amounts[amounts.len() - 1]));
+			}
+			Self::deposit_event(RawEvent::BatchTradeExecuted(trader, results));
+			Ok(())
+		}
 
-		let (core_amount, asset_amount) = if total_liquidity.is_zero() || core_reserve.is_zero() {
-			// empty exchange pool
-			(liquidity_to_buy, One::one())
-		} else {
-			let core_amount = liquidity_to_buy * core_reserve / total_liquidity;
-			let asset_reserve = <pallet_generic_asset::Module<T>>::free_balance(&asset_id, &exchange_address);
-			let asset_amount = core_amount * asset_reserve / core_reserve + One::one();
+		/// Place a limit order to sell `sell_amount` of `asset_to_sell` for at least `minimum_buy` of `asset_to_buy`.
+		/// The order is filled at the end of the first block the exchange price allows it, and expires after block
+		/// `valid_until`. `sell_amount` is reserved from the trader while the order is open.
+		/// An account may have at most `MaxOpenOrdersPerAccount` orders open at once.
+		///
+		/// `asset_to_sell` - asset ID to sell
+		/// `asset_to_buy` - asset ID to buy
+		/// `sell_amount` - The amount of `asset_to_sell` to sell
+		/// `minimum_buy` - Minimum `asset_to_buy` to receive
+		/// `valid_until` - The last block the order may be filled in
+		#[weight = SimpleDispatchInfo::FixedNormal(LIMIT_ORDER_WEIGHT)]
+		pub fn place_limit_order(
+			origin,
+			#[compact] asset_to_sell: T::AssetId,
+			#[compact] asset_to_buy: T::AssetId,
+			#[compact] sell_amount: T::Balance,
+			#[compact] minimum_buy: T::Balance,
+			valid_until: T::BlockNumber
+		) -> DispatchResult {
+			let trader = ensure_signed(origin)?;
+			ensure!(asset_to_sell != asset_to_buy, Error::<T>::AssetCannotSwapForItself);
+			ensure!(!sell_amount.is_zero() && !minimum_buy.is_zero(), Error::<T>::CannotTradeZero);
+			Self::ensure_not_expired(Some(valid_until))?;
 
-			(core_amount, asset_amount)
-		};
-		LiquidityPrice {
-			core: core_amount,
-			asset: asset_amount,
+			let mut open_orders = Self::open_orders();
+			ensure!(
+				open_orders.len() < T::MaxOpenOrders::get() as usize,
+				Error::<T>::TooManyOpenOrders
+			);
+			let account_open_orders = Self::open_order_count(&trader);
+			ensure!(
+				account_open_orders < T::MaxOpenOrdersPerAccount::get(),
+				Error::<T>::TooManyOpenOrdersForAccount
+			);
+			<pallet_generic_asset::Module<T>>::reserve(&asset_to_sell, &trader, sell_amount)?;
+
+			let order_id = Self::next_order_id();
+			<NextOrderId>::put(order_id.wrapping_add(1));
+			<LimitOrders<T>>::insert(order_id, LimitOrder {
+				owner: trader.clone(),
+				asset_to_sell,
+				asset_to_buy,
+				sell_amount,
+				minimum_buy,
+				valid_until,
+			});
+			open_orders.push(order_id);
+			<OpenOrders>::put(open_orders);
+			<OpenOrderCount<T>>::insert(&trader, account_open_orders + 1);
+
+			Self::deposit_event(RawEvent::OrderPlaced(
+				order_id,
+				trader,
+				asset_to_sell,
+				sell_amount,
+				asset_to_buy,
+				minimum_buy,
+			));
+			Ok(())
 		}
-	}
 
-	/// Account Liquidity Value
-	///
-	/// Returns a struct containing:
-	///   * the total liquidity in an account for a given asset ID
-	///   * the total withdrawable core asset
-	///   * the total withdrawable trade asset
-	pub fn account_liquidity_value(who: &T::AccountId, asset_id: T::AssetId) -> LiquidityValue<T::Balance> {
-		let core_asset_id = Self::core_asset_id();
-		let exchange_key = (core_asset_id, asset_id);
-		let account_liquidity = <LiquidityBalance<T>>::get(&exchange_key, who);
-		Self::liquidity_value(asset_id, account_liquidity)
-	}
+		/// Cancel an open limit order, returning its reserved funds
+		///
+		/// `order_id` - The ID of the order to cancel
+		pub fn cancel_limit_order(origin, #[compact] order_id: OrderId) -> DispatchResult {
+			let trader = ensure_signed(origin)?;
+			let order = Self::limit_order(order_id).ok_or(Error::<T>::OrderNotFound)?;
+			ensure!(order.owner == trader, Error::<T>::NotOrderOwner);
 
-	/// The Value of a specific amount of liquidity
-	///
-	/// Takes an `asset_id` and an amount of `liquidity_to_withdraw` and returns its value
-	/// from the exchange.
-	///
-	/// Returns a struct containing:
-	///   * the withdrawable liquidity for the given `asset_id`
-	///   * the core asset exchangable for the `liquidity_to_withdraw`
-	///   * the trade asset exchangable for the `liquidity_to_withdraw`
-	fn liquidity_value(asset_id: T::AssetId, liquidity_to_withdraw: T::Balance) -> LiquidityValue<T::Balance> {
-		let core_asset_id = Self::core_asset_id();
-		let exchange_key = (core_asset_id, asset_id);
-		let total_liquidity = <TotalLiquidity<T>>::get(&exchange_key);
-		let exchange_address = T::ExchangeAddressGenerator::exchange_address_for(asset_id);
-		let asset_reserve = <pallet_generic_asset::Module<T>>::free_balance(&asset_id, &exchange_address);
-		let core_reserve = <pallet_generic_asset::Module<T>>::free_balance(&core_asset_id, &exchange_address);
-		Self::calculate_liquidity_value(asset_reserve, core_reserve, liquidity_to_withdraw, total_liquidity)
-	}
+			Self::close_order(order_id, &order);
+			<OpenOrders>::mutate(|open_orders| open_orders.retain(|id| *id != order_id));
 
-	/// Calculate the Value of Liquidity
-	///
-	/// Simple helper function to calculate the value of liquidity
-	fn calculate_liquidity_value(
-		asset_reserve: T::Balance,
-		core_reserve: T::Balance,
-		liquidity_to_withdraw: T::Balance,
-		total_liquidity: T::Balance,
-	) -> LiquidityValue<T::Balance> {
-		if total_liquidity.is_zero() {
-			LiquidityValue {
-				liquidity: Zero::zero(),
-				core: Zero::zero(),
-				asset: Zero::zero(),
+			Self::deposit_event(RawEvent::OrderCancelled(order_id, trader));
+			Ok(())
+		}
+
+		/// Register the weight of the open limit orders checked in `on_finalize`.
+		/// Orders placed during the block pay for their own check when they are placed.
+		fn on_initialize(_n: T::BlockNumber) -> Weight {
+			let to_check = Self::open_orders().len().min(T::MaxOrdersPerBlock::get() as usize);
+			LIMIT_ORDER_WEIGHT.saturating_mul(to_check as Weight)
+		}
+
+		/// Fill or expire open limit orders, up to `MaxOrdersPerBlock` each block
+		fn on_finalize(n: T::BlockNumber) {
+			Self::process_limit_orders(n);
+		}
+
+		/// Deposit a single asset to mint liquidity
+		/// The optimal portion of `amount` is sold into the exchange first, so the remainder and the
+		/// proceeds are added at the exchange's new ratio. Any remainder which does not fit the ratio
+		/// stays with the depositor.
+		/// Only constant product exchanges are supported. The sale and the deposit are both checked
+		/// before any transfer is made.
+		///
+		/// `asset_id` - The trade asset ID
+		/// `deposit_asset` - The asset to deposit, either the core asset or `asset_id`
+		/// `amount` - Amount of `deposit_asset` to deposit
+		/// `min_liquidity` - The minimum liquidity to add
+		pub fn add_liquidity_single_sided(
+			origin,
+			#[compact] asset_id: T::AssetId,
+			#[compact] deposit_asset: T::AssetId,
+			#[compact] amount: T::Balance,
+			#[compact] min_liquidity: T::Balance
+		) -> DispatchResult {
+			let from_account = ensure_signed(origin)?;
+			let core_asset_id = Self::core_asset_id();
+			ensure!(asset_id != core_asset_id, Error::<T>::InvalidAssetId);
+			ensure!(
+				deposit_asset == core_asset_id || deposit_asset == asset_id,
+				Error::<T>::InvalidAssetId
+			);
+			ensure!(!amount.is_zero(), Error::<T>::CannotAddLiquidityWithZero);
+			ensure!(
+				<pallet_generic_asset::Module<T>>::free_balance(&deposit_asset, &from_account) >= amount,
+				if deposit_asset == core_asset_id {
+					Error::<T>::InsufficientCoreAssetBalance
+				} else {
+					Error::<T>::InsufficientTradeAssetBalance
+				}
+			);
+
+			let deposit = Self::single_sided_deposit(asset_id, deposit_asset, amount)?;
+			ensure!(
+				deposit.liquidity >= min_liquidity,
+				Error::<T>::MinimumLiquidityRequirementNotMet
+			);
+			Self::ensure_single_sided_deposit_allowed(&from_account, asset_id, deposit_asset, &deposit)?;
+
+			let other_asset = if deposit_asset == core_asset_id { asset_id } else { core_asset_id };
+			Self::execute_sell(
+				&from_account,
+				&from_account,
+				&deposit_asset,
+				&other_asset,
+				deposit.swap_amount,
+				deposit.swap_proceeds,
+			)?;
+			Self::deposit_liquidity(&from_account, asset_id, deposit.core, deposit.asset, deposit.liquidity)
+		}
+
+		/// Burn liquidity to withdraw a single asset
+		/// The core asset and trade asset are withdrawn at the current ratio, then the one not requested
+		/// is sold into the exchange for `withdraw_asset`.
+		///
+		/// `asset_id` - The trade asset ID
+		/// `liquidity_to_withdraw` - Amount of user's liquidity to withdraw
+		/// `withdraw_asset` - The asset to withdraw, either the core asset or `asset_id`
+		/// `min_withdraw` - The minimum amount of `withdraw_asset` withdrawn
+		pub fn remove_liquidity_single_sided(
+			origin,
+			#[compact] asset_id: T::AssetId,
+			#[compact] liquidity_to_withdraw: T::Balance,
+			#[compact] withdraw_asset: T::AssetId,
+			#[compact] min_withdraw: T::Balance
+		) -> DispatchResult {
+			let from_account = ensure_signed(origin)?;
+			let core_asset_id = Self::core_asset_id();
+			ensure!(asset_id != core_asset_id, Error::<T>::InvalidAssetId);
+			ensure!(
+				withdraw_asset == core_asset_id || withdraw_asset == asset_id,
+				Error::<T>::InvalidAssetId
+			);
+			let exchange_key = (core_asset_id, asset_id);
+			let account_liquidity = <LiquidityBalance<T>>::get(&exchange_key, &from_account);
+			ensure!(
+				account_liquidity >= liquidity_to_withdraw,
+				Error::<T>::InsufficientLiquidity
+			);
+			ensure!(
+				<TotalLiquidity<T>>::get(&exchange_key) > Zero::zero(),
+				Error::<T>::EmptyExchangePool
+			);
+
+			let withdraw_value = Self::liquidity_value(asset_id, liquidity_to_withdraw);
+			let (core_reserve, asset_reserve) = Self::get_exchange_reserves(&asset_id);
+			let core_reserve = core_reserve
+				.checked_sub(&withdraw_value.core)
+				.ok_or(Error::<T>::InsufficientExchangePoolReserve)?;
+			let asset_reserve = asset_reserve
+				.checked_sub(&withdraw_value.asset)
+				.ok_or(Error::<T>::InsufficientExchangePoolReserve)?;
+			// The withdrawn amount of `withdraw_asset`, the other asset to sell and the reserves to sell it into
+			let (other_asset, withdrawn, sell_amount, sell_reserve, buy_reserve) = if withdraw_asset == core_asset_id {
+				(asset_id, withdraw_value.core, withdraw_value.asset, asset_reserve, core_reserve)
+			} else {
+				(core_asset_id, withdraw_value.asset, withdraw_value.core, core_reserve, asset_reserve)
+			};
+			// The sale must be allowed before any liquidity is withdrawn
+			let (proceeds, sell_reserve, buy_reserve) = if sell_amount.is_zero() {
+				(Zero::zero(), sell_reserve, buy_reserve)
+			} else {
+				Self::ensure_exchange_open(&asset_id)?;
+				let fee_rate = Self::fee_rate_for(&asset_id);
+				let curve = Self::curve_for(&asset_id);
+				let proceeds = Self::calculate_sell_price(sell_amount, sell_reserve, buy_reserve, fee_rate, curve)?;
+				let protocol_fee = Self::calculate_protocol_fee(sell_amount, fee_rate, Self::protocol_fee_share());
+				(
+					proceeds,
+					sell_reserve.saturating_add(sell_amount - protocol_fee),
+					buy_reserve.saturating_sub(proceeds),
+				)
+			};
+			let (core_reserve, asset_reserve) = if withdraw_asset == core_asset_id {
+				(buy_reserve, sell_reserve)
+			} else {
+				(sell_reserve, buy_reserve)
+			};
+			if !sell_amount.is_zero() {
+				Self::ensure_price_movement_allowed(&asset_id, core_reserve, asset_reserve)?;
+			}
+			Self::ensure_minimum_reserve(core_reserve, asset_reserve)?;
+			ensure!(
+				withdrawn.saturating_add(proceeds) >= min_withdraw,
+				if withdraw_asset == core_asset_id {
+					Error::<T>::MinimumCoreAssetRequirementNotMet
+				} else {
+					Error::<T>::MinimumTradeAssetRequirementNotMet
+				}
+			);
+
+			Self::withdraw_liquidity(&from_account, asset_id, liquidity_to_withdraw, &withdraw_value)?;
+			if !sell_amount.is_zero() {
+				Self::execute_sell(&from_account, &from_account, &other_asset, &withdraw_asset, sell_amount, proceeds)?;
+			}
+			Ok(())
+		}
+
+		/// Transfer liquidity holdings in an exchange to another account
+		///
+		/// `asset_id` - The trade asset ID
+		/// `to` - The account to receive the liquidity
+		/// `amount` - Amount of user's liquidity to transfer
+		pub fn transfer_liquidity(
+			origin,
+			#[compact] asset_id: T::AssetId,
+			to: T::AccountId,
+			#[compact] amount: T::Balance
+		) -> DispatchResult {
+			let from_account = ensure_signed(origin)?;
+			let exchange_key = (Self::core_asset_id(), asset_id);
+			ensure!(
+				<LiquidityBalance<T>>::get(&exchange_key, &from_account) >= amount,
+				Error::<T>::InsufficientLiquidity
+			);
+
+			if from_account != to {
+				Self::transfer_liquidity_position(&exchange_key, &from_account, &to, amount);
+				<LiquidityBalance<T>>::mutate(&exchange_key, &from_account, |balance| *balance = balance.saturating_sub(amount));
+				<LiquidityBalance<T>>::mutate(&exchange_key, &to, |balance| *balance = balance.saturating_add(amount));
+			}
+			Self::deposit_event(RawEvent::LiquidityTransferred(from_account, to, asset_id, amount));
+			Ok(())
+		}
+
+		/// Set the fee rate of the exchange for `asset_id`, overriding the default fee rate (root only)
+		///
+		/// `asset_id` - The trade asset ID
+		/// `new_fee_rate` - The fee rate to apply to trades in the exchange, less than one
+		pub fn set_exchange_fee_rate(
+			origin,
+			#[compact] asset_id: T::AssetId,
+			new_fee_rate: FeeRate<PerMillion>
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			Self::ensure_valid_fee_rate(new_fee_rate)?;
+			let exchange_key = (Self::core_asset_id(), asset_id);
+			<ExchangeFeeRate<T>>::insert(&exchange_key, new_fee_rate);
+			Self::deposit_event(RawEvent::ExchangeFeeRateSet(asset_id, new_fee_rate));
+			Ok(())
+		}
+
+		/// Clear the fee rate of the exchange for `asset_id`, so that it uses the default fee rate (root only)
+		///
+		/// `asset_id` - The trade asset ID
+		pub fn clear_exchange_fee_rate(origin, #[compact] asset_id: T::AssetId) -> DispatchResult {
+			ensure_root(origin)?;
+			let exchange_key = (Self::core_asset_id(), asset_id);
+			<ExchangeFeeRate<T>>::remove(&exchange_key);
+			Self::deposit_event(RawEvent::ExchangeFeeRateCleared(asset_id));
+			Ok(())
+		}
+
+		/// Set the pricing curve of the exchange for `asset_id` (root only)
+		/// The curve may only be set while the exchange is empty, as switching the curve of a funded exchange
+		/// would reprice its liquidity at once.
+		///
+		/// `asset_id` - The trade asset ID
+		/// `curve` - The curve to price trades in the exchange with
+		pub fn set_exchange_curve(origin, #[compact] asset_id: T::AssetId, curve: Curve) -> DispatchResult {
+			ensure_root(origin)?;
+			ensure!(curve.is_valid(), Error::<T>::InvalidPricingCurve);
+			let exchange_key = (Self::core_asset_id(), asset_id);
+			ensure!(!<ExchangeCreatedAt<T>>::contains_key(&exchange_key), Error::<T>::ExchangeNotEmpty);
+			<PricingCurves<T>>::insert(&exchange_key, curve);
+			Ok(())
+		}
+
+		/// Set the share of each trading fee paid to the protocol (root only)
+		///
+		/// `new_share` - The share of the fee, at most one
+		pub fn set_protocol_fee_share(origin, new_share: FeeRate<PerMillion>) -> DispatchResult {
+			ensure_root(origin)?;
+			ensure!(
+				LowPrecisionUnsigned::from(new_share) <= LowPrecisionUnsigned::from(FeeRate::<PerMillion>::one()),
+				Error::<T>::InvalidProtocolFeeShare
+			);
+			ProtocolFeeShare::put(new_share);
+			Ok(())
+		}
+
+		/// Pay the protocol fees held by the exchange for `asset_id` to `ProtocolFeeRecipient`
+		/// Anyone may call this on purpose, the fees are only ever paid to `ProtocolFeeRecipient`.
+		/// The fees in both assets are withdrawn, or neither is.
+		///
+		/// `asset_id` - The trade asset ID
+		pub fn withdraw_protocol_fees(origin, #[compact] asset_id: T::AssetId) -> DispatchResult {
+			let _ = ensure_signed(origin)?;
+			let core_asset_id = Self::core_asset_id();
+			let exchange_key = (core_asset_id, asset_id);
+			let exchange_address = T::ExchangeAddressGenerator::exchange_address_for(asset_id);
+			let recipient = T::ProtocolFeeRecipient::get();
+
+			let core_fees = Self::protocol_fees(&exchange_key, &core_asset_id);
+			let asset_fees = Self::protocol_fees(&exchange_key, &asset_id);
+			if !core_fees.is_zero() {
+				<pallet_generic_asset::Module<T>>::make_transfer(&core_asset_id, &exchange_address, &recipient, core_fees)?;
+			}
+			if !asset_fees.is_zero() {
+				let result = <pallet_generic_asset::Module<T>>::make_transfer(&asset_id, &exchange_address, &recipient, asset_fees);
+				if result.is_err() && !core_fees.is_zero() {
+					// Reverse the core fee transfer, the recipient has just received it so this cannot fail
+					let _ = <pallet_generic_asset::Module<T>>::make_transfer(&core_asset_id, &recipient, &exchange_address, core_fees);
+				}
+				result?;
+			}
+			<ProtocolFees<T>>::remove(&exchange_key, &core_asset_id);
+			<ProtocolFees<T>>::remove(&exchange_key, &asset_id);
+
+			Self::deposit_event(RawEvent::ProtocolFeesWithdrawn(asset_id, core_fees, asset_fees));
+			Ok(())
+		}
+
+		/// Stop all trading on the exchange for `asset_id` (root or `PauseOrigin`)
+		/// Liquidity may still be added and removed at the exchange's ratio while it is paused.
+		///
+		/// `asset_id` - The trade asset ID
+		pub fn pause_exchange(origin, #[compact] asset_id: T::AssetId) -> DispatchResult {
+			T::PauseOrigin::try_origin(origin).map(|_| ()).or_else(ensure_root)?;
+			let exchange_key = (Self::core_asset_id(), asset_id);
+			<PausedExchanges<T>>::insert(&exchange_key, true);
+			Self::deposit_event(RawEvent::ExchangePaused(asset_id));
+			Ok(())
+		}
+
+		/// Allow trading on the exchange for `asset_id` again (root or `PauseOrigin`)
+		///
+		/// `asset_id` - The trade asset ID
+		pub fn resume_exchange(origin, #[compact] asset_id: T::AssetId) -> DispatchResult {
+			T::PauseOrigin::try_origin(origin).map(|_| ()).or_else(ensure_root)?;
+			let exchange_key = (Self::core_asset_id(), asset_id);
+			<PausedExchanges<T>>::remove(&exchange_key);
+			Self::deposit_event(RawEvent::ExchangeResumed(asset_id));
+			Ok(())
+		}
+
+		/// Set the smallest reserve of each asset an exchange may be left with by a withdrawal (root only)
+		///
+		/// `new_minimum` - The minimum reserve
+		pub fn set_minimum_reserve(origin, #[compact] new_minimum: T::Balance) -> DispatchResult {
+			ensure_root(origin)?;
+			<MinimumReserve<T>>::put(new_minimum);
+			Ok(())
+		}
+
+		/// Set the largest change in an exchange's price allowed within a block, zero disables the limit (root only)
+		///
+		/// `new_limit` - The change allowed, as a share of the price before the block's first trade
+		pub fn set_max_price_movement(origin, new_limit: FeeRate<PerMillion>) -> DispatchResult {
+			ensure_root(origin)?;
+			MaxPriceMovement::put(new_limit);
+			Ok(())
+		}
+
+		/// Borrow reserves of the exchange for `asset_id`, dispatch `call` as the borrower, then repay the
+		/// reserves borrowed plus the exchange's trading fee.
+		/// Storage isn't reverted when a call fails, so the repayment is reserved from the borrower before
+		/// anything is lent and is paid back out of the reservation, whether `call` succeeds or not.
+		/// The exchange can't be traded on and its liquidity can't change while `call` is dispatched.
+		/// Fails with the error of `call` if it failed, after the exchange has been repaid.
+		///
+		/// `asset_id` - The trade asset ID
+		/// `core_amount` - The amount of core asset to borrow
+		/// `asset_amount` - The amount of `asset_id` to borrow
+		/// `call` - The call to dispatch with the borrowed reserves
+		#[weight = FunctionOf(
+			|(_, _, _, call): (&T::AssetId, &T::Balance, &T::Balance, &Box<<T as Trait>::Call>)|
+				call.get_dispatch_info().weight.saturating_add(FLASH_SWAP_WEIGHT),
+			DispatchClass::Normal,
+			true
+		)]
+		pub fn flash_swap(
+			origin,
+			#[compact] asset_id: T::AssetId,
+			#[compact] core_amount: T::Balance,
+			#[compact] asset_amount: T::Balance,
+			call: Box<<T as Trait>::Call>
+		) -> DispatchResult {
+			let borrower = ensure_signed(origin)?;
+			let core_asset_id = Self::core_asset_id();
+			ensure!(asset_id != core_asset_id, Error::<T>::InvalidAssetId);
+			ensure!(!core_amount.is_zero() || !asset_amount.is_zero(), Error::<T>::CannotTradeZero);
+			Self::ensure_exchange_open(&asset_id)?;
+			let (core_reserve, asset_reserve) = Self::get_exchange_reserves(&asset_id);
+			ensure!(
+				core_reserve > core_amount && asset_reserve > asset_amount,
+				Error::<T>::InsufficientExchangePoolReserve
+			);
+
+			let fee_rate = Self::fee_rate_for(&asset_id);
+			let core_repayment = Self::calculate_flash_swap_repayment(core_amount, fee_rate)?;
+			let asset_repayment = Self::calculate_flash_swap_repayment(asset_amount, fee_rate)?;
+			ensure!(
+				<pallet_generic_asset::Module<T>>::free_balance(&core_asset_id, &borrower) >= core_repayment,
+				Error::<T>::InsufficientCoreAssetBalance
+			);
+			ensure!(
+				<pallet_generic_asset::Module<T>>::free_balance(&asset_id, &borrower) >= asset_repayment,
+				Error::<T>::InsufficientTradeAssetBalance
+			);
+			<pallet_generic_asset::Module<T>>::reserve(&core_asset_id, &borrower, core_repayment)?;
+			if let Err(err) = <pallet_generic_asset::Module<T>>::reserve(&asset_id, &borrower, asset_repayment) {
+				let _ = <pallet_generic_asset::Module<T>>::unreserve(&core_asset_id, &borrower, core_repayment);
+				return Err(err);
+			}
+
+			// Lend the reserves, the exchange account holds no locks so these transfers cannot fail
+			let exchange_key = (core_asset_id, asset_id);
+			let exchange_address = T::ExchangeAddressGenerator::exchange_address_for(asset_id);
+			Self::update_price_oracle(&asset_id);
+			let _ = <pallet_generic_asset::Module<T>>::make_transfer(
+				&core_asset_id,
+				&exchange_address,
+				&borrower,
+				core_amount,
+			);
+			let _ = <pallet_generic_asset::Module<T>>::make_transfer(
+				&asset_id,
+				&exchange_address,
+				&borrower,
+				asset_amount,
+			);
+
+			<FlashSwapping<T>>::insert(&exchange_key, true);
+			let call_result = call.dispatch(RawOrigin::Signed(borrower.clone()).into());
+			<FlashSwapping<T>>::remove(&exchange_key);
+
+			Self::repay_flash_swap(
+				&borrower,
+				&exchange_key,
+				&exchange_address,
+				&core_asset_id,
+				core_amount,
+				core_repayment,
+				fee_rate,
+			)?;
+			Self::repay_flash_swap(
+				&borrower,
+				&exchange_key,
+				&exchange_address,
+				&asset_id,
+				asset_amount,
+				asset_repayment,
+				fee_rate,
+			)?;
+			Self::ensure_flash_swap_invariant(&asset_id, core_reserve, asset_reserve)?;
+			call_result?;
+
+			Self::deposit_event(RawEvent::FlashSwap(
+				borrower,
+				asset_id,
+				core_amount,
+				asset_amount,
+				core_repayment,
+				asset_repayment,
+			));
+			Ok(())
+		}
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		<T as frame_system::Trait>::AccountId,
+		<T as pallet_generic_asset::Trait>::AssetId,
+		<T as pallet_generic_asset::Trait>::Balance
+	{
+		/// Provider, core asset amount, trade asset id, trade asset amount
+		AddLiquidity(AccountId, Balance, AssetId, Balance),
+		/// Provider, core asset amount, trade asset id, trade asset amount
+		RemoveLiquidity(AccountId, Balance, AssetId, Balance),
+		/// From, To, trade asset id, liquidity amount
+		LiquidityTransferred(AccountId, AccountId, AssetId, Balance),
+		/// AssetSold, AssetBought, Buyer, SoldAmount, BoughtAmount, ProtocolFee (in AssetSold)
+		/// Emitted once for each trade, or for each exchange passed through by a trade via an explicit path.
+		/// Breaking change: ProtocolFee was added as a sixth field, clients decoding the five field event must
+		/// be updated.
+		AssetPurchase(AssetId, AssetId, AccountId, Balance, Balance, Balance),
+		/// Trader, (SoldAmount, BoughtAmount) of each trade in the batch
+		BatchTradeExecuted(AccountId, Vec<(Balance, Balance)>),
+		/// OrderId, Trader, AssetToSell, SellAmount, AssetToBuy, MinimumBuy
+		OrderPlaced(OrderId, AccountId, AssetId, Balance, AssetId, Balance),
+		/// OrderId, Trader, SoldAmount, BoughtAmount
+		OrderFilled(OrderId, AccountId, Balance, Balance),
+		/// OrderId, Trader
+		OrderExpired(OrderId, AccountId),
+		/// OrderId, Trader
+		OrderCancelled(OrderId, AccountId),
+		/// TradeAssetId, ExchangeAddress
+		ExchangeCreated(AssetId, AccountId),
+		/// TradeAssetId
+		ExchangeEmptied(AssetId),
+		/// TradeAssetId, CoreAssetAmount, TradeAssetAmount
+		ProtocolFeesWithdrawn(AssetId, Balance, Balance),
+		/// TradeAssetId
+		ExchangePaused(AssetId),
+		/// TradeAssetId
+		ExchangeResumed(AssetId),
+		/// TradeAssetId, FeeRate
+		ExchangeFeeRateSet(AssetId, FeeRate<PerMillion>),
+		/// TradeAssetId
+		ExchangeFeeRateCleared(AssetId),
+		/// Borrower, TradeAssetId, CoreAssetBorrowed, TradeAssetBorrowed, CoreAssetRepaid, TradeAssetRepaid
+		FlashSwap(AccountId, AssetId, Balance, Balance, Balance, Balance),
+	}
+);
+
+decl_storage! {
+	trait Store for Module<T: Trait> as CennzxSpot {
+		/// AssetId of Core Asset
+		pub CoreAssetId get(core_asset_id) config(): T::AssetId;
+		/// Default Trading fee rate
+		pub DefaultFeeRate get(fee_rate) config(): FeeRate<PerMillion>;
+		/// Trading fee rate of an exchange, overrides `DefaultFeeRate` when set
+		pub ExchangeFeeRate get(exchange_fee_rate): map hasher(twox_64_concat) ExchangeKey<T> => Option<FeeRate<PerMillion>>;
+		/// The pricing curve of an exchange, constant product unless set
+		pub PricingCurves get(pricing_curve): map hasher(twox_64_concat) ExchangeKey<T> => Curve;
+		/// The share of each trading fee paid to the protocol rather than liquidity providers
+		pub ProtocolFeeShare get(protocol_fee_share): FeeRate<PerMillion>;
+		/// Protocol fees held by an exchange until they are withdrawn to `ProtocolFeeRecipient`
+		/// Key: `(core_asset_id, trade_asset_id), asset_id`
+		pub ProtocolFees get(protocol_fees): double_map hasher(twox_64_concat) ExchangeKey<T>, hasher(twox_64_concat) T::AssetId => T::Balance;
+		/// Total liquidity holdings of all investers in an exchange.
+		/// ie/ total_liquidity(exchange) == sum(liquidity_balance(exchange, user)) at all times
+		pub TotalLiquidity get(total_liquidity): map hasher(twox_64_concat) ExchangeKey<T> => T::Balance;
+
+		/// Liquidity holdings of a user in an exchange pool.
+		/// Key: `(core_asset_id, trade_asset_id), account_id`
+		pub LiquidityBalance get(liquidity_balance): double_map hasher(twox_64_concat) ExchangeKey<T>, hasher(blake2_128_concat) T::AccountId => T::Balance;
+
+		/// Trading fees earned by the liquidity providers of an exchange, as `(core, asset)`
+		pub CollectedFees get(collected_fees): map hasher(twox_64_concat) ExchangeKey<T> => (T::Balance, T::Balance);
+		/// Cumulative trading fees earned per unit of liquidity in an exchange, as `(core, asset)`.
+		/// Scaled by `PRICE_SCALE` and wrap on overflow, so only the difference between two values is meaningful.
+		pub FeeGrowth get(fee_growth): map hasher(twox_64_concat) ExchangeKey<T> => (LowPrecisionUnsigned, LowPrecisionUnsigned);
+		/// The cost and fee earnings of the liquidity held by a user in an exchange pool, as of its last settlement.
+		/// Key: `(core_asset_id, trade_asset_id), account_id`
+		pub LiquidityPositions get(liquidity_position): double_map hasher(twox_64_concat) ExchangeKey<T>, hasher(blake2_128_concat) T::AccountId => LiquidityPosition<T::Balance>;
+
+		/// Trade asset IDs of the exchanges holding liquidity, in the order they were created
+		pub ActiveExchanges get(active_exchanges): Vec<T::AssetId>;
+		/// The block an exchange holding liquidity was created in
+		pub ExchangeCreatedAt get(exchange_created_at): map hasher(twox_64_concat) ExchangeKey<T> => Option<T::BlockNumber>;
+
+		/// Cumulative price observations of an exchange, a ring buffer of at most `MaxPriceObservations` entries.
+		/// Key: `(core_asset_id, trade_asset_id), index`
+		pub PriceObservations get(price_observation): double_map hasher(twox_64_concat) ExchangeKey<T>, hasher(twox_64_concat) u32 => Option<PriceObservation<T::BlockNumber>>;
+
+		/// The ring buffer index of the latest price observation of an exchange and the number of observations held.
+		pub PriceObservationIndex get(price_observation_index): map hasher(twox_64_concat) ExchangeKey<T> => (u32, u32);
+
+		/// The ID of the next limit order placed
+		pub NextOrderId get(next_order_id): OrderId;
+		/// Open limit orders
+		pub LimitOrders get(limit_order): map hasher(twox_64_concat) OrderId => Option<LimitOrderOf<T>>;
+		/// IDs of the open limit orders, in the order they will next be checked for a fill or expiry
+		pub OpenOrders get(open_orders): Vec<OrderId>;
+		/// The number of open limit orders placed by an account
+		pub OpenOrderCount get(open_order_count): map hasher(blake2_128_concat) T::AccountId => u32;
+
+		/// The smallest reserve of each asset an exchange may be left with by a withdrawal
+		pub MinimumReserve get(minimum_reserve): T::Balance;
+
+		/// Exchanges where trading is paused
+		pub PausedExchanges get(is_paused): map hasher(twox_64_concat) ExchangeKey<T> => bool;
+		/// Exchanges lending their reserves to a flash swap, their reserves can't be traded or changed until it is repaid
+		pub FlashSwapping get(is_flash_swapping): map hasher(twox_64_concat) ExchangeKey<T> => bool;
+		/// The largest change in an exchange's price allowed within a block, the limit is disabled when zero
+		pub MaxPriceMovement get(max_price_movement): FeeRate<PerMillion>;
+		/// The price of one core asset in trade asset before the first trade in the block an exchange was last
+		/// traded in, scaled by `PRICE_SCALE`. Only recorded while `MaxPriceMovement` is set.
+		pub OpeningPrice get(opening_price): map hasher(twox_64_concat) ExchangeKey<T> => Option<(T::BlockNumber, LowPrecisionUnsigned)>;
+	}
+}
+
+// The main implementation block for the module.
+impl<T: Trait> Module<T> {
+	//
+	// Liquidity
+	//
+
+	/// Mint liquidity holdings for a user in a specified exchange
+	fn mint_liquidity(exchange_key: &ExchangeKey<T>, who: &T::AccountId, increase: T::Balance) {
+		let balance = <LiquidityBalance<T>>::get(exchange_key, who);
+		let new_balance = balance.saturating_add(increase);
+		<LiquidityBalance<T>>::insert(exchange_key, who, new_balance);
+		<TotalLiquidity<T>>::mutate(exchange_key, |balance| *balance = balance.saturating_add(increase));
+	}
+
+	/// Burn liquidity holdings from a user in a specified exchange
+	fn burn_liquidity(exchange_key: &ExchangeKey<T>, who: &T::AccountId, decrease: T::Balance) {
+		let balance = <LiquidityBalance<T>>::get(exchange_key, who);
+		let decrease = decrease.min(balance);
+		let new_balance = balance - decrease;
+		<LiquidityBalance<T>>::insert(exchange_key, who, new_balance);
+		<TotalLiquidity<T>>::mutate(exchange_key, |balance| *balance = balance.saturating_sub(decrease));
+	}
+
+	/// Pay `core_amount` and `trade_asset_amount` from `who` into the exchange for `asset_id`
+	/// and mint them `liquidity_minted`. A new exchange also mints `MinimumLiquidity` to itself.
+	fn deposit_liquidity(
+		who: &T::AccountId,
+		asset_id: T::AssetId,
+		core_amount: T::Balance,
+		trade_asset_amount: T::Balance,
+		liquidity_minted: T::Balance,
+	) -> DispatchResult {
+		let core_asset_id = Self::core_asset_id();
+		let exchange_key = (core_asset_id, asset_id);
+		let exchange_address = T::ExchangeAddressGenerator::exchange_address_for(asset_id);
+		let total_liquidity = <TotalLiquidity<T>>::get(&exchange_key);
+		ensure!(!Self::is_flash_swapping(&exchange_key), Error::<T>::FlashSwapInProgress);
+
+		Self::update_price_oracle(&asset_id);
+		<pallet_generic_asset::Module<T>>::make_transfer(&core_asset_id, who, &exchange_address, core_amount)?;
+		<pallet_generic_asset::Module<T>>::make_transfer(&asset_id, who, &exchange_address, trade_asset_amount)?;
+
+		let mut position = Self::settled_liquidity_position(&exchange_key, who);
+		position.core_cost = position.core_cost.saturating_add(core_amount);
+		position.asset_cost = position.asset_cost.saturating_add(trade_asset_amount);
+		<LiquidityPositions<T>>::insert(&exchange_key, who, position);
+		Self::mint_liquidity(&exchange_key, who, liquidity_minted);
+		if total_liquidity.is_zero() {
+			// No account can withdraw liquidity held by the exchange itself
+			let locked_liquidity = T::MinimumLiquidity::get();
+			if !locked_liquidity.is_zero() {
+				let position = Self::settled_liquidity_position(&exchange_key, &exchange_address);
+				<LiquidityPositions<T>>::insert(&exchange_key, &exchange_address, position);
+				Self::mint_liquidity(&exchange_key, &exchange_address, locked_liquidity);
+			}
+		}
+		// An exchange left holding only its locked liquidity is created anew
+		Self::create_exchange(&exchange_key, exchange_address);
+		Self::deposit_event(RawEvent::AddLiquidity(
+			who.clone(),
+			core_amount,
+			asset_id,
+			trade_asset_amount,
+		));
+		Ok(())
+	}
+
+	/// Burn `liquidity_to_withdraw` from `who` and pay them its `withdraw_value` from the exchange for `asset_id`
+	fn withdraw_liquidity(
+		who: &T::AccountId,
+		asset_id: T::AssetId,
+		liquidity_to_withdraw: T::Balance,
+		withdraw_value: &LiquidityValue<T::Balance>,
+	) -> DispatchResult {
+		let core_asset_id = Self::core_asset_id();
+		let exchange_key = (core_asset_id, asset_id);
+		let exchange_address = T::ExchangeAddressGenerator::exchange_address_for(asset_id);
+		ensure!(!Self::is_flash_swapping(&exchange_key), Error::<T>::FlashSwapInProgress);
+
+		Self::update_price_oracle(&asset_id);
+		<pallet_generic_asset::Module<T>>::make_transfer(&core_asset_id, &exchange_address, who, withdraw_value.core)?;
+		<pallet_generic_asset::Module<T>>::make_transfer(&asset_id, &exchange_address, who, withdraw_value.asset)?;
+
+		// The withdrawn share of the position's unrealised fees is paid out as part of `withdraw_value`
+		let liquidity = <LiquidityBalance<T>>::get(&exchange_key, who);
+		let mut position = Self::settled_liquidity_position(&exchange_key, who);
+		Self::take_share(&mut position.core_cost, liquidity_to_withdraw, liquidity);
+		Self::take_share(&mut position.asset_cost, liquidity_to_withdraw, liquidity);
+		let core_fees = Self::take_share(&mut position.unrealised_core_fees, liquidity_to_withdraw, liquidity);
+		let asset_fees = Self::take_share(&mut position.unrealised_asset_fees, liquidity_to_withdraw, liquidity);
+		position.realised_core_fees = position.realised_core_fees.saturating_add(core_fees);
+		position.realised_asset_fees = position.realised_asset_fees.saturating_add(asset_fees);
+		<LiquidityPositions<T>>::insert(&exchange_key, who, position);
+		Self::burn_liquidity(&exchange_key, who, liquidity_to_withdraw);
+		Self::deposit_event(RawEvent::RemoveLiquidity(
+			who.clone(),
+			withdraw_value.core,
+			asset_id,
+			withdraw_value.asset,
+		));
+		// Liquidity locked in the exchange can never be withdrawn, so the exchange is empty once only it is left
+		let locked_liquidity = <LiquidityBalance<T>>::get(&exchange_key, &exchange_address);
+		if <TotalLiquidity<T>>::get(&exchange_key) <= locked_liquidity {
+			Self::remove_exchange(&exchange_key);
+		}
+		Ok(())
+	}
+
+	/// Move `amount` of `from`'s liquidity position in an exchange to `to`, with its share of the cost
+	/// and unrealised fees. Fees earned so far are settled into both positions first.
+	fn transfer_liquidity_position(
+		exchange_key: &ExchangeKey<T>,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: T::Balance,
+	) {
+		let liquidity = <LiquidityBalance<T>>::get(exchange_key, from);
+		let mut from_position = Self::settled_liquidity_position(exchange_key, from);
+		let mut to_position = Self::settled_liquidity_position(exchange_key, to);
+
+		let core_cost = Self::take_share(&mut from_position.core_cost, amount, liquidity);
+		let asset_cost = Self::take_share(&mut from_position.asset_cost, amount, liquidity);
+		let core_fees = Self::take_share(&mut from_position.unrealised_core_fees, amount, liquidity);
+		let asset_fees = Self::take_share(&mut from_position.unrealised_asset_fees, amount, liquidity);
+		to_position.core_cost = to_position.core_cost.saturating_add(core_cost);
+		to_position.asset_cost = to_position.asset_cost.saturating_add(asset_cost);
+		to_position.unrealised_core_fees = to_position.unrealised_core_fees.saturating_add(core_fees);
+		to_position.unrealised_asset_fees = to_position.unrealised_asset_fees.saturating_add(asset_fees);
+
+		<LiquidityPositions<T>>::insert(exchange_key, from, from_position);
+		<LiquidityPositions<T>>::insert(exchange_key, to, to_position);
+	}
+
+	/// The liquidity position of `who` in an exchange with the fees earned since its last settlement
+	/// added to its unrealised fees. Must be called before the liquidity balance of `who` changes.
+	fn settled_liquidity_position(exchange_key: &ExchangeKey<T>, who: &T::AccountId) -> LiquidityPosition<T::Balance> {
+		let mut position = <LiquidityPositions<T>>::get(exchange_key, who);
+		let liquidity = <LiquidityBalance<T>>::get(exchange_key, who);
+		let (core_fee_growth, asset_fee_growth) = Self::fee_growth(exchange_key);
+
+		let core_fees = Self::calculate_fees_earned(liquidity, core_fee_growth.wrapping_sub(position.core_fee_growth));
+		let asset_fees =
+			Self::calculate_fees_earned(liquidity, asset_fee_growth.wrapping_sub(position.asset_fee_growth));
+		position.unrealised_core_fees = position.unrealised_core_fees.saturating_add(core_fees);
+		position.unrealised_asset_fees = position.unrealised_asset_fees.saturating_add(asset_fees);
+		position.core_fee_growth = core_fee_growth;
+		position.asset_fee_growth = asset_fee_growth;
+		position
+	}
+
+	/// `liquidity` - The liquidity held
+	/// `fee_growth` - The growth in fees earned per unit of liquidity, scaled by `PRICE_SCALE`
+	/// Returns the fees earned by `liquidity`, rounded down
+	fn calculate_fees_earned(liquidity: T::Balance, fee_growth: LowPrecisionUnsigned) -> T::Balance {
+		let liquidity: LowPrecisionUnsigned = T::BalanceToUnsignedInt::from(liquidity).into();
+		let fees = HighPrecisionUnsigned::from(liquidity) * HighPrecisionUnsigned::from(fee_growth)
+			/ HighPrecisionUnsigned::from(PRICE_SCALE);
+		let fees = LowPrecisionUnsigned::try_from(fees).unwrap_or(LowPrecisionUnsigned::max_value());
+		T::UnsignedIntToBalance::from(fees).into()
+	}
+
+	/// Remove the share `amount / liquidity` of `value` from it, rounded down, and return the share removed
+	fn take_share(value: &mut T::Balance, amount: T::Balance, liquidity: T::Balance) -> T::Balance {
+		if liquidity.is_zero() {
+			return Zero::zero();
+		}
+		let value_u128: LowPrecisionUnsigned = T::BalanceToUnsignedInt::from(*value).into();
+		let amount: LowPrecisionUnsigned = T::BalanceToUnsignedInt::from(amount.min(liquidity)).into();
+		let liquidity: LowPrecisionUnsigned = T::BalanceToUnsignedInt::from(liquidity).into();
+		let share = HighPrecisionUnsigned::from(value_u128) * HighPrecisionUnsigned::from(amount)
+			/ HighPrecisionUnsigned::from(liquidity);
+		// `share` is at most `value` as `amount` is at most `liquidity`
+		let share: T::Balance = T::UnsignedIntToBalance::from(share.low_u128()).into();
+		*value = value.saturating_sub(share);
+		share
+	}
+
+	/// Credit the liquidity providers of an exchange with `fee` of `asset_id`, in proportion to their liquidity
+	fn accrue_liquidity_fee(exchange_key: &ExchangeKey<T>, asset_id: &T::AssetId, fee: T::Balance) {
+		if fee.is_zero() {
+			return;
+		}
+		let is_core = *asset_id == exchange_key.0;
+		<CollectedFees<T>>::mutate(exchange_key, |(core_fees, asset_fees)| {
+			let fees = if is_core { core_fees } else { asset_fees };
+			*fees = fees.saturating_add(fee);
+		});
+
+		let total_liquidity = <TotalLiquidity<T>>::get(exchange_key);
+		if total_liquidity.is_zero() {
+			return;
+		}
+		let fee: LowPrecisionUnsigned = T::BalanceToUnsignedInt::from(fee).into();
+		let total_liquidity: LowPrecisionUnsigned = T::BalanceToUnsignedInt::from(total_liquidity).into();
+		let growth_hp = HighPrecisionUnsigned::from(fee) * HighPrecisionUnsigned::from(PRICE_SCALE)
+			/ HighPrecisionUnsigned::from(total_liquidity);
+		let growth = growth_hp.low_u128();
+		<FeeGrowth<T>>::mutate(exchange_key, |(core_growth, asset_growth)| {
+			let fee_growth = if is_core { core_growth } else { asset_growth };
+			*fee_growth = fee_growth.wrapping_add(growth);
+		});
+	}
+
+	/// The cost and fee earnings of the liquidity held by `who` in the exchange for `asset_id`.
+	/// Unrealised fees include those earned since the position was last settled.
+	pub fn account_fee_earnings(who: &T::AccountId, asset_id: T::AssetId) -> LiquidityPosition<T::Balance> {
+		let exchange_key = (Self::core_asset_id(), asset_id);
+		Self::settled_liquidity_position(&exchange_key, who)
+	}
+
+	/// The single sided deposit of `amount` of `deposit_asset` into the exchange for `asset_id`
+	///
+	/// The portion of `amount` sold is the one leaving the remainder and the proceeds at the exchange's
+	/// ratio after the sale. The amounts added are then rounded down so the trade asset required,
+	/// which `add_liquidity` rounds up, does not exceed what is available.
+	/// The portion is sized for a constant product exchange, so other curves are unsupported.
+	pub fn single_sided_deposit(
+		asset_id: T::AssetId,
+		deposit_asset: T::AssetId,
+		amount: T::Balance,
+	) -> Result<SingleSidedDeposit<T::Balance>, DispatchError> {
+		let core_asset_id = Self::core_asset_id();
+		let exchange_key = (core_asset_id, asset_id);
+		let total_liquidity = <TotalLiquidity<T>>::get(&exchange_key);
+		let (core_reserve, asset_reserve) = Self::get_exchange_reserves(&asset_id);
+		ensure!(
+			!total_liquidity.is_zero() && !core_reserve.is_zero() && !asset_reserve.is_zero(),
+			Error::<T>::EmptyExchangePool
+		);
+
+		let curve = Self::curve_for(&asset_id);
+		ensure!(curve == Curve::ConstantProduct, Error::<T>::UnsupportedPricingCurve);
+
+		let fee_rate = Self::fee_rate_for(&asset_id);
+		let (deposit_reserve, other_reserve) = if deposit_asset == core_asset_id {
+			(core_reserve, asset_reserve)
+		} else {
+			(asset_reserve, core_reserve)
+		};
+		let swap_amount = Self::calculate_single_sided_swap(amount, deposit_reserve, fee_rate)?;
+		ensure!(!swap_amount.is_zero(), Error::<T>::CannotTradeZero);
+		let swap_proceeds = Self::calculate_sell_price(swap_amount, deposit_reserve, other_reserve, fee_rate, curve)?;
+
+		// The exchange reserves after the sale, the protocol fee is set aside from the amount sold
+		let protocol_fee = Self::calculate_protocol_fee(swap_amount, fee_rate, Self::protocol_fee_share());
+		let deposit_reserve = deposit_reserve + swap_amount - protocol_fee;
+		let other_reserve = other_reserve - swap_proceeds;
+		let (core_available, asset_available, core_reserve, asset_reserve) = if deposit_asset == core_asset_id {
+			(amount - swap_amount, swap_proceeds, deposit_reserve, other_reserve)
+		} else {
+			(swap_proceeds, amount - swap_amount, other_reserve, deposit_reserve)
+		};
+		ensure!(asset_available > One::one(), Error::<T>::CannotAddLiquidityWithZero);
+
+		let core_amount = core_available.min((asset_available - One::one()) * core_reserve / asset_reserve);
+		ensure!(!core_amount.is_zero(), Error::<T>::CannotAddLiquidityWithZero);
+		let trade_asset_amount = core_amount * asset_reserve / core_reserve + One::one();
+		let liquidity_minted = core_amount * total_liquidity / core_reserve;
+
+		Ok(SingleSidedDeposit {
+			swap_amount,
+			swap_proceeds,
+			core: core_amount,
+			asset: trade_asset_amount,
+			liquidity: liquidity_minted,
+		})
+	}
+
+	/// Ensure the sale and the deposit of a single sided `deposit` by `who` can both be made,
+	/// so a deposit which can't be made never leaves the sale behind
+	fn ensure_single_sided_deposit_allowed(
+		who: &T::AccountId,
+		asset_id: T::AssetId,
+		deposit_asset: T::AssetId,
+		deposit: &SingleSidedDeposit<T::Balance>,
+	) -> DispatchResult {
+		let core_asset_id = Self::core_asset_id();
+		Self::ensure_exchange_open(&asset_id)?;
+
+		// The exchange reserves after the sale, the protocol fee is set aside from the amount sold
+		let fee_rate = Self::fee_rate_for(&asset_id);
+		let protocol_fee = Self::calculate_protocol_fee(deposit.swap_amount, fee_rate, Self::protocol_fee_share());
+		let amount_in = deposit.swap_amount.saturating_sub(protocol_fee);
+		let (core_reserve, asset_reserve) = Self::get_exchange_reserves(&asset_id);
+		let (other_asset, deposited, other_deposited, core_reserve, asset_reserve) = if deposit_asset == core_asset_id {
+			(
+				asset_id,
+				deposit.core,
+				deposit.asset,
+				core_reserve.saturating_add(amount_in),
+				asset_reserve.saturating_sub(deposit.swap_proceeds),
+			)
+		} else {
+			(
+				core_asset_id,
+				deposit.asset,
+				deposit.core,
+				core_reserve.saturating_sub(deposit.swap_proceeds),
+				asset_reserve.saturating_add(amount_in),
+			)
+		};
+		Self::ensure_price_movement_allowed(&asset_id, core_reserve, asset_reserve)?;
+
+		// `who` pays the amount sold and their deposit of `deposit_asset`, then their deposit of the
+		// other asset out of the sale's proceeds
+		let deposit_asset_paid = deposit
+			.swap_amount
+			.checked_add(&deposited)
+			.ok_or(Error::<T>::Overflow)?;
+		let deposit_asset_balance = <pallet_generic_asset::Module<T>>::free_balance(&deposit_asset, who)
+			.checked_sub(&deposit_asset_paid)
+			.ok_or(Error::<T>::InsufficientBalance)?;
+		<pallet_generic_asset::Module<T>>::ensure_can_withdraw(
+			&deposit_asset,
+			who,
+			deposit_asset_paid,
+			WithdrawReason::Transfer.into(),
+			deposit_asset_balance,
+		)?;
+		let other_asset_balance = <pallet_generic_asset::Module<T>>::free_balance(&other_asset, who)
+			.saturating_add(deposit.swap_proceeds)
+			.checked_sub(&other_deposited)
+			.ok_or(Error::<T>::InsufficientBalance)?;
+		<pallet_generic_asset::Module<T>>::ensure_can_withdraw(
+			&other_asset,
+			who,
+			other_deposited,
+			WithdrawReason::Transfer.into(),
+			other_asset_balance,
+		)
+	}
+
+	/// `amount` - Amount of the asset deposited
+	/// `deposit_reserve` - How much of the deposited asset is in the exchange
+	/// `fee_rate` - The trading fee rate of the exchange
+	/// Returns the amount to sell so the remainder and the proceeds are at the exchange's ratio after the sale
+	///
+	/// With `f` the fee scale, `r` the fee rate, `A` the reserve and `a` the amount, this is the root of
+	/// `f*x^2 + A*(2f + r)*x - a*A*(f + r) = 0`
+	fn calculate_single_sided_swap(
+		amount: T::Balance,
+		deposit_reserve: T::Balance,
+		fee_rate: FeeRate<PerMillion>,
+	) -> sp_std::result::Result<T::Balance, DispatchError> {
+		let scale = HighPrecisionUnsigned::from(LowPrecisionUnsigned::from(FeeRate::<PerMillion>::one()));
+		let rate = HighPrecisionUnsigned::from(LowPrecisionUnsigned::from(fee_rate));
+		let amount_hp = HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(amount).into());
+		let reserve_hp = HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(deposit_reserve).into());
+		let two = HighPrecisionUnsigned::from(2);
+
+		let b = reserve_hp
+			.checked_mul(scale * two + rate)
+			.ok_or::<Error<T>>(Error::<T>::Overflow)?;
+		let four_ac = amount_hp
+			.checked_mul(reserve_hp)
+			.and_then(|x| x.checked_mul(scale + rate))
+			.and_then(|x| x.checked_mul(scale * two * two))
+			.ok_or::<Error<T>>(Error::<T>::Overflow)?;
+		let discriminant = b
+			.checked_mul(b)
+			.and_then(|x| x.checked_add(four_ac))
+			.ok_or::<Error<T>>(Error::<T>::Overflow)?;
+		let swap_amount_hp = (Self::integer_sqrt(discriminant) - b) / (scale * two);
+
+		let swap_amount_result: Result<LowPrecisionUnsigned, &'static str> =
+			LowPrecisionUnsigned::try_from(swap_amount_hp);
+		ensure!(swap_amount_result.is_ok(), Error::<T>::Overflow);
+		Ok(T::UnsignedIntToBalance::from(swap_amount_result.unwrap()).into())
+	}
+
+	/// The largest integer whose square is at most `n`
+	fn integer_sqrt(n: HighPrecisionUnsigned) -> HighPrecisionUnsigned {
+		let two = HighPrecisionUnsigned::from(2);
+		if n < two {
+			return n;
+		}
+		// Newton's method, starting from a power of two above the root
+		let mut x = HighPrecisionUnsigned::from(1) << ((n.bits() + 1) / 2);
+		loop {
+			let y = (x + n / x) / two;
+			if y >= x {
+				return x;
+			}
+			x = y;
+		}
+	}
+
+	/// The Price of Liquidity for a particular `asset_id` exchange
+	///
+	/// The price includes
+	///   * a required amount of core asset
+	///   * a required amount of `asset_id`
+	///
+	/// Note: if the exchange does not exist, the cost in `asset` is 1, because the investor
+	///       determines the exchange rate
+	pub fn liquidity_price(asset_id: T::AssetId, liquidity_to_buy: T::Balance) -> LiquidityPrice<T::Balance> {
+		let core_asset_id = Self::core_asset_id();
+		let exchange_key = (core_asset_id, asset_id);
+		let total_liquidity = <TotalLiquidity<T>>::get(&exchange_key);
+		let (core_reserve, asset_reserve) = Self::get_exchange_reserves(&asset_id);
+
+		let (core_amount, asset_amount) = if total_liquidity.is_zero() || core_reserve.is_zero() {
+			// empty exchange pool
+			(liquidity_to_buy, One::one())
+		} else {
+			let core_amount = liquidity_to_buy * core_reserve / total_liquidity;
+			let asset_amount = core_amount * asset_reserve / core_reserve + One::one();
+
+			(core_amount, asset_amount)
+		};
+		LiquidityPrice {
+			core: core_amount,
+			asset: asset_amount,
+		}
+	}
+
+	/// Record a new exchange in the index of active exchanges
+	fn create_exchange(exchange_key: &ExchangeKey<T>, exchange_address: T::AccountId) {
+		if <ExchangeCreatedAt<T>>::contains_key(exchange_key) {
+			return;
+		}
+		<ExchangeCreatedAt<T>>::insert(exchange_key, <frame_system::Module<T>>::block_number());
+		<ActiveExchanges<T>>::mutate(|exchanges| exchanges.push(exchange_key.1));
+		Self::deposit_event(RawEvent::ExchangeCreated(exchange_key.1, exchange_address));
+	}
+
+	/// Remove an exchange with no liquidity, other than its locked liquidity, from the index of active exchanges
+	fn remove_exchange(exchange_key: &ExchangeKey<T>) {
+		if !<ExchangeCreatedAt<T>>::contains_key(exchange_key) {
+			return;
+		}
+		<ExchangeCreatedAt<T>>::remove(exchange_key);
+		<ActiveExchanges<T>>::mutate(|exchanges| exchanges.retain(|asset_id| *asset_id != exchange_key.1));
+		Self::deposit_event(RawEvent::ExchangeEmptied(exchange_key.1));
+	}
+
+	/// Active exchanges
+	///
+	/// Returns the reserves, total liquidity, fee rate, address and creation block of every exchange holding
+	/// liquidity, in the order they were created
+	pub fn exchanges() -> Vec<ExchangeInfo<T::AccountId, T::AssetId, T::Balance, T::BlockNumber>> {
+		let core_asset_id = Self::core_asset_id();
+		Self::active_exchanges()
+			.into_iter()
+			.map(|asset_id| {
+				let exchange_key = (core_asset_id, asset_id);
+				let (core_reserve, asset_reserve) = Self::get_exchange_reserves(&asset_id);
+				ExchangeInfo {
+					asset_id,
+					exchange_address: T::ExchangeAddressGenerator::exchange_address_for(asset_id),
+					core_reserve,
+					asset_reserve,
+					total_liquidity: Self::total_liquidity(&exchange_key),
+					fee_rate: Self::fee_rate_for(&asset_id),
+					created_at: Self::exchange_created_at(&exchange_key).unwrap_or_default(),
+				}
+			})
+			.collect()
+	}
+
+	/// Account Liquidity Value
+	///
+	/// Returns a struct containing:
+	///   * the total liquidity in an account for a given asset ID
+	///   * the total withdrawable core asset
+	///   * the total withdrawable trade asset
+	pub fn account_liquidity_value(who: &T::AccountId, asset_id: T::AssetId) -> LiquidityValue<T::Balance> {
+		let core_asset_id = Self::core_asset_id();
+		let exchange_key = (core_asset_id, asset_id);
+		let account_liquidity = <LiquidityBalance<T>>::get(&exchange_key, who);
+		Self::liquidity_value(asset_id, account_liquidity)
+	}
+
+	/// The Value of a specific amount of liquidity
+	///
+	/// Takes an `asset_id` and an amount of `liquidity_to_withdraw` and returns its value
+	/// from the exchange.
+	///
+	/// Returns a struct containing:
+	///   * the withdrawable liquidity for the given `asset_id`
+	///   * the core asset exchangable for the `liquidity_to_withdraw`
+	///   * the trade asset exchangable for the `liquidity_to_withdraw`
+	fn liquidity_value(asset_id: T::AssetId, liquidity_to_withdraw: T::Balance) -> LiquidityValue<T::Balance> {
+		let core_asset_id = Self::core_asset_id();
+		let exchange_key = (core_asset_id, asset_id);
+		let total_liquidity = <TotalLiquidity<T>>::get(&exchange_key);
+		let (core_reserve, asset_reserve) = Self::get_exchange_reserves(&asset_id);
+		Self::calculate_liquidity_value(asset_reserve, core_reserve, liquidity_to_withdraw, total_liquidity)
+	}
+
+	/// Calculate the Value of Liquidity
+	///
+	/// Simple helper function to calculate the value of liquidity
+	fn calculate_liquidity_value(
+		asset_reserve: T::Balance,
+		core_reserve: T::Balance,
+		liquidity_to_withdraw: T::Balance,
+		total_liquidity: T::Balance,
+	) -> LiquidityValue<T::Balance> {
+		if total_liquidity.is_zero() {
+			LiquidityValue {
+				liquidity: Zero::zero(),
+				core: Zero::zero(),
+				asset: Zero::zero(),
 			}
 		} else {
 			let liquidity_amount = liquidity_to_withdraw.min(total_liquidity);
@@ -470,7 +1744,13 @@ impl<T: Trait> Module<T> {
 		ensure!(buy_amount > Zero::zero(), Error::<T>::CannotTradeZero);
 
 		let (core_reserve, asset_reserve) = Self::get_exchange_reserves(asset_id);
-		Self::calculate_buy_price(buy_amount, asset_reserve, core_reserve)
+		Self::calculate_buy_price(
+			buy_amount,
+			asset_reserve,
+			core_reserve,
+			Self::fee_rate_for(asset_id),
+			Self::curve_for(asset_id),
+		)
 	}
 
 	/// `asset_id` - Trade asset
@@ -483,17 +1763,27 @@ impl<T: Trait> Module<T> {
 		ensure!(buy_amount > Zero::zero(), Error::<T>::CannotTradeZero);
 
 		let (core_reserve, asset_reserve) = Self::get_exchange_reserves(asset_id);
-		Self::calculate_buy_price(buy_amount, core_reserve, asset_reserve)
+		Self::calculate_buy_price(
+			buy_amount,
+			core_reserve,
+			asset_reserve,
+			Self::fee_rate_for(asset_id),
+			Self::curve_for(asset_id),
+		)
 	}
 
 	/// `buy_amount` - Amount to buy
 	/// `sell_reserve`- How much of the asset to sell is in the exchange
 	/// `buy_reserve` - How much of the asset to buy is in the exchange
+	/// `fee_rate` - The trading fee rate of the exchange
+	/// `curve` - The pricing curve of the exchange
 	/// Returns the amount of sellable asset is required
 	fn calculate_buy_price(
 		buy_amount: T::Balance,
 		sell_reserve: T::Balance,
 		buy_reserve: T::Balance,
+		fee_rate: FeeRate<PerMillion>,
+		curve: Curve,
 	) -> sp_std::result::Result<T::Balance, DispatchError> {
 		ensure!(
 			!sell_reserve.is_zero() && !buy_reserve.is_zero(),
@@ -504,11 +1794,9 @@ impl<T: Trait> Module<T> {
 		let buy_amount_hp = HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(buy_amount).into());
 		let buy_reserve_hp = HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(buy_reserve).into());
 		let sell_reserve_hp = HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(sell_reserve).into());
-		let denominator_hp = buy_reserve_hp - buy_amount_hp;
-		let price_hp = sell_reserve_hp
-			.saturating_mul(buy_amount_hp)
-			.checked_div(denominator_hp)
-			.ok_or::<Error<T>>(Error::<T>::DivideByZero)?;
+		let price_hp = curve
+			.buy_amount_in(buy_amount_hp, sell_reserve_hp, buy_reserve_hp)
+			.ok_or::<Error<T>>(Error::<T>::Overflow)?;
 
 		let price_lp_result: Result<LowPrecisionUnsigned, &'static str> = LowPrecisionUnsigned::try_from(price_hp);
 		ensure!(price_lp_result.is_ok(), Error::<T>::Overflow);
@@ -517,7 +1805,7 @@ impl<T: Trait> Module<T> {
 		let price_plus_one = price_lp
 			.checked_add(One::one())
 			.ok_or::<Error<T>>(Error::<T>::Overflow)?;
-		let fee_rate_plus_one = Self::fee_rate()
+		let fee_rate_plus_one = fee_rate
 			.checked_add(FeeRate::<PerMillion>::one())
 			.ok_or::<Error<T>>(Error::<T>::Overflow)?;
 		let output = fee_rate_plus_one
@@ -526,112 +1814,858 @@ impl<T: Trait> Module<T> {
 		Ok(T::UnsignedIntToBalance::from(output.into()).into())
 	}
 
-	/// Get the sell price of some asset for another
-	/// In simple terms: 'If I sell _x_ amount of asset _a_ how much of asset _b_ will I get in return?'
-	/// `asset_to_sell` is the asset to be sold
-	/// `amount_to_sell` is the amount of `asset_to_sell` to be sold
-	/// `asset_to_payout` is the asset to be paid out in exchange for the sale of `asset_to_sell` (the final sale value is given in this asset)
-	pub fn get_sell_price(
-		asset_to_sell: T::AssetId,
-		amount_to_sell: T::Balance,
-		asset_to_payout: T::AssetId,
-	) -> Result<T::Balance, DispatchError> {
-		ensure!(asset_to_sell != asset_to_payout, Error::<T>::AssetCannotSwapForItself);
+	/// Get the sell price of some asset for another
+	/// In simple terms: 'If I sell _x_ amount of asset _a_ how much of asset _b_ will I get in return?'
+	/// `asset_to_sell` is the asset to be sold
+	/// `amount_to_sell` is the amount of `asset_to_sell` to be sold
+	/// `asset_to_payout` is the asset to be paid out in exchange for the sale of `asset_to_sell` (the final sale value is given in this asset)
+	pub fn get_sell_price(
+		asset_to_sell: T::AssetId,
+		amount_to_sell: T::Balance,
+		asset_to_payout: T::AssetId,
+	) -> Result<T::Balance, DispatchError> {
+		ensure!(asset_to_sell != asset_to_payout, Error::<T>::AssetCannotSwapForItself);
+
+		// Find the value of `amount_to_sell` of `asset_to_sell` in terms of core asset
+		// (how much core asset is the sale worth?)
+		let core_asset_amount = if asset_to_sell == Self::core_asset_id() {
+			amount_to_sell
+		} else {
+			Self::get_asset_to_core_sell_price(&asset_to_sell, amount_to_sell)?
+		};
+
+		// Skip payout asset price if asset to be paid out is core
+		// (how much `asset_to_payout` is the sale worth?)
+		let payout_asset_value = if asset_to_payout == Self::core_asset_id() {
+			core_asset_amount
+		} else {
+			Self::get_core_to_asset_sell_price(&asset_to_payout, core_asset_amount)?
+		};
+
+		Ok(payout_asset_value)
+	}
+
+	/// `asset_id` - Trade asset
+	/// `amount_sold` - Amount of the trade asset to sell
+	/// Returns amount of core that can be bought with input assets.
+	pub fn get_asset_to_core_sell_price(
+		asset_id: &T::AssetId,
+		sell_amount: T::Balance,
+	) -> sp_std::result::Result<T::Balance, DispatchError> {
+		ensure!(sell_amount > Zero::zero(), Error::<T>::CannotTradeZero);
+
+		let (core_reserve, asset_reserve) = Self::get_exchange_reserves(asset_id);
+		Self::calculate_sell_price(
+			sell_amount,
+			asset_reserve,
+			core_reserve,
+			Self::fee_rate_for(asset_id),
+			Self::curve_for(asset_id),
+		)
+	}
+
+	/// Returns the amount of trade asset to pay for `sell_amount` of core sold.
+	///
+	/// `asset_id` - Trade asset
+	/// `sell_amount` - Amount of input core to sell
+	pub fn get_core_to_asset_sell_price(
+		asset_id: &T::AssetId,
+		sell_amount: T::Balance,
+	) -> sp_std::result::Result<T::Balance, DispatchError> {
+		ensure!(sell_amount > Zero::zero(), Error::<T>::CannotTradeZero);
+
+		let (core_reserve, asset_reserve) = Self::get_exchange_reserves(asset_id);
+		Self::calculate_sell_price(
+			sell_amount,
+			core_reserve,
+			asset_reserve,
+			Self::fee_rate_for(asset_id),
+			Self::curve_for(asset_id),
+		)
+	}
+
+	/// `sell_amount` - Amount to sell
+	/// `sell_reserve`- How much of the asset to sell is in the exchange
+	/// `buy_reserve` - How much of the asset to buy is in the exchange
+	/// `fee_rate` - The trading fee rate of the exchange
+	/// `curve` - The pricing curve of the exchange
+	/// Returns the amount of buyable asset that would be received
+	fn calculate_sell_price(
+		sell_amount: T::Balance,
+		sell_reserve: T::Balance,
+		buy_reserve: T::Balance,
+		fee_rate: FeeRate<PerMillion>,
+		curve: Curve,
+	) -> sp_std::result::Result<T::Balance, DispatchError> {
+		ensure!(
+			!sell_reserve.is_zero() && !buy_reserve.is_zero(),
+			Error::<T>::EmptyExchangePool
+		);
+
+		let div_rate: FeeRate<PerMillion> = fee_rate
+			.checked_add(FeeRate::<PerMillion>::one())
+			.ok_or::<Error<T>>(Error::<T>::Overflow)?;
+
+		let sell_amount_scaled = FeeRate::<PerMillion>::from(T::BalanceToUnsignedInt::from(sell_amount).into())
+			.checked_div(div_rate)
+			.ok_or::<Error<T>>(Error::<T>::DivideByZero)?;
+
+		let sell_reserve_hp = HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(sell_reserve).into());
+		let buy_reserve_hp = HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(buy_reserve).into());
+		let sell_amount_scaled_hp = HighPrecisionUnsigned::from(LowPrecisionUnsigned::from(sell_amount_scaled));
+		let price_hp = curve
+			.sell_amount_out(sell_amount_scaled_hp, sell_reserve_hp, buy_reserve_hp)
+			.ok_or::<Error<T>>(Error::<T>::Overflow)?;
+
+		let price_lp_result: Result<LowPrecisionUnsigned, &'static str> = LowPrecisionUnsigned::try_from(price_hp);
+		ensure!(price_lp_result.is_ok(), Error::<T>::Overflow);
+		let price_lp = price_lp_result.unwrap();
+
+		let price = T::UnsignedIntToBalance::from(price_lp).into();
+		ensure!(buy_reserve > price, Error::<T>::InsufficientExchangePoolReserve);
+		Ok(price)
+	}
+
+	/// Get a quote for buying `amount_to_buy` of `asset_to_buy` with `asset_to_pay`
+	pub fn get_buy_quote(
+		asset_to_buy: T::AssetId,
+		amount_to_buy: T::Balance,
+		asset_to_pay: T::AssetId,
+	) -> Result<TradeQuote<T::AssetId, T::Balance>, DispatchError> {
+		Self::get_quote(&TradeInstruction::Buy {
+			recipient: None,
+			asset_to_sell: asset_to_pay,
+			asset_to_buy,
+			buy_amount: amount_to_buy,
+			maximum_sell: Zero::zero(),
+		})
+	}
+
+	/// Get a quote for selling `amount_to_sell` of `asset_to_sell` for `asset_to_payout`
+	pub fn get_sell_quote(
+		asset_to_sell: T::AssetId,
+		amount_to_sell: T::Balance,
+		asset_to_payout: T::AssetId,
+	) -> Result<TradeQuote<T::AssetId, T::Balance>, DispatchError> {
+		Self::get_quote(&TradeInstruction::Sell {
+			recipient: None,
+			asset_to_sell,
+			asset_to_buy: asset_to_payout,
+			sell_amount: amount_to_sell,
+			minimum_buy: Zero::zero(),
+		})
+	}
+
+	/// Quote `trade` against the current exchange reserves, ignoring its limit
+	fn get_quote(trade: &TradeInstructionOf<T>) -> Result<TradeQuote<T::AssetId, T::Balance>, DispatchError> {
+		let mut reserves = BTreeMap::new();
+		let (route, amounts) = Self::simulate_trade(trade, &mut reserves)?;
+		let (amount_in, amount_out) = (amounts[0], amounts[amounts.len() - 1]);
+		ensure!(!amount_out.is_zero(), Error::<T>::DivideByZero);
+
+		let spot_price_before = Self::route_spot_price(&route, &Self::get_exchange_reserves)?;
+		let spot_price_after =
+			Self::route_spot_price(&route, &|asset_id| reserves.get(asset_id).cloned().unwrap_or_default())?;
+
+		// The amount to sell for `amount_out` if no trading fees were charged
+		let mut amount_in_without_fees = amount_out;
+		for hop in route.windows(2).rev() {
+			let (sell_reserve, buy_reserve) = Self::hop_reserves(hop, &Self::get_exchange_reserves);
+			amount_in_without_fees = Self::calculate_buy_price(
+				amount_in_without_fees,
+				sell_reserve,
+				buy_reserve,
+				Default::default(),
+				Self::curve_for(&Self::hop_asset_id(hop)),
+			)?;
+		}
+
+		let to_hp = |balance: T::Balance| HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(balance).into());
+		let scale_hp = HighPrecisionUnsigned::from(PRICE_SCALE);
+		let execution_price_hp = to_hp(amount_in).saturating_mul(scale_hp) / to_hp(amount_out);
+		let execution_price_without_fees_hp =
+			to_hp(amount_in_without_fees).saturating_mul(scale_hp) / to_hp(amount_out);
+		let spot_price_before_hp = to_hp(spot_price_before);
+		let per_million_hp = HighPrecisionUnsigned::from(LowPrecisionUnsigned::from(FeeRate::<PerMillion>::one()));
+		let price_impact_hp = execution_price_without_fees_hp
+			.saturating_sub(spot_price_before_hp)
+			.saturating_mul(per_million_hp)
+			.checked_div(spot_price_before_hp)
+			.ok_or::<Error<T>>(Error::<T>::DivideByZero)?;
+
+		let execution_price_result: Result<LowPrecisionUnsigned, &'static str> =
+			LowPrecisionUnsigned::try_from(execution_price_hp);
+		let price_impact_result: Result<LowPrecisionUnsigned, &'static str> =
+			LowPrecisionUnsigned::try_from(price_impact_hp);
+		ensure!(
+			execution_price_result.is_ok() && price_impact_result.is_ok(),
+			Error::<T>::Overflow
+		);
+
+		Ok(TradeQuote {
+			route,
+			amount_in,
+			amount_out,
+			fee: amount_in.saturating_sub(amount_in_without_fees),
+			spot_price_before,
+			spot_price_after,
+			execution_price: T::UnsignedIntToBalance::from(execution_price_result.unwrap()).into(),
+			price_impact: FeeRate::<PerMillion>::from(price_impact_result.unwrap()),
+		})
+	}
+
+	/// The price of one of the last asset in `route` in the first asset in `route`, scaled by `PRICE_SCALE`
+	/// `reserves` gives the (core reserve, asset reserve) of the exchange for a trade asset ID
+	fn route_spot_price(
+		route: &[T::AssetId],
+		reserves: &dyn Fn(&T::AssetId) -> (T::Balance, T::Balance),
+	) -> Result<T::Balance, DispatchError> {
+		let mut price_hp = HighPrecisionUnsigned::from(PRICE_SCALE);
+		for hop in route.windows(2) {
+			let (sell_reserve, buy_reserve) = Self::hop_reserves(hop, reserves);
+			ensure!(
+				!sell_reserve.is_zero() && !buy_reserve.is_zero(),
+				Error::<T>::EmptyExchangePool
+			);
+			let sell_reserve_hp = HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(sell_reserve).into());
+			let buy_reserve_hp = HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(buy_reserve).into());
+			// The price of the asset bought in the asset sold
+			let (numerator_hp, denominator_hp) = Self::curve_for(&Self::hop_asset_id(hop))
+				.marginal_price(buy_reserve_hp, sell_reserve_hp)
+				.ok_or::<Error<T>>(Error::<T>::Overflow)?;
+			price_hp = price_hp
+				.saturating_mul(numerator_hp)
+				.checked_div(denominator_hp)
+				.ok_or::<Error<T>>(Error::<T>::DivideByZero)?;
+		}
+
+		let price_lp_result: Result<LowPrecisionUnsigned, &'static str> = LowPrecisionUnsigned::try_from(price_hp);
+		ensure!(price_lp_result.is_ok(), Error::<T>::Overflow);
+		Ok(T::UnsignedIntToBalance::from(price_lp_result.unwrap()).into())
+	}
+
+	/// The trade asset ID of the exchange traded through when selling `hop[0]` for `hop[1]`
+	fn hop_asset_id(hop: &[T::AssetId]) -> T::AssetId {
+		if hop[0] == Self::core_asset_id() {
+			hop[1]
+		} else {
+			hop[0]
+		}
+	}
+
+	/// The (sell reserve, buy reserve) of the exchange traded through when selling `hop[0]` for `hop[1]`
+	/// `reserves` gives the (core reserve, asset reserve) of the exchange for a trade asset ID
+	fn hop_reserves(
+		hop: &[T::AssetId],
+		reserves: &dyn Fn(&T::AssetId) -> (T::Balance, T::Balance),
+	) -> (T::Balance, T::Balance) {
+		if hop[0] == Self::core_asset_id() {
+			reserves(&hop[1])
+		} else {
+			let (core_reserve, asset_reserve) = reserves(&hop[0]);
+			(asset_reserve, core_reserve)
+		}
+	}
+
+	/// Get the buy price of the last asset in `path` when trading through each exchange along `path`
+	/// In simple terms: 'If I want to buy _x_ amount of the last asset, how much of the first asset will it cost?'
+	/// `path` is the assets to trade through, starting with the asset to pay and ending with the asset to buy
+	/// `amount_to_buy` is the amount of the last asset in `path` required
+	pub fn get_buy_price_via_path(path: &[T::AssetId], amount_to_buy: T::Balance) -> Result<T::Balance, DispatchError> {
+		let amounts = Self::get_path_buy_amounts(path, amount_to_buy)?;
+		Ok(amounts[0])
+	}
+
+	/// Get the sell price of the first asset in `path` when trading through each exchange along `path`
+	/// In simple terms: 'If I sell _x_ amount of the first asset, how much of the last asset will I get in return?'
+	/// `path` is the assets to trade through, starting with the asset to sell and ending with the asset to payout
+	/// `amount_to_sell` is the amount of the first asset in `path` to be sold
+	pub fn get_sell_price_via_path(
+		path: &[T::AssetId],
+		amount_to_sell: T::Balance,
+	) -> Result<T::Balance, DispatchError> {
+		let amounts = Self::get_path_sell_amounts(path, amount_to_sell)?;
+		Ok(amounts[amounts.len() - 1])
+	}
+
+	/// Returns the amount of each asset in `path` traded when buying `amount_to_buy` of the last asset in `path`
+	fn get_path_buy_amounts(path: &[T::AssetId], amount_to_buy: T::Balance) -> Result<Vec<T::Balance>, DispatchError> {
+		Self::ensure_valid_path(path)?;
+
+		let mut amounts = vec![Zero::zero(); path.len()];
+		amounts[path.len() - 1] = amount_to_buy;
+		for i in (0..path.len() - 1).rev() {
+			amounts[i] = Self::get_buy_price(path[i + 1], amounts[i + 1], path[i])?;
+		}
+
+		Ok(amounts)
+	}
+
+	/// Returns the amount of each asset in `path` traded when selling `amount_to_sell` of the first asset in `path`
+	fn get_path_sell_amounts(
+		path: &[T::AssetId],
+		amount_to_sell: T::Balance,
+	) -> Result<Vec<T::Balance>, DispatchError> {
+		Self::ensure_valid_path(path)?;
+
+		let mut amounts = vec![Zero::zero(); path.len()];
+		amounts[0] = amount_to_sell;
+		for i in 1..path.len() {
+			amounts[i] = Self::get_sell_price(path[i - 1], amounts[i - 1], path[i])?;
+		}
+
+		Ok(amounts)
+	}
+
+	/// Check `path` is a valid route through the exchanges
+	/// Each hop must trade through a single exchange, i.e. one side of the hop is the core asset,
+	/// and each exchange may only be traded through once.
+	fn ensure_valid_path(path: &[T::AssetId]) -> DispatchResult {
+		ensure!(path.len() >= 2, Error::<T>::InvalidExchangePath);
+
+		let core_asset_id = Self::core_asset_id();
+		let mut exchanges: Vec<T::AssetId> = Vec::with_capacity(path.len() - 1);
+		for hop in path.windows(2) {
+			ensure!(hop[0] != hop[1], Error::<T>::AssetCannotSwapForItself);
+			let asset_id = if hop[0] == core_asset_id {
+				hop[1]
+			} else if hop[1] == core_asset_id {
+				hop[0]
+			} else {
+				return Err(Error::<T>::InvalidExchangePath.into());
+			};
+			ensure!(!exchanges.contains(&asset_id), Error::<T>::InvalidExchangePath);
+			exchanges.push(asset_id);
+		}
+
+		Ok(())
+	}
+
+	/// The trading fee rate of the exchange for `asset_id`
+	/// Returns the exchange specific fee rate if set, otherwise the default fee rate
+	pub fn fee_rate_for(asset_id: &T::AssetId) -> FeeRate<PerMillion> {
+		let exchange_key = (Self::core_asset_id(), *asset_id);
+		Self::exchange_fee_rate(&exchange_key).unwrap_or_else(Self::fee_rate)
+	}
+
+	/// The pricing curve of the exchange for `asset_id`
+	pub fn curve_for(asset_id: &T::AssetId) -> Curve {
+		let exchange_key = (Self::core_asset_id(), *asset_id);
+		Self::pricing_curve(&exchange_key)
+	}
+
+	/// A helper for pricing functions
+	/// Fetches the reserves from an exchange for a particular `asset_id`
+	/// Protocol fees held by the exchange until they are withdrawn are not part of its reserves.
+	fn get_exchange_reserves(asset_id: &T::AssetId) -> (T::Balance, T::Balance) {
+		let core_asset_id = Self::core_asset_id();
+		let exchange_key = (core_asset_id, *asset_id);
+		let exchange_address = T::ExchangeAddressGenerator::exchange_address_for(*asset_id);
+
+		let core_reserve = <pallet_generic_asset::Module<T>>::free_balance(&core_asset_id, &exchange_address)
+			.saturating_sub(Self::protocol_fees(&exchange_key, &core_asset_id));
+		let asset_reserve = <pallet_generic_asset::Module<T>>::free_balance(asset_id, &exchange_address)
+			.saturating_sub(Self::protocol_fees(&exchange_key, asset_id));
+		(core_reserve, asset_reserve)
+	}
+
+	/// Get the protocol fee charged when selling `amount_to_sell` of `asset_to_sell` for `asset_to_buy`
+	/// This is the protocol share of the fee paid into the first exchange of the trade, in `asset_to_sell`
+	pub fn get_protocol_fee(
+		asset_to_sell: T::AssetId,
+		amount_to_sell: T::Balance,
+		asset_to_buy: T::AssetId,
+	) -> Result<T::Balance, DispatchError> {
+		ensure!(asset_to_sell != asset_to_buy, Error::<T>::AssetCannotSwapForItself);
+		let core_asset_id = Self::core_asset_id();
+		let exchange_asset_id = if asset_to_sell == core_asset_id {
+			asset_to_buy
+		} else {
+			asset_to_sell
+		};
+		Ok(Self::calculate_protocol_fee(
+			amount_to_sell,
+			Self::fee_rate_for(&exchange_asset_id),
+			Self::protocol_fee_share(),
+		))
+	}
+
+	/// `amount_in` - The amount paid into an exchange, including the trading fee
+	/// `fee_rate` - The trading fee rate of the exchange
+	/// Returns the trading fee included in `amount_in`
+	fn calculate_trading_fee(amount_in: T::Balance, fee_rate: FeeRate<PerMillion>) -> T::Balance {
+		let amount_in = T::BalanceToUnsignedInt::from(amount_in).into();
+		let trading_fee = fee_rate
+			.checked_add(FeeRate::<PerMillion>::one())
+			.and_then(|div_rate| FeeRate::<PerMillion>::from(amount_in).checked_div(div_rate))
+			.map(|amount_without_fee| amount_in.saturating_sub(amount_without_fee.into()))
+			.unwrap_or_else(Zero::zero);
+		T::UnsignedIntToBalance::from(trading_fee).into()
+	}
+
+	/// `amount_in` - The amount paid into an exchange, including the trading fee
+	/// `fee_rate` - The trading fee rate of the exchange
+	/// `protocol_fee_share` - The share of the trading fee paid to the protocol
+	/// Returns the protocol share of the trading fee included in `amount_in`
+	fn calculate_protocol_fee(
+		amount_in: T::Balance,
+		fee_rate: FeeRate<PerMillion>,
+		protocol_fee_share: FeeRate<PerMillion>,
+	) -> T::Balance {
+		let trading_fee = T::BalanceToUnsignedInt::from(Self::calculate_trading_fee(amount_in, fee_rate)).into();
+		let protocol_fee = FeeRate::<PerMillion>::from(trading_fee)
+			.checked_mul(protocol_fee_share)
+			.map(LowPrecisionUnsigned::from)
+			.unwrap_or_else(Zero::zero);
+		T::UnsignedIntToBalance::from(protocol_fee).into()
+	}
+
+	//
+	// Price oracle
+	//
+
+	/// The time weighted average price of the exchange for `asset_id` over the last `window` blocks
+	/// Trades and liquidity changes made in the current block do not affect the price.
+	///
+	/// Returns a struct containing:
+	///   * the average price of one core asset in `asset_id`
+	///   * the average price of one `asset_id` in core asset
+	/// Both prices are scaled by `PRICE_SCALE`
+	pub fn twap(asset_id: T::AssetId, window: T::BlockNumber) -> Result<ExchangePrice<T::Balance>, DispatchError> {
+		ensure!(!window.is_zero(), Error::<T>::InvalidPriceWindow);
+		let now = <frame_system::Module<T>>::block_number();
+		ensure!(now >= window, Error::<T>::InsufficientPriceHistory);
+
+		let exchange_key = (Self::core_asset_id(), asset_id);
+		let current =
+			Self::current_price_observation(&exchange_key).ok_or::<Error<T>>(Error::<T>::InsufficientPriceHistory)?;
+		let past = Self::price_observation_at(&exchange_key, now - window)
+			.ok_or::<Error<T>>(Error::<T>::InsufficientPriceHistory)?;
+
+		let window = window.saturated_into::<LowPrecisionUnsigned>();
+		let core_price = current.core_price_cumulative.wrapping_sub(past.core_price_cumulative) / window;
+		let asset_price = current.asset_price_cumulative.wrapping_sub(past.asset_price_cumulative) / window;
+
+		Ok(ExchangePrice {
+			core: T::UnsignedIntToBalance::from(core_price).into(),
+			asset: T::UnsignedIntToBalance::from(asset_price).into(),
+		})
+	}
+
+	/// Record a price observation for the exchange of `asset_id`, at most once per block
+	/// This must be called before the exchange reserves change, so that the price accumulated since the
+	/// last observation is the price the reserves have held over that period.
+	fn update_price_oracle(asset_id: &T::AssetId) {
+		let exchange_key = (Self::core_asset_id(), *asset_id);
+		let now = <frame_system::Module<T>>::block_number();
+		let (index, count) = Self::price_observation_index(&exchange_key);
+
+		let observation = match Self::price_observation(&exchange_key, index) {
+			Some(latest) if count > 0 => {
+				if latest.block_number == now {
+					return;
+				}
+				Self::accumulate_price(asset_id, latest, now)
+			}
+			_ => PriceObservation {
+				block_number: now,
+				..Default::default()
+			},
+		};
+
+		let max_observations = T::MaxPriceObservations::get().max(1);
+		let next_index = if count == 0 { 0 } else { (index + 1) % max_observations };
+		<PriceObservations<T>>::insert(&exchange_key, next_index, observation);
+		<PriceObservationIndex<T>>::insert(
+			&exchange_key,
+			(next_index, count.saturating_add(1).min(max_observations)),
+		);
+	}
+
+	/// The latest price observation of an exchange, accumulated up to the current block
+	fn current_price_observation(exchange_key: &ExchangeKey<T>) -> Option<PriceObservation<T::BlockNumber>> {
+		let (index, count) = Self::price_observation_index(exchange_key);
+		if count == 0 {
+			return None;
+		}
+		let latest = Self::price_observation(exchange_key, index)?;
+		Some(Self::accumulate_price(
+			&exchange_key.1,
+			latest,
+			<frame_system::Module<T>>::block_number(),
+		))
+	}
+
+	/// The cumulative prices of an exchange as at block `target`
+	/// Interpolates between the observations made either side of `target`, which is exact as the price
+	/// is constant between observations.
+	/// Returns `None` if the oldest observation held was made after `target`
+	fn price_observation_at(
+		exchange_key: &ExchangeKey<T>,
+		target: T::BlockNumber,
+	) -> Option<PriceObservation<T::BlockNumber>> {
+		let (index, count) = Self::price_observation_index(exchange_key);
+		if count == 0 {
+			return None;
+		}
+		let max_observations = T::MaxPriceObservations::get().max(1);
+		// the ring buffer index of the oldest observation
+		let oldest = (index + max_observations + 1 - count) % max_observations;
+		let observation = |position: u32| Self::price_observation(exchange_key, (oldest + position) % max_observations);
+
+		if observation(0)?.block_number > target {
+			return None;
+		}
+
+		// Binary search for the latest observation made at or before `target`
+		let (mut low, mut high) = (0, count - 1);
+		while low < high {
+			let mid = (low + high + 1) / 2;
+			if observation(mid)?.block_number <= target {
+				low = mid;
+			} else {
+				high = mid - 1;
+			}
+		}
+
+		let before = observation(low)?;
+		let after = if low + 1 < count {
+			observation(low + 1)?
+		} else {
+			Self::accumulate_price(&exchange_key.1, before, <frame_system::Module<T>>::block_number())
+		};
+
+		let elapsed = (after.block_number - before.block_number).saturated_into::<LowPrecisionUnsigned>();
+		if elapsed.is_zero() {
+			return Some(before);
+		}
+		let offset = (target - before.block_number).saturated_into::<LowPrecisionUnsigned>();
+		let core_price = after.core_price_cumulative.wrapping_sub(before.core_price_cumulative) / elapsed;
+		let asset_price = after.asset_price_cumulative.wrapping_sub(before.asset_price_cumulative) / elapsed;
+
+		Some(PriceObservation {
+			block_number: target,
+			core_price_cumulative: before
+				.core_price_cumulative
+				.wrapping_add(core_price.wrapping_mul(offset)),
+			asset_price_cumulative: before
+				.asset_price_cumulative
+				.wrapping_add(asset_price.wrapping_mul(offset)),
+		})
+	}
+
+	/// Accumulate the current prices of the exchange for `asset_id` onto `observation`, up to block `now`
+	fn accumulate_price(
+		asset_id: &T::AssetId,
+		observation: PriceObservation<T::BlockNumber>,
+		now: T::BlockNumber,
+	) -> PriceObservation<T::BlockNumber> {
+		let elapsed = now
+			.saturating_sub(observation.block_number)
+			.saturated_into::<LowPrecisionUnsigned>();
+		let (core_reserve, asset_reserve) = Self::get_exchange_reserves(asset_id);
+		let (core_price, asset_price) =
+			Self::calculate_spot_prices(core_reserve, asset_reserve, Self::curve_for(asset_id));
+
+		PriceObservation {
+			block_number: now,
+			core_price_cumulative: observation
+				.core_price_cumulative
+				.wrapping_add(core_price.wrapping_mul(elapsed)),
+			asset_price_cumulative: observation
+				.asset_price_cumulative
+				.wrapping_add(asset_price.wrapping_mul(elapsed)),
+		}
+	}
+
+	/// `core_reserve` - How much core asset is in the exchange
+	/// `asset_reserve` - How much trade asset is in the exchange
+	/// `curve` - The pricing curve of the exchange
+	/// Returns the price of one core asset in trade asset and the price of one trade asset in core asset,
+	/// scaled by `PRICE_SCALE`. An empty exchange has no price.
+	fn calculate_spot_prices(
+		core_reserve: T::Balance,
+		asset_reserve: T::Balance,
+		curve: Curve,
+	) -> (LowPrecisionUnsigned, LowPrecisionUnsigned) {
+		if core_reserve.is_zero() || asset_reserve.is_zero() {
+			return (Zero::zero(), Zero::zero());
+		}
+
+		let core_reserve_hp = HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(core_reserve).into());
+		let asset_reserve_hp = HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(asset_reserve).into());
+		let scale_hp = HighPrecisionUnsigned::from(PRICE_SCALE);
+		let price_hp = |base_reserve_hp, quote_reserve_hp| {
+			curve
+				.marginal_price(base_reserve_hp, quote_reserve_hp)
+				.and_then(|(numerator_hp, denominator_hp)| {
+					numerator_hp.saturating_mul(scale_hp).checked_div(denominator_hp)
+				})
+				.unwrap_or_else(HighPrecisionUnsigned::max_value)
+		};
+		let core_price_hp = price_hp(core_reserve_hp, asset_reserve_hp);
+		let asset_price_hp = price_hp(asset_reserve_hp, core_reserve_hp);
+
+		(
+			LowPrecisionUnsigned::try_from(core_price_hp).unwrap_or(LowPrecisionUnsigned::max_value()),
+			LowPrecisionUnsigned::try_from(asset_price_hp).unwrap_or(LowPrecisionUnsigned::max_value()),
+		)
+	}
+
+	//
+	// Limit orders
+	//
+
+	/// Check up to `MaxOrdersPerBlock` open orders, filling those the exchange price allows and expiring those
+	/// past their deadline. Orders left open are moved to the back of the queue.
+	fn process_limit_orders(now: T::BlockNumber) {
+		let mut open_orders = Self::open_orders();
+		if open_orders.is_empty() {
+			return;
+		}
+		let to_check = open_orders.len().min(T::MaxOrdersPerBlock::get() as usize);
+		let mut still_open = Vec::new();
+
+		for order_id in open_orders.drain(..to_check).collect::<Vec<OrderId>>() {
+			let order = match Self::limit_order(order_id) {
+				Some(order) => order,
+				None => continue,
+			};
+			if now > order.valid_until {
+				Self::close_order(order_id, &order);
+				Self::deposit_event(RawEvent::OrderExpired(order_id, order.owner));
+			} else if !Self::try_fill_order(order_id, &order) {
+				still_open.push(order_id);
+			}
+		}
+
+		open_orders.extend(still_open);
+		<OpenOrders>::put(open_orders);
+	}
+
+	/// Fill `order` if the exchange price allows it. Returns whether the order was filled.
+	fn try_fill_order(order_id: OrderId, order: &LimitOrderOf<T>) -> bool {
+		match Self::get_sell_price(order.asset_to_sell, order.sell_amount, order.asset_to_buy) {
+			Ok(amount_to_buy) if amount_to_buy >= order.minimum_buy => (),
+			_ => return false,
+		}
 
-		// Find the value of `amount_to_sell` of `asset_to_sell` in terms of core asset
-		// (how much core asset is the sale worth?)
-		let core_asset_amount = if asset_to_sell == Self::core_asset_id() {
-			amount_to_sell
-		} else {
-			Self::get_asset_to_core_sell_price(&asset_to_sell, amount_to_sell)?
-		};
+		let _ = <pallet_generic_asset::Module<T>>::unreserve(&order.asset_to_sell, &order.owner, order.sell_amount);
+		match Self::execute_sell(
+			&order.owner,
+			&order.owner,
+			&order.asset_to_sell,
+			&order.asset_to_buy,
+			order.sell_amount,
+			order.minimum_buy,
+		) {
+			Ok(amount_bought) => {
+				Self::remove_order(order_id, &order.owner);
+				Self::deposit_event(RawEvent::OrderFilled(
+					order_id,
+					order.owner.clone(),
+					order.sell_amount,
+					amount_bought,
+				));
+				true
+			}
+			Err(_) => {
+				// The trade made no changes, so the funds just released can be reserved again
+				let _ =
+					<pallet_generic_asset::Module<T>>::reserve(&order.asset_to_sell, &order.owner, order.sell_amount);
+				false
+			}
+		}
+	}
 
-		// Skip payout asset price if asset to be paid out is core
-		// (how much `asset_to_payout` is the sale worth?)
-		let payout_asset_value = if asset_to_payout == Self::core_asset_id() {
-			core_asset_amount
+	/// Remove an open order and return its reserved funds to the owner
+	/// The caller is responsible for removing `order_id` from `OpenOrders`
+	fn close_order(order_id: OrderId, order: &LimitOrderOf<T>) {
+		let _ = <pallet_generic_asset::Module<T>>::unreserve(&order.asset_to_sell, &order.owner, order.sell_amount);
+		Self::remove_order(order_id, &order.owner);
+	}
+
+	/// Remove an open order from storage and from the open order count of its `owner`
+	fn remove_order(order_id: OrderId, owner: &T::AccountId) {
+		<LimitOrders<T>>::remove(order_id);
+		let count = Self::open_order_count(owner).saturating_sub(1);
+		if count == 0 {
+			<OpenOrderCount<T>>::remove(owner);
 		} else {
-			Self::get_core_to_asset_sell_price(&asset_to_payout, core_asset_amount)?
-		};
+			<OpenOrderCount<T>>::insert(owner, count);
+		}
+	}
 
-		Ok(payout_asset_value)
+	/// Ensure trading is not paused on the exchange for `asset_id`, and its reserves aren't lent to a flash swap
+	fn ensure_exchange_open(asset_id: &T::AssetId) -> DispatchResult {
+		let exchange_key = (Self::core_asset_id(), *asset_id);
+		ensure!(!Self::is_paused(&exchange_key), Error::<T>::ExchangePaused);
+		ensure!(!Self::is_flash_swapping(&exchange_key), Error::<T>::FlashSwapInProgress);
+		Ok(())
 	}
 
-	/// `asset_id` - Trade asset
-	/// `amount_sold` - Amount of the trade asset to sell
-	/// Returns amount of core that can be bought with input assets.
-	pub fn get_asset_to_core_sell_price(
+	/// Ensure a trade leaving the exchange for `asset_id` with `core_reserve` and `asset_reserve` moves its
+	/// price no more than `MaxPriceMovement` from its price before the first trade in the current block
+	fn ensure_price_movement_allowed(
 		asset_id: &T::AssetId,
-		sell_amount: T::Balance,
-	) -> sp_std::result::Result<T::Balance, DispatchError> {
-		ensure!(sell_amount > Zero::zero(), Error::<T>::CannotTradeZero);
+		core_reserve: T::Balance,
+		asset_reserve: T::Balance,
+	) -> DispatchResult {
+		let max_movement = LowPrecisionUnsigned::from(Self::max_price_movement());
+		if max_movement.is_zero() {
+			return Ok(());
+		}
+		let opening_price = Self::opening_core_price(asset_id);
+		if opening_price.is_zero() {
+			return Ok(());
+		}
 
-		let (core_reserve, asset_reserve) = Self::get_exchange_reserves(asset_id);
-		Self::calculate_sell_price(sell_amount, asset_reserve, core_reserve)
+		let (price, _) = Self::calculate_spot_prices(core_reserve, asset_reserve, Self::curve_for(asset_id));
+		let movement = if price > opening_price {
+			price - opening_price
+		} else {
+			opening_price - price
+		};
+		let per_million_hp = HighPrecisionUnsigned::from(LowPrecisionUnsigned::from(FeeRate::<PerMillion>::one()));
+		let movement_hp =
+			HighPrecisionUnsigned::from(movement) * per_million_hp / HighPrecisionUnsigned::from(opening_price);
+		ensure!(
+			movement_hp <= HighPrecisionUnsigned::from(max_movement),
+			Error::<T>::PriceMovementLimitExceeded
+		);
+		Ok(())
 	}
 
-	/// Returns the amount of trade asset to pay for `sell_amount` of core sold.
-	///
-	/// `asset_id` - Trade asset
-	/// `sell_amount` - Amount of input core to sell
-	pub fn get_core_to_asset_sell_price(
-		asset_id: &T::AssetId,
-		sell_amount: T::Balance,
-	) -> sp_std::result::Result<T::Balance, DispatchError> {
-		ensure!(sell_amount > Zero::zero(), Error::<T>::CannotTradeZero);
+	/// The price of one core asset in trade asset on the exchange for `asset_id` before the first trade
+	/// in the current block, scaled by `PRICE_SCALE`
+	fn opening_core_price(asset_id: &T::AssetId) -> LowPrecisionUnsigned {
+		let exchange_key = (Self::core_asset_id(), *asset_id);
+		match Self::opening_price(&exchange_key) {
+			Some((block_number, price)) if block_number == <frame_system::Module<T>>::block_number() => price,
+			_ => {
+				let (core_reserve, asset_reserve) = Self::get_exchange_reserves(asset_id);
+				Self::calculate_spot_prices(core_reserve, asset_reserve, Self::curve_for(asset_id)).0
+			}
+		}
+	}
 
-		let (core_reserve, asset_reserve) = Self::get_exchange_reserves(asset_id);
-		Self::calculate_sell_price(sell_amount, core_reserve, asset_reserve)
+	/// Record the price of the exchange for `asset_id` if it is about to be traded on for the first time
+	/// in the current block. Must be called before any reserves change.
+	fn record_opening_price(asset_id: &T::AssetId) {
+		if LowPrecisionUnsigned::from(Self::max_price_movement()).is_zero() {
+			return;
+		}
+		let exchange_key = (Self::core_asset_id(), *asset_id);
+		let now = <frame_system::Module<T>>::block_number();
+		if Self::opening_price(&exchange_key).map_or(true, |(block_number, _)| block_number != now) {
+			<OpeningPrice<T>>::insert(&exchange_key, (now, Self::opening_core_price(asset_id)));
+		}
 	}
 
-	/// `sell_amount` - Amount to sell
-	/// `sell_reserve`- How much of the asset to sell is in the exchange
-	/// `buy_reserve` - How much of the asset to buy is in the exchange
-	/// Returns the amount of buyable asset that would be received
-	fn calculate_sell_price(
-		sell_amount: T::Balance,
-		sell_reserve: T::Balance,
-		buy_reserve: T::Balance,
-	) -> sp_std::result::Result<T::Balance, DispatchError> {
+	/// Ensure an exchange left with `core_reserve` and `asset_reserve` holds at least `MinimumReserve` of each
+	fn ensure_minimum_reserve(core_reserve: T::Balance, asset_reserve: T::Balance) -> DispatchResult {
+		let minimum_reserve = Self::minimum_reserve();
 		ensure!(
-			!sell_reserve.is_zero() && !buy_reserve.is_zero(),
-			Error::<T>::EmptyExchangePool
+			core_reserve >= minimum_reserve && asset_reserve >= minimum_reserve,
+			Error::<T>::MinimumReserveRequirementNotMet
 		);
+		Ok(())
+	}
 
-		let div_rate: FeeRate<PerMillion> = Self::fee_rate()
-			.checked_add(FeeRate::<PerMillion>::one())
-			.ok_or::<Error<T>>(Error::<T>::Overflow)?;
-
-		let sell_amount_scaled = FeeRate::<PerMillion>::from(T::BalanceToUnsignedInt::from(sell_amount).into())
-			.checked_div(div_rate)
-			.ok_or::<Error<T>>(Error::<T>::DivideByZero)?;
+	/// `amount` - The amount borrowed by a flash swap
+	/// `fee_rate` - The trading fee rate of the exchange lending `amount`
+	/// Returns `amount` plus the trading fee on it, rounded up
+	fn calculate_flash_swap_repayment(
+		amount: T::Balance,
+		fee_rate: FeeRate<PerMillion>,
+	) -> sp_std::result::Result<T::Balance, DispatchError> {
+		let scale = HighPrecisionUnsigned::from(LowPrecisionUnsigned::from(FeeRate::<PerMillion>::one()));
+		let rate = HighPrecisionUnsigned::from(LowPrecisionUnsigned::from(fee_rate));
+		let amount_hp = HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(amount).into());
+
+		let repayment_hp = amount_hp
+			.checked_mul(scale + rate)
+			.and_then(|x| x.checked_add(scale - HighPrecisionUnsigned::from(1)))
+			.ok_or::<Error<T>>(Error::<T>::Overflow)?
+			/ scale;
+
+		let repayment_result: Result<LowPrecisionUnsigned, &'static str> = LowPrecisionUnsigned::try_from(repayment_hp);
+		ensure!(repayment_result.is_ok(), Error::<T>::Overflow);
+		Ok(T::UnsignedIntToBalance::from(repayment_result.unwrap()).into())
+	}
 
-		let sell_reserve_hp = HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(sell_reserve).into());
-		let buy_reserve_hp = HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(buy_reserve).into());
-		let sell_amount_scaled_hp = HighPrecisionUnsigned::from(LowPrecisionUnsigned::from(sell_amount_scaled));
-		let denominator_hp = sell_amount_scaled_hp + sell_reserve_hp;
-		let price_hp = buy_reserve_hp
-			.saturating_mul(sell_amount_scaled_hp)
-			.checked_div(denominator_hp)
-			.ok_or::<Error<T>>(Error::<T>::DivideByZero)?;
+	/// Pay the `repayment` reserved from `borrower` for borrowing `amount` of `asset_id` to the exchange
+	/// The fee included in the repayment is shared between the protocol and the exchange's liquidity providers.
+	fn repay_flash_swap(
+		borrower: &T::AccountId,
+		exchange_key: &ExchangeKey<T>,
+		exchange_address: &T::AccountId,
+		asset_id: &T::AssetId,
+		amount: T::Balance,
+		repayment: T::Balance,
+		fee_rate: FeeRate<PerMillion>,
+	) -> DispatchResult {
+		if repayment.is_zero() {
+			return Ok(());
+		}
+		// Any repayment the reservation no longer covers is paid from the borrower's free balance
+		let unpaid =
+			<pallet_generic_asset::Module<T>>::repatriate_reserved(asset_id, borrower, exchange_address, repayment);
+		if !unpaid.is_zero() {
+			<pallet_generic_asset::Module<T>>::make_transfer(asset_id, borrower, exchange_address, unpaid)?;
+		}
 
-		let price_lp_result: Result<LowPrecisionUnsigned, &'static str> = LowPrecisionUnsigned::try_from(price_hp);
-		ensure!(price_lp_result.is_ok(), Error::<T>::Overflow);
-		let price_lp = price_lp_result.unwrap();
+		let protocol_fee = Self::calculate_protocol_fee(repayment, fee_rate, Self::protocol_fee_share());
+		if !protocol_fee.is_zero() {
+			<ProtocolFees<T>>::mutate(exchange_key, asset_id, |fees| *fees = fees.saturating_add(protocol_fee));
+		}
+		Self::accrue_liquidity_fee(
+			exchange_key,
+			asset_id,
+			repayment.saturating_sub(amount).saturating_sub(protocol_fee),
+		);
+		Ok(())
+	}
 
-		let price = T::UnsignedIntToBalance::from(price_lp).into();
-		ensure!(buy_reserve > price, Error::<T>::InsufficientExchangePoolReserve);
-		Ok(price)
+	/// Ensure the exchange for `asset_id` has been repaid after a flash swap, so neither of its reserves is less
+	/// than `core_reserve` and `asset_reserve` from before the swap and its constant product hasn't decreased
+	fn ensure_flash_swap_invariant(
+		asset_id: &T::AssetId,
+		core_reserve: T::Balance,
+		asset_reserve: T::Balance,
+	) -> DispatchResult {
+		let (core_reserve_after, asset_reserve_after) = Self::get_exchange_reserves(asset_id);
+		let product = |core: T::Balance, asset: T::Balance| {
+			HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(core).into())
+				* HighPrecisionUnsigned::from(T::BalanceToUnsignedInt::from(asset).into())
+		};
+		ensure!(
+			core_reserve_after >= core_reserve
+				&& asset_reserve_after >= asset_reserve
+				&& product(core_reserve_after, asset_reserve_after) >= product(core_reserve, asset_reserve),
+			Error::<T>::FlashSwapInvariantBroken
+		);
+		Ok(())
 	}
 
-	/// A helper for pricing functions
-	/// Fetches the reserves from an exchange for a particular `asset_id`
-	fn get_exchange_reserves(asset_id: &T::AssetId) -> (T::Balance, T::Balance) {
-		let exchange_address = T::ExchangeAddressGenerator::exchange_address_for(*asset_id);
+	/// Ensure `fee_rate` is less than one, so a trade always leaves something after its fee
+	fn ensure_valid_fee_rate(fee_rate: FeeRate<PerMillion>) -> DispatchResult {
+		ensure!(
+			LowPrecisionUnsigned::from(fee_rate) < LowPrecisionUnsigned::from(FeeRate::<PerMillion>::one()),
+			Error::<T>::InvalidFeeRate
+		);
+		Ok(())
+	}
 
-		let core_reserve = <pallet_generic_asset::Module<T>>::free_balance(&Self::core_asset_id(), &exchange_address);
-		let asset_reserve = <pallet_generic_asset::Module<T>>::free_balance(asset_id, &exchange_address);
-		(core_reserve, asset_reserve)
+	/// Ensure a trade with deadline `valid_until` may execute in the current block
+	fn ensure_not_expired(valid_until: Option<T::BlockNumber>) -> DispatchResult {
+		if let Some(valid_until) = valid_until {
+			ensure!(
+				<frame_system::Module<T>>::block_number() <= valid_until,
+				Error::<T>::TradeExpired
+			);
+		}
+		Ok(())
 	}
 
 	//
@@ -714,6 +2748,242 @@ impl<T: Trait> Module<T> {
 		Ok(amount_to_buy)
 	}
 
+	/// Buy `amount_to_buy` of the last asset in `path` with the first asset in `path`.
+	///
+	/// `trader` - Account selling the first asset in `path`
+	/// `recipient` - Account to receive the last asset in `path`
+	/// `path` - The assets to trade through
+	/// `amount_to_buy` - The amount of the last asset in `path` to buy
+	/// `maximum_sell` - Maximum acceptable amount of the first asset in `path` the trader will sell
+	pub fn execute_buy_via_path(
+		trader: &T::AccountId,
+		recipient: &T::AccountId,
+		path: &[T::AssetId],
+		amount_to_buy: T::Balance,
+		maximum_sell: T::Balance,
+	) -> sp_std::result::Result<T::Balance, DispatchError> {
+		// Check the sell amount meets the maximum requirement
+		let amounts = Self::get_path_buy_amounts(path, amount_to_buy)?;
+		let amount_to_sell = amounts[0];
+		ensure!(amount_to_sell <= maximum_sell, Error::<T>::MaximumSellRequirementNotMet);
+
+		// Check the trader has enough balance
+		ensure!(
+			<pallet_generic_asset::Module<T>>::free_balance(&path[0], trader) >= amount_to_sell,
+			Error::<T>::InsufficientBalance
+		);
+
+		Self::execute_path_trade(trader, recipient, path, &amounts)?;
+
+		Ok(amount_to_sell)
+	}
+
+	/// Sell `amount_to_sell` of the first asset in `path` for at least `minimum_buy` of the last asset in `path`.
+	///
+	/// `trader` - Account selling the first asset in `path`
+	/// `recipient` - Account to receive the last asset in `path`
+	/// `path` - The assets to trade through
+	/// `amount_to_sell` - The amount of the first asset in `path` to sell
+	/// `minimum_buy` - The minimum acceptable amount of the last asset in `path` to receive
+	pub fn execute_sell_via_path(
+		trader: &T::AccountId,
+		recipient: &T::AccountId,
+		path: &[T::AssetId],
+		amount_to_sell: T::Balance,
+		minimum_buy: T::Balance,
+	) -> sp_std::result::Result<T::Balance, DispatchError> {
+		// Check the trader has enough balance
+		ensure!(
+			<pallet_generic_asset::Module<T>>::free_balance(&path[0], trader) >= amount_to_sell,
+			Error::<T>::InsufficientBalance
+		);
+
+		// Check the buy amount meets the minimum requirement
+		let amounts = Self::get_path_sell_amounts(path, amount_to_sell)?;
+		let amount_to_buy = amounts[amounts.len() - 1];
+		ensure!(amount_to_buy >= minimum_buy, Error::<T>::MinimumBuyRequirementNotMet);
+
+		Self::execute_path_trade(trader, recipient, path, &amounts)?;
+
+		Ok(amount_to_buy)
+	}
+
+	/// Check every trade in a batch of trades would succeed, without making any transfers
+	/// Each trade is priced against the exchange reserves and trader balances left by the trades before it.
+	///
+	/// Returns the recipient, route and the amount of each asset along it of each trade
+	fn simulate_batch_trade(
+		trader: &T::AccountId,
+		trades: &[TradeInstructionOf<T>],
+	) -> Result<Vec<(T::AccountId, Vec<T::AssetId>, Vec<T::Balance>)>, DispatchError> {
+		let core_asset_id = Self::core_asset_id();
+		let mut reserves: BTreeMap<T::AssetId, (T::Balance, T::Balance)> = BTreeMap::new();
+		// Free balance of the trader in each asset traded
+		let mut balances: BTreeMap<T::AssetId, T::Balance> = BTreeMap::new();
+		let mut simulated_trades = Vec::with_capacity(trades.len());
+
+		for trade in trades {
+			let (path, amounts) = Self::simulate_trade(trade, &mut reserves)?;
+			for exchange_asset_id in path.iter().filter(|asset_id| **asset_id != core_asset_id) {
+				Self::ensure_exchange_open(exchange_asset_id)?;
+				if let Some((core_reserve, asset_reserve)) = reserves.get(exchange_asset_id) {
+					Self::ensure_price_movement_allowed(exchange_asset_id, *core_reserve, *asset_reserve)?;
+				}
+			}
+			let (amount_sold, amount_bought) = (amounts[0], amounts[amounts.len() - 1]);
+			let recipient = match trade {
+				TradeInstruction::Buy {
+					recipient,
+					maximum_sell,
+					..
+				} => {
+					ensure!(amount_sold <= *maximum_sell, Error::<T>::MaximumSellRequirementNotMet);
+					recipient
+				}
+				TradeInstruction::Sell {
+					recipient, minimum_buy, ..
+				} => {
+					ensure!(amount_bought >= *minimum_buy, Error::<T>::MinimumBuyRequirementNotMet);
+					recipient
+				}
+			};
+
+			let (asset_to_sell, asset_to_buy) = (path[0], path[path.len() - 1]);
+			let balance = balances
+				.entry(asset_to_sell)
+				.or_insert_with(|| <pallet_generic_asset::Module<T>>::free_balance(&asset_to_sell, trader));
+			ensure!(*balance >= amount_sold, Error::<T>::InsufficientBalance);
+			*balance -= amount_sold;
+			<pallet_generic_asset::Module<T>>::ensure_can_withdraw(
+				&asset_to_sell,
+				trader,
+				amount_sold,
+				WithdrawReason::Transfer.into(),
+				*balance,
+			)?;
+			let recipient = recipient.clone().unwrap_or_else(|| trader.clone());
+			if recipient == *trader {
+				let balance = balances
+					.entry(asset_to_buy)
+					.or_insert_with(|| <pallet_generic_asset::Module<T>>::free_balance(&asset_to_buy, trader));
+				*balance = balance.saturating_add(amount_bought);
+			}
+			simulated_trades.push((recipient, path, amounts));
+		}
+
+		Ok(simulated_trades)
+	}
+
+	/// Price `trade` against `reserves` and update `reserves` as if it was made, without checking its limit
+	/// `reserves` holds the (core reserve, asset reserve) of exchanges by trade asset ID, any exchange
+	/// missing from it is read from storage.
+	///
+	/// Returns the route of the trade and the amount of each asset along it
+	fn simulate_trade(
+		trade: &TradeInstructionOf<T>,
+		reserves: &mut BTreeMap<T::AssetId, (T::Balance, T::Balance)>,
+	) -> Result<(Vec<T::AssetId>, Vec<T::Balance>), DispatchError> {
+		let core_asset_id = Self::core_asset_id();
+		let (asset_to_sell, asset_to_buy) = match trade {
+			TradeInstruction::Buy {
+				asset_to_sell,
+				asset_to_buy,
+				..
+			}
+			| TradeInstruction::Sell {
+				asset_to_sell,
+				asset_to_buy,
+				..
+			} => (*asset_to_sell, *asset_to_buy),
+		};
+		ensure!(asset_to_sell != asset_to_buy, Error::<T>::AssetCannotSwapForItself);
+		let path = if asset_to_sell == core_asset_id || asset_to_buy == core_asset_id {
+			vec![asset_to_sell, asset_to_buy]
+		} else {
+			vec![asset_to_sell, core_asset_id, asset_to_buy]
+		};
+		// The (trade asset ID, sell reserve, buy reserve, fee rate, curve) of the exchange for each hop
+		let hops: Vec<(T::AssetId, T::Balance, T::Balance, FeeRate<PerMillion>, Curve)> = path
+			.windows(2)
+			.map(|hop| {
+				let exchange_asset_id = Self::hop_asset_id(hop);
+				let (core_reserve, asset_reserve) = *reserves
+					.entry(exchange_asset_id)
+					.or_insert_with(|| Self::get_exchange_reserves(&exchange_asset_id));
+				let fee_rate = Self::fee_rate_for(&exchange_asset_id);
+				let curve = Self::curve_for(&exchange_asset_id);
+				if hop[0] == core_asset_id {
+					(exchange_asset_id, core_reserve, asset_reserve, fee_rate, curve)
+				} else {
+					(exchange_asset_id, asset_reserve, core_reserve, fee_rate, curve)
+				}
+			})
+			.collect();
+
+		let mut amounts = vec![Zero::zero(); path.len()];
+		match trade {
+			TradeInstruction::Buy { buy_amount, .. } => {
+				amounts[path.len() - 1] = *buy_amount;
+				for (i, (_, sell_reserve, buy_reserve, fee_rate, curve)) in hops.iter().enumerate().rev() {
+					ensure!(amounts[i + 1] > Zero::zero(), Error::<T>::CannotTradeZero);
+					amounts[i] =
+						Self::calculate_buy_price(amounts[i + 1], *sell_reserve, *buy_reserve, *fee_rate, *curve)?;
+				}
+			}
+			TradeInstruction::Sell { sell_amount, .. } => {
+				amounts[0] = *sell_amount;
+				for (i, (_, sell_reserve, buy_reserve, fee_rate, curve)) in hops.iter().enumerate() {
+					ensure!(amounts[i] > Zero::zero(), Error::<T>::CannotTradeZero);
+					amounts[i + 1] =
+						Self::calculate_sell_price(amounts[i], *sell_reserve, *buy_reserve, *fee_rate, *curve)?;
+				}
+			}
+		}
+
+		// The protocol fee is set aside from the amount paid into each exchange
+		let protocol_fee_share = Self::protocol_fee_share();
+		for (i, (exchange_asset_id, _, _, fee_rate, _)) in hops.iter().enumerate() {
+			let amount_in = amounts[i] - Self::calculate_protocol_fee(amounts[i], *fee_rate, protocol_fee_share);
+			if let Some((core_reserve, asset_reserve)) = reserves.get_mut(exchange_asset_id) {
+				if path[i] == core_asset_id {
+					*core_reserve = core_reserve.saturating_add(amount_in);
+					*asset_reserve = asset_reserve.saturating_sub(amounts[i + 1]);
+				} else {
+					*asset_reserve = asset_reserve.saturating_add(amount_in);
+					*core_reserve = core_reserve.saturating_sub(amounts[i + 1]);
+				}
+			}
+		}
+
+		Ok((path, amounts))
+	}
+
+	/// Move the `amounts` of each asset in `path` through the exchanges along `path`
+	/// The first asset is paid by `trader` and the last asset is received by `recipient`
+	fn execute_path_trade(
+		trader: &T::AccountId,
+		recipient: &T::AccountId,
+		path: &[T::AssetId],
+		amounts: &[T::Balance],
+	) -> DispatchResult {
+		let protocol_fees = Self::make_trade_transfers(trader, recipient, path, amounts)?;
+
+		for i in 0..path.len() - 1 {
+			Self::deposit_event(RawEvent::AssetPurchase(
+				path[i],
+				path[i + 1],
+				trader.clone(),
+				amounts[i],
+				amounts[i + 1],
+				protocol_fees[i],
+			));
+		}
+
+		Ok(())
+	}
+
+	/// Trade `amount_to_sell` of `asset_to_sell` for `amount_to_buy` of `asset_to_buy`
+	/// Trades between two non-core assets are routed through the core asset, emitting one event for the trade
 	fn execute_trade(
 		trader: &T::AccountId,
 		recipient: &T::AccountId,
@@ -727,56 +2997,137 @@ impl<T: Trait> Module<T> {
 		// If either asset is core, we only need to make one exchange
 		// otherwise, we make two exchanges
 		if *asset_to_sell == core_asset_id || *asset_to_buy == core_asset_id {
-			let exchange_address = if *asset_to_buy == core_asset_id {
-				T::ExchangeAddressGenerator::exchange_address_for(*asset_to_sell)
-			} else {
-				T::ExchangeAddressGenerator::exchange_address_for(*asset_to_buy)
-			};
-			let _ = <pallet_generic_asset::Module<T>>::make_transfer(
-				&asset_to_sell,
+			Self::execute_trade_amounts(
 				trader,
-				&exchange_address,
-				amount_to_sell,
-			)
-			.and(<pallet_generic_asset::Module<T>>::make_transfer(
-				&asset_to_buy,
-				&exchange_address,
 				recipient,
-				amount_to_buy,
-			));
+				&[*asset_to_sell, *asset_to_buy],
+				&[amount_to_sell, amount_to_buy],
+			)
 		} else {
 			let core_amount = Self::get_asset_to_core_sell_price(asset_to_sell, amount_to_sell)?;
-			let exchange_address_a = T::ExchangeAddressGenerator::exchange_address_for(*asset_to_sell);
-			let exchange_address_b = T::ExchangeAddressGenerator::exchange_address_for(*asset_to_buy);
-
-			let _ = <pallet_generic_asset::Module<T>>::make_transfer(
-				asset_to_sell,
+			Self::execute_trade_amounts(
 				trader,
-				&exchange_address_a,
-				amount_to_sell,
-			)
-			.and(<pallet_generic_asset::Module<T>>::make_transfer(
-				&core_asset_id,
-				&exchange_address_a,
-				&exchange_address_b,
-				core_amount,
-			))
-			.and(<pallet_generic_asset::Module<T>>::make_transfer(
-				asset_to_buy,
-				&exchange_address_b,
 				recipient,
-				amount_to_buy,
-			));
-		};
+				&[*asset_to_sell, core_asset_id, *asset_to_buy],
+				&[amount_to_sell, core_amount, amount_to_buy],
+			)
+		}
+	}
+
+	/// Move the `amounts` of each asset in `path` through the exchanges along `path`, as a single trade
+	/// The first asset is paid by `trader` and the last asset is received by `recipient`
+	fn execute_trade_amounts(
+		trader: &T::AccountId,
+		recipient: &T::AccountId,
+		path: &[T::AssetId],
+		amounts: &[T::Balance],
+	) -> DispatchResult {
+		let protocol_fees = Self::make_trade_transfers(trader, recipient, path, amounts)?;
 
 		Self::deposit_event(RawEvent::AssetPurchase(
-			*asset_to_sell,
-			*asset_to_buy,
+			path[0],
+			path[path.len() - 1],
 			trader.clone(),
-			amount_to_sell,
-			amount_to_buy,
+			amounts[0],
+			amounts[amounts.len() - 1],
+			protocol_fees[0],
 		));
 
 		Ok(())
 	}
+
+	/// Make the transfers for a trade through the exchanges along `path`, all or nothing.
+	/// `trader` pays `amounts[0]` of `path[0]` into the first exchange, each exchange pays the next
+	/// asset on to the following exchange and the last exchange pays out to `recipient`.
+	///
+	/// Fails with `ExchangePaused` or `PriceMovementLimitExceeded` before any transfer is made if an exchange
+	/// along `path` is paused or the trade would move its price too far within the block.
+	/// The price oracle of each exchange is updated before any reserves change.
+	/// If any transfer fails the transfers already made are reversed and `TradeTransferFailed` is returned.
+	/// Otherwise the protocol share of each exchange's fee is set aside, and returned in the asset paid to it,
+	/// and the rest of the fee is credited to the exchange's liquidity providers.
+	fn make_trade_transfers(
+		trader: &T::AccountId,
+		recipient: &T::AccountId,
+		path: &[T::AssetId],
+		amounts: &[T::Balance],
+	) -> Result<Vec<T::Balance>, DispatchError> {
+		let core_asset_id = Self::core_asset_id();
+		let protocol_fee_share = Self::protocol_fee_share();
+
+		// The (trade asset ID, fee rate, protocol fee) of the exchange for each hop
+		let mut exchanges = Vec::with_capacity(path.len() - 1);
+		for i in 0..path.len() - 1 {
+			let (asset_to_sell, asset_to_buy) = (path[i], path[i + 1]);
+			let exchange_asset_id = if asset_to_buy == core_asset_id {
+				asset_to_sell
+			} else {
+				asset_to_buy
+			};
+			Self::ensure_exchange_open(&exchange_asset_id)?;
+			let fee_rate = Self::fee_rate_for(&exchange_asset_id);
+			let protocol_fee = Self::calculate_protocol_fee(amounts[i], fee_rate, protocol_fee_share);
+			let amount_in = amounts[i].saturating_sub(protocol_fee);
+			let (core_reserve, asset_reserve) = Self::get_exchange_reserves(&exchange_asset_id);
+			let (core_reserve, asset_reserve) = if asset_to_sell == core_asset_id {
+				(
+					core_reserve.saturating_add(amount_in),
+					asset_reserve.saturating_sub(amounts[i + 1]),
+				)
+			} else {
+				(
+					core_reserve.saturating_sub(amounts[i + 1]),
+					asset_reserve.saturating_add(amount_in),
+				)
+			};
+			Self::ensure_price_movement_allowed(&exchange_asset_id, core_reserve, asset_reserve)?;
+			exchanges.push((exchange_asset_id, fee_rate, protocol_fee));
+		}
+
+		let mut transfers = Vec::with_capacity(path.len());
+		let mut payer = trader.clone();
+		for (i, (exchange_asset_id, _, _)) in exchanges.iter().enumerate() {
+			let asset_to_sell = path[i];
+			Self::update_price_oracle(exchange_asset_id);
+			Self::record_opening_price(exchange_asset_id);
+			let exchange_address = T::ExchangeAddressGenerator::exchange_address_for(*exchange_asset_id);
+			transfers.push((asset_to_sell, payer, exchange_address.clone(), amounts[i]));
+			payer = exchange_address;
+		}
+		transfers.push((
+			path[path.len() - 1],
+			payer,
+			recipient.clone(),
+			amounts[amounts.len() - 1],
+		));
+
+		for (i, (asset_id, from, to, amount)) in transfers.iter().enumerate() {
+			if <pallet_generic_asset::Module<T>>::make_transfer(asset_id, from, to, *amount).is_err() {
+				// Roll back in reverse order. Every leg being reversed was paid into an exchange
+				// account which has just received the funds, so the reversal cannot fail.
+				for (asset_id, from, to, amount) in transfers[..i].iter().rev() {
+					let _ = <pallet_generic_asset::Module<T>>::make_transfer(asset_id, to, from, *amount);
+				}
+				return Err(Error::<T>::TradeTransferFailed.into());
+			}
+		}
+
+		let protocol_fees = exchanges
+			.iter()
+			.enumerate()
+			.map(|(i, &(exchange_asset_id, fee_rate, protocol_fee))| {
+				let exchange_key = (core_asset_id, exchange_asset_id);
+				if !protocol_fee.is_zero() {
+					<ProtocolFees<T>>::mutate(&exchange_key, &path[i], |fees| {
+						*fees = fees.saturating_add(protocol_fee)
+					});
+				}
+				let trading_fee = Self::calculate_trading_fee(amounts[i], fee_rate);
+				Self::accrue_liquidity_fee(&exchange_key, &path[i], trading_fee.saturating_sub(protocol_fee));
+				protocol_fee
+			})
+			.collect();
+
+		Ok(protocol_fees)
+	}
 }
@@ -26,10 +26,14 @@ use crate::{
 	types::{FeeRate, LowPrecisionUnsigned, PerMillion, PerThousand},
 	Call, GenesisConfig, Module, Trait,
 };
-use core::convert::TryFrom;
-use frame_support::{additional_traits::DummyDispatchVerifier, impl_outer_origin};
+use core::{cell::RefCell, convert::TryFrom};
+use frame_support::{
+	additional_traits::DummyDispatchVerifier,
+	impl_outer_event, impl_outer_origin,
+	traits::{EnsureOrigin, Get},
+};
 use pallet_generic_asset;
-use sp_core::{sr25519, H256};
+use sp_core::{crypto::UncheckedInto, sr25519, H256};
 use sp_runtime::{
 	testing::Header,
 	traits::{BlakeTwo256, IdentifyAccount, IdentityLookup, Verify},
@@ -42,6 +46,18 @@ impl_outer_origin! {
 	pub enum Origin for Test where system = frame_system {}
 }
 
+mod cennzx_spot {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		system,
+		pallet_generic_asset<T>,
+		cennzx_spot<T>,
+	}
+}
+
 // For testing the module, we construct most of a mock runtime. This means
 // first constructing a configuration type (`Test`) which `impl`s each of the
 // configuration traits of modules we want to use.
@@ -53,6 +69,22 @@ parameter_types! {
 	pub const MaximumBlockWeight: u32 = 1024;
 	pub const MaximumBlockLength: u32 = 2 * 1024;
 	pub const AvailableBlockRatio: Perbill = Perbill::one();
+	pub const MaxPriceObservations: u32 = 3;
+	pub const MaxOpenOrders: u32 = 4;
+	pub const MaxOpenOrdersPerAccount: u32 = 3;
+	pub const MaxOrdersPerBlock: u32 = 2;
+	pub const MaxBatchTrades: u32 = 3;
+}
+
+thread_local! {
+	static MINIMUM_LIQUIDITY: RefCell<LowPrecisionUnsigned> = RefCell::new(0);
+}
+
+pub struct MinimumLiquidity;
+impl Get<LowPrecisionUnsigned> for MinimumLiquidity {
+	fn get() -> LowPrecisionUnsigned {
+		MINIMUM_LIQUIDITY.with(|v| *v.borrow())
+	}
 }
 
 impl frame_system::Trait for Test {
@@ -65,7 +97,7 @@ impl frame_system::Trait for Test {
 	type AccountId = AccountId;
 	type Lookup = IdentityLookup<AccountId>;
 	type Header = Header;
-	type Event = ();
+	type Event = TestEvent;
 	type BlockHashCount = BlockHashCount;
 	type Doughnut = ();
 	type DelegatedDispatchVerifier = DummyDispatchVerifier<Self::Doughnut, Self::AccountId>;
@@ -79,7 +111,7 @@ impl frame_system::Trait for Test {
 impl pallet_generic_asset::Trait for Test {
 	type Balance = LowPrecisionUnsigned;
 	type AssetId = u32;
-	type Event = ();
+	type Event = TestEvent;
 }
 
 pub struct UnsignedIntToBalance(LowPrecisionUnsigned);
@@ -94,15 +126,40 @@ impl From<UnsignedIntToBalance> for LowPrecisionUnsigned {
 	}
 }
 
+pub struct ProtocolFeeRecipient;
+impl Get<AccountId> for ProtocolFeeRecipient {
+	fn get() -> AccountId {
+		H256::from_low_u64_be(100).unchecked_into()
+	}
+}
+
+// Only root may pause exchanges
+pub struct PauseOrigin;
+impl EnsureOrigin<Origin> for PauseOrigin {
+	type Success = ();
+	fn try_origin(o: Origin) -> Result<Self::Success, Origin> {
+		Err(o)
+	}
+}
+
 impl Trait for Test {
 	type Call = Call<Self>;
-	type Event = ();
+	type Event = TestEvent;
 	type ExchangeAddressGenerator = ExchangeAddressGenerator<Self>;
 	type BalanceToUnsignedInt = LowPrecisionUnsigned;
 	type UnsignedIntToBalance = UnsignedIntToBalance;
+	type MaxPriceObservations = MaxPriceObservations;
+	type MaxOpenOrders = MaxOpenOrders;
+	type MaxOpenOrdersPerAccount = MaxOpenOrdersPerAccount;
+	type MaxOrdersPerBlock = MaxOrdersPerBlock;
+	type MaxBatchTrades = MaxBatchTrades;
+	type ProtocolFeeRecipient = ProtocolFeeRecipient;
+	type PauseOrigin = PauseOrigin;
+	type MinimumLiquidity = MinimumLiquidity;
 }
 
 pub type CennzXSpot = Module<Test>;
+pub type System = frame_system::Module<Test>;
 
 pub const CORE_ASSET_ID: u32 = 0;
 pub const TRADE_ASSET_A_ID: u32 = 1;
@@ -152,27 +209,44 @@ impl<T: Trait> AssetIdAuthority for FeeAssetIdProvider<T> {
 
 pub struct ExtBuilder {
 	core_asset_id: u32,
+	staking_asset_id: u32,
 	fee_rate: FeeRate<PerMillion>,
+	minimum_liquidity: LowPrecisionUnsigned,
 }
 
 impl Default for ExtBuilder {
 	fn default() -> Self {
 		Self {
 			core_asset_id: 0,
+			staking_asset_id: 0,
 			fee_rate: FeeRate::<PerMillion>::try_from(FeeRate::<PerThousand>::from(3u128)).unwrap(),
+			minimum_liquidity: 0,
 		}
 	}
 }
 
 impl ExtBuilder {
+	/// Set the generic asset staking asset ID, it is the only asset affected by balance locks
+	pub fn staking_asset_id(mut self, staking_asset_id: u32) -> Self {
+		self.staking_asset_id = staking_asset_id;
+		self
+	}
+
+	/// Set the liquidity locked in an exchange by its first deposit
+	pub fn minimum_liquidity(mut self, minimum_liquidity: LowPrecisionUnsigned) -> Self {
+		self.minimum_liquidity = minimum_liquidity;
+		self
+	}
+
 	pub fn build(self) -> sp_io::TestExternalities {
+		MINIMUM_LIQUIDITY.with(|v| *v.borrow_mut() = self.minimum_liquidity);
 		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
 		pallet_generic_asset::GenesisConfig::<Test> {
 			assets: Vec::new(),
 			initial_balance: 0,
 			endowed_accounts: Vec::new(),
 			next_asset_id: 100,
-			staking_asset_id: 0,
+			staking_asset_id: self.staking_asset_id,
 			spending_asset_id: 10,
 			permissions: vec![],
 			asset_meta: vec![],
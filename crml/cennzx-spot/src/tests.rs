@@ -19,13 +19,20 @@
 use crate::{
 	impls::ExchangeAddressFor,
 	mock::{self, CORE_ASSET_ID, TRADE_ASSET_A_ID, TRADE_ASSET_B_ID},
-	types::{FeeRate, LowPrecisionUnsigned, PerMillion, PerThousand},
-	CoreAssetId, Error, Trait,
+	types::{FeeRate, LowPrecisionUnsigned, PerMillion, PerThousand, PRICE_SCALE},
+	Call, CoreAssetId, Curve, Error, Event, LimitOrder, RawEvent, TradeInstruction, Trait, BATCH_TRADE_WEIGHT,
+	LIMIT_ORDER_WEIGHT,
 };
+use codec::Encode;
 use core::convert::TryFrom;
-use frame_support::{traits::Currency, StorageValue};
-use mock::{AccountId, CennzXSpot, ExtBuilder, Origin, Test};
+use frame_support::{
+	traits::{Currency, Get, LockableCurrency, OnFinalize, OnInitialize, WithdrawReasons},
+	weights::GetDispatchInfo,
+	StorageValue,
+};
+use mock::{AccountId, CennzXSpot, ExtBuilder, Origin, System, Test, TestEvent};
 use sp_core::{crypto::UncheckedInto, H256};
+use sp_runtime::traits::BadOrigin;
 
 // Default exchange asset IDs
 const DEFAULT_EXCHANGE_KEY: (u32, u32) = (
@@ -33,11 +40,26 @@ const DEFAULT_EXCHANGE_KEY: (u32, u32) = (
 	resolve_asset_id!(TradeAssetCurrencyA),
 );
 
+// A balance lock used to force transfers from an account to fail
+const TEST_LOCK_ID: [u8; 8] = *b"testlock";
+
 // Alias the types with `Test`, for convenience
 type CoreAssetCurrency = mock::CoreAssetCurrency<Test>;
 type TradeAssetCurrencyA = mock::TradeAssetCurrencyA<Test>;
 type TradeAssetCurrencyB = mock::TradeAssetCurrencyB<Test>;
 
+/// The CENNZX-Spot events deposited so far
+/// Events are only deposited after the block number is set
+fn cennzx_events() -> Vec<Event<Test>> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			TestEvent::cennzx_spot(event) => Some(event),
+			_ => None,
+		})
+		.collect()
+}
+
 #[test]
 fn investor_can_add_liquidity() {
 	ExtBuilder::default().build().execute_with(|| {
@@ -163,12 +185,12 @@ fn remove_liquidity_fails_with_empty_pool() {
 fn calculate_buy_price_zero_cases() {
 	ExtBuilder::default().build().execute_with(|| {
 		assert_err!(
-			CennzXSpot::calculate_buy_price(100, 0, 10),
+			CennzXSpot::calculate_buy_price(100, 0, 10, CennzXSpot::fee_rate(), Curve::ConstantProduct),
 			Error::<Test>::EmptyExchangePool
 		);
 
 		assert_err!(
-			CennzXSpot::calculate_buy_price(100, 10, 0),
+			CennzXSpot::calculate_buy_price(100, 10, 0, CennzXSpot::fee_rate(), Curve::ConstantProduct),
 			Error::<Test>::EmptyExchangePool
 		);
 	});
@@ -179,10 +201,19 @@ fn calculate_buy_price_zero_cases() {
 #[test]
 fn calculate_buy_price_for_valid_data() {
 	ExtBuilder::default().build().execute_with(|| {
-		assert_ok!(CennzXSpot::calculate_buy_price(123, 1000, 1000), 141);
+		assert_ok!(
+			CennzXSpot::calculate_buy_price(123, 1000, 1000, CennzXSpot::fee_rate(), Curve::ConstantProduct),
+			141
+		);
 
 		assert_ok!(
-			CennzXSpot::calculate_buy_price(100_000_000_000_000, 120_627_710_511_649_660, 20_627_710_511_649_660,),
+			CennzXSpot::calculate_buy_price(
+				100_000_000_000_000,
+				120_627_710_511_649_660,
+				20_627_710_511_649_660,
+				CennzXSpot::fee_rate(),
+				Curve::ConstantProduct
+			),
 			589396433540516
 		);
 	});
@@ -198,6 +229,8 @@ fn calculate_buy_price_for_max_reserve_balance() {
 				LowPrecisionUnsigned::max_value() / 2,
 				LowPrecisionUnsigned::max_value() / 2,
 				LowPrecisionUnsigned::max_value(),
+				CennzXSpot::fee_rate(),
+				Curve::ConstantProduct,
 			),
 			170651607010850639426882365627031758044
 		);
@@ -215,6 +248,8 @@ fn calculate_buy_price_should_fail_with_max_reserve_and_max_amount() {
 				LowPrecisionUnsigned::max_value() - 100,
 				LowPrecisionUnsigned::max_value(),
 				LowPrecisionUnsigned::max_value(),
+				CennzXSpot::fee_rate(),
+				Curve::ConstantProduct,
 			),
 			Error::<Test>::Overflow
 		);
@@ -229,12 +264,12 @@ fn calculate_buy_price_max_withdrawal() {
 		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
 
 		assert_err!(
-			CennzXSpot::calculate_buy_price(1000, 1000, 1000),
+			CennzXSpot::calculate_buy_price(1000, 1000, 1000, CennzXSpot::fee_rate(), Curve::ConstantProduct),
 			Error::<Test>::InsufficientExchangePoolReserve
 		);
 
 		assert_err!(
-			CennzXSpot::calculate_buy_price(1_000_000, 1000, 1000),
+			CennzXSpot::calculate_buy_price(1_000_000, 1000, 1000, CennzXSpot::fee_rate(), Curve::ConstantProduct),
 			Error::<Test>::InsufficientExchangePoolReserve
 		);
 	});
@@ -992,16 +1027,31 @@ fn core_to_asset_transfer_buy_10_to_1000() {
 #[test]
 fn calculate_sell_price_for_valid_data() {
 	ExtBuilder::default().build().execute_with(|| {
-		assert_ok!(CennzXSpot::calculate_sell_price(123, 1000, 1000), 108);
+		assert_ok!(
+			CennzXSpot::calculate_sell_price(123, 1000, 1000, CennzXSpot::fee_rate(), Curve::ConstantProduct),
+			108
+		);
 
 		// No f32/f64 types, so we use large values to test precision
 		assert_ok!(
-			CennzXSpot::calculate_sell_price(123_000_000, 1_000_000_000, 1_000_000_000),
+			CennzXSpot::calculate_sell_price(
+				123_000_000,
+				1_000_000_000,
+				1_000_000_000,
+				CennzXSpot::fee_rate(),
+				Curve::ConstantProduct
+			),
 			109236233
 		);
 
 		assert_ok!(
-			CennzXSpot::calculate_sell_price(100_000_000_000_000, 120_627_710_511_649_660, 4_999_727_416_279_531_363),
+			CennzXSpot::calculate_sell_price(
+				100_000_000_000_000,
+				120_627_710_511_649_660,
+				4_999_727_416_279_531_363,
+				CennzXSpot::fee_rate(),
+				Curve::ConstantProduct
+			),
 			4128948876492407
 		);
 
@@ -1009,7 +1059,9 @@ fn calculate_sell_price_for_valid_data() {
 			CennzXSpot::calculate_sell_price(
 				100_000_000_000_000,
 				120_627_710_511_649_660,
-				LowPrecisionUnsigned::max_value()
+				LowPrecisionUnsigned::max_value(),
+				CennzXSpot::fee_rate(),
+				Curve::ConstantProduct
 			),
 			281017019450612581324176880746747822
 		);
@@ -1026,7 +1078,9 @@ fn calculate_sell_price_for_max_reserve_balance() {
 			CennzXSpot::calculate_sell_price(
 				LowPrecisionUnsigned::max_value() / 2,
 				LowPrecisionUnsigned::max_value() / 2,
-				LowPrecisionUnsigned::max_value()
+				LowPrecisionUnsigned::max_value(),
+				CennzXSpot::fee_rate(),
+				Curve::ConstantProduct
 			),
 			169886353929574869427545984738775941814
 		);
@@ -1301,6 +1355,7 @@ fn core_to_asset_transfer_sell() {
 #[test]
 fn asset_to_asset_buy() {
 	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
 		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
 		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
 		let trader: AccountId = with_account!(CoreAssetCurrency => 2200, TradeAssetCurrencyA => 2200);
@@ -1319,6 +1374,19 @@ fn asset_to_asset_buy() {
 		assert_balance_eq!(trader, TradeAssetCurrencyA => 1984);
 		assert_balance_eq!(trader, TradeAssetCurrencyB => 150);
 		assert_balance_eq!(trader, CoreAssetCurrency => 2200);
+
+		// One event is emitted for the trade, although it passes through two exchanges
+		assert_eq!(
+			cennzx_events(),
+			vec![RawEvent::AssetPurchase(
+				TRADE_ASSET_A_ID,
+				TRADE_ASSET_B_ID,
+				trader,
+				216,
+				150,
+				0
+			)]
+		);
 	});
 }
 
@@ -1531,6 +1599,164 @@ fn set_fee_rate() {
 	});
 }
 
+#[test]
+fn set_fee_rate_fails_with_invalid_fee_rate() {
+	ExtBuilder::default().build().execute_with(|| {
+		let fee_rate = CennzXSpot::fee_rate();
+		assert_noop!(
+			CennzXSpot::set_fee_rate(Origin::ROOT, FeeRate::<PerMillion>::one()),
+			Error::<Test>::InvalidFeeRate
+		);
+		assert_eq!(CennzXSpot::fee_rate(), fee_rate);
+	});
+}
+
+#[test]
+fn set_exchange_fee_rate() {
+	ExtBuilder::default().build().execute_with(|| {
+		let default_fee_rate = CennzXSpot::fee_rate();
+		let new_fee_rate = FeeRate::<PerMillion>::try_from(FeeRate::<PerThousand>::from(1u128)).unwrap();
+		System::set_block_number(1);
+		assert_ok!(CennzXSpot::set_exchange_fee_rate(
+			Origin::ROOT,
+			resolve_asset_id!(TradeAssetCurrencyA),
+			new_fee_rate
+		));
+		assert_eq!(
+			cennzx_events(),
+			vec![RawEvent::ExchangeFeeRateSet(
+				resolve_asset_id!(TradeAssetCurrencyA),
+				new_fee_rate
+			)]
+		);
+
+		assert_eq!(CennzXSpot::exchange_fee_rate(&DEFAULT_EXCHANGE_KEY), Some(new_fee_rate));
+		assert_eq!(
+			CennzXSpot::fee_rate_for(&resolve_asset_id!(TradeAssetCurrencyA)),
+			new_fee_rate
+		);
+		assert_eq!(
+			CennzXSpot::fee_rate_for(&resolve_asset_id!(TradeAssetCurrencyB)),
+			default_fee_rate
+		);
+	});
+}
+
+#[test]
+fn set_exchange_fee_rate_fails_with_invalid_fee_rate() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(
+			CennzXSpot::set_exchange_fee_rate(
+				Origin::ROOT,
+				resolve_asset_id!(TradeAssetCurrencyA),
+				FeeRate::<PerMillion>::one()
+			),
+			Error::<Test>::InvalidFeeRate
+		);
+		assert_eq!(CennzXSpot::exchange_fee_rate(&DEFAULT_EXCHANGE_KEY), None);
+	});
+}
+
+#[test]
+fn set_exchange_fee_rate_requires_root() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 100, TradeAssetCurrencyA => 100);
+		assert_noop!(
+			CennzXSpot::set_exchange_fee_rate(
+				Origin::signed(investor.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				0.into()
+			),
+			BadOrigin
+		);
+		assert_noop!(
+			CennzXSpot::clear_exchange_fee_rate(Origin::signed(investor), resolve_asset_id!(TradeAssetCurrencyA)),
+			BadOrigin
+		);
+	});
+}
+
+#[test]
+fn clear_exchange_fee_rate() {
+	ExtBuilder::default().build().execute_with(|| {
+		let new_fee_rate = FeeRate::<PerMillion>::try_from(FeeRate::<PerThousand>::from(1u128)).unwrap();
+		assert_ok!(CennzXSpot::set_exchange_fee_rate(
+			Origin::ROOT,
+			resolve_asset_id!(TradeAssetCurrencyA),
+			new_fee_rate
+		));
+		System::set_block_number(1);
+		assert_ok!(CennzXSpot::clear_exchange_fee_rate(
+			Origin::ROOT,
+			resolve_asset_id!(TradeAssetCurrencyA)
+		));
+		assert_eq!(
+			cennzx_events(),
+			vec![RawEvent::ExchangeFeeRateCleared(resolve_asset_id!(TradeAssetCurrencyA))]
+		);
+
+		assert_eq!(CennzXSpot::exchange_fee_rate(&DEFAULT_EXCHANGE_KEY), None);
+		assert_eq!(
+			CennzXSpot::fee_rate_for(&resolve_asset_id!(TradeAssetCurrencyA)),
+			CennzXSpot::fee_rate()
+		);
+	});
+}
+
+#[test]
+fn get_sell_price_with_exchange_fee_rate() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
+		let _ = CennzXSpot::set_fee_rate(Origin::ROOT, 0.into());
+		let _ = CennzXSpot::set_exchange_fee_rate(Origin::ROOT, resolve_asset_id!(TradeAssetCurrencyA), 100_000.into());
+
+		assert_eq!(
+			CennzXSpot::get_sell_price(
+				resolve_asset_id!(CoreAssetCurrency),
+				100,
+				resolve_asset_id!(TradeAssetCurrencyA),
+			),
+			Ok(82)
+		);
+		assert_eq!(
+			CennzXSpot::get_sell_price(
+				resolve_asset_id!(CoreAssetCurrency),
+				100,
+				resolve_asset_id!(TradeAssetCurrencyB),
+			),
+			Ok(90)
+		);
+	});
+}
+
+#[test]
+fn get_buy_price_with_exchange_fee_rate() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
+		let _ = CennzXSpot::set_fee_rate(Origin::ROOT, 0.into());
+		let _ = CennzXSpot::set_exchange_fee_rate(Origin::ROOT, resolve_asset_id!(TradeAssetCurrencyA), 100_000.into());
+
+		assert_eq!(
+			CennzXSpot::get_buy_price(
+				resolve_asset_id!(TradeAssetCurrencyA),
+				100,
+				resolve_asset_id!(CoreAssetCurrency),
+			),
+			Ok(123)
+		);
+		assert_eq!(
+			CennzXSpot::get_buy_price(
+				resolve_asset_id!(TradeAssetCurrencyB),
+				100,
+				resolve_asset_id!(CoreAssetCurrency),
+			),
+			Ok(112)
+		);
+	});
+}
+
 #[test]
 fn get_buy_price_simple() {
 	ExtBuilder::default().build().execute_with(|| {
@@ -1802,3 +2028,2658 @@ fn get_sell_price_no_exchange() {
 		);
 	});
 }
+
+#[test]
+fn get_buy_price_via_path() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
+		let _ = CennzXSpot::set_fee_rate(Origin::ROOT, 0.into());
+
+		assert_eq!(
+			CennzXSpot::get_buy_price_via_path(
+				&[
+					resolve_asset_id!(TradeAssetCurrencyA),
+					resolve_asset_id!(CoreAssetCurrency),
+					resolve_asset_id!(TradeAssetCurrencyB),
+				],
+				100,
+			),
+			Ok(127)
+		);
+		assert_eq!(
+			CennzXSpot::get_buy_price_via_path(
+				&[
+					resolve_asset_id!(TradeAssetCurrencyA),
+					resolve_asset_id!(CoreAssetCurrency)
+				],
+				100,
+			),
+			Ok(112)
+		);
+	});
+}
+
+#[test]
+fn get_sell_price_via_path() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
+		let _ = CennzXSpot::set_fee_rate(Origin::ROOT, 0.into());
+
+		assert_eq!(
+			CennzXSpot::get_sell_price_via_path(
+				&[
+					resolve_asset_id!(TradeAssetCurrencyB),
+					resolve_asset_id!(CoreAssetCurrency),
+					resolve_asset_id!(TradeAssetCurrencyA),
+				],
+				100,
+			),
+			Ok(82)
+		);
+		assert_eq!(
+			CennzXSpot::get_sell_price_via_path(
+				&[
+					resolve_asset_id!(CoreAssetCurrency),
+					resolve_asset_id!(TradeAssetCurrencyA)
+				],
+				100,
+			),
+			Ok(90)
+		);
+	});
+}
+
+#[test]
+fn get_price_via_invalid_path() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
+
+		// path too short
+		assert_err!(
+			CennzXSpot::get_sell_price_via_path(&[resolve_asset_id!(TradeAssetCurrencyA)], 100),
+			Error::<Test>::InvalidExchangePath
+		);
+		// hop does not trade through a single exchange
+		assert_err!(
+			CennzXSpot::get_sell_price_via_path(
+				&[
+					resolve_asset_id!(TradeAssetCurrencyA),
+					resolve_asset_id!(TradeAssetCurrencyB)
+				],
+				100
+			),
+			Error::<Test>::InvalidExchangePath
+		);
+		// exchange is traded through twice
+		assert_err!(
+			CennzXSpot::get_buy_price_via_path(
+				&[
+					resolve_asset_id!(TradeAssetCurrencyA),
+					resolve_asset_id!(CoreAssetCurrency),
+					resolve_asset_id!(TradeAssetCurrencyA),
+				],
+				100
+			),
+			Error::<Test>::InvalidExchangePath
+		);
+		assert_err!(
+			CennzXSpot::get_buy_price_via_path(
+				&[
+					resolve_asset_id!(TradeAssetCurrencyA),
+					resolve_asset_id!(TradeAssetCurrencyA)
+				],
+				100
+			),
+			Error::<Test>::AssetCannotSwapForItself
+		);
+	});
+}
+
+#[test]
+fn buy_asset_via_path() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 2200, TradeAssetCurrencyA => 2200);
+
+		assert_ok!(CennzXSpot::buy_asset_via_path(
+			Origin::signed(trader.clone()),
+			None,
+			vec![
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency),
+				resolve_asset_id!(TradeAssetCurrencyB),
+			],
+			150,  // buy_amount: T::Balance,
+			300,  // maximum asset A to sell
+			None, // valid_until
+		));
+
+		assert_exchange_balance_eq!(CoreAssetCurrency => 824, TradeAssetCurrencyA => 1216);
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1176, TradeAssetCurrencyB => 850);
+		assert_balance_eq!(trader, TradeAssetCurrencyA => 1984);
+		assert_balance_eq!(trader, TradeAssetCurrencyB => 150);
+		assert_balance_eq!(trader, CoreAssetCurrency => 2200);
+
+		// An event is emitted for each exchange the trade passes through
+		assert_eq!(
+			cennzx_events(),
+			vec![
+				RawEvent::AssetPurchase(TRADE_ASSET_A_ID, CORE_ASSET_ID, trader.clone(), 216, 176, 0),
+				RawEvent::AssetPurchase(CORE_ASSET_ID, TRADE_ASSET_B_ID, trader, 176, 150, 0),
+			]
+		);
+	});
+}
+
+#[test]
+fn buy_asset_via_path_error_exceed_max_sale() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
+		let trader = with_account!(CoreAssetCurrency => 100, TradeAssetCurrencyA => 100);
+
+		assert_err!(
+			CennzXSpot::buy_asset_via_path(
+				Origin::signed(trader),
+				None,
+				vec![
+					resolve_asset_id!(TradeAssetCurrencyA),
+					resolve_asset_id!(CoreAssetCurrency),
+					resolve_asset_id!(TradeAssetCurrencyB),
+				],
+				156,  // buy_amount
+				100,  // maximum asset A to sell
+				None, // valid_until
+			),
+			Error::<Test>::MaximumSellRequirementNotMet
+		);
+	});
+}
+
+#[test]
+fn sell_asset_via_path() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 2200, TradeAssetCurrencyA => 2200);
+		let recipient: AccountId = with_account!("bob", CoreAssetCurrency => 100, TradeAssetCurrencyB => 100);
+
+		assert_ok!(CennzXSpot::sell_asset_via_path(
+			Origin::signed(trader.clone()),
+			Some(recipient.clone()),
+			vec![
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency),
+				resolve_asset_id!(TradeAssetCurrencyB),
+			],
+			150,  // sell_amount
+			100,  // min buy limit for asset B
+			None, // valid_until
+		));
+
+		assert_exchange_balance_eq!(CoreAssetCurrency => 871, TradeAssetCurrencyA => 1150);
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1129, TradeAssetCurrencyB => 887);
+		assert_balance_eq!(trader, TradeAssetCurrencyA => 2050);
+		assert_balance_eq!(recipient, TradeAssetCurrencyB => 213);
+		assert_balance_eq!(trader, CoreAssetCurrency => 2200);
+
+		// An event is emitted for each exchange the trade passes through
+		assert_eq!(
+			cennzx_events(),
+			vec![
+				RawEvent::AssetPurchase(TRADE_ASSET_A_ID, CORE_ASSET_ID, trader.clone(), 150, 129, 0),
+				RawEvent::AssetPurchase(CORE_ASSET_ID, TRADE_ASSET_B_ID, trader, 129, 113, 0),
+			]
+		);
+	});
+}
+
+#[test]
+fn sell_asset_via_path_error_less_than_min_sale() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
+		let trader = with_account!(CoreAssetCurrency => 100, TradeAssetCurrencyA => 200);
+
+		assert_err!(
+			CennzXSpot::sell_asset_via_path(
+				Origin::signed(trader),
+				None,
+				vec![
+					resolve_asset_id!(TradeAssetCurrencyA),
+					resolve_asset_id!(CoreAssetCurrency),
+					resolve_asset_id!(TradeAssetCurrencyB),
+				],
+				156,  // sell_amount
+				200,  // min buy limit for asset B
+				None, // valid_until
+			),
+			Error::<Test>::MinimumBuyRequirementNotMet
+		);
+	});
+}
+
+#[test]
+fn core_to_asset_trade_fails_when_trader_transfer_fails() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 2200, TradeAssetCurrencyA => 2200);
+		CoreAssetCurrency::set_lock(TEST_LOCK_ID, &trader, u128::max_value(), WithdrawReasons::all());
+
+		assert_err!(
+			CennzXSpot::sell_asset(
+				Origin::signed(trader.clone()),
+				None,
+				<CoreAssetId<Test>>::get(),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				50, // sell_amount: T::Balance,
+				40, // min_sale: T::Balance,
+			),
+			Error::<Test>::TradeTransferFailed
+		);
+
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		assert_balance_eq!(trader, CoreAssetCurrency => 2200);
+		assert_balance_eq!(trader, TradeAssetCurrencyA => 2200);
+	});
+}
+
+#[test]
+fn asset_to_core_trade_rolls_back_when_exchange_transfer_fails() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 2200, TradeAssetCurrencyA => 2200);
+		let exchange_address =
+			<Test as Trait>::ExchangeAddressGenerator::exchange_address_for(resolve_asset_id!(TradeAssetCurrencyA));
+		CoreAssetCurrency::set_lock(
+			TEST_LOCK_ID,
+			&exchange_address,
+			u128::max_value(),
+			WithdrawReasons::all(),
+		);
+
+		assert_err!(
+			CennzXSpot::buy_asset(
+				Origin::signed(trader.clone()),
+				None,
+				resolve_asset_id!(TradeAssetCurrencyA),
+				<CoreAssetId<Test>>::get(),
+				50,  // buy_amount: T::Balance,
+				100, // max_sale: T::Balance,
+			),
+			Error::<Test>::TradeTransferFailed
+		);
+
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		assert_balance_eq!(trader, CoreAssetCurrency => 2200);
+		assert_balance_eq!(trader, TradeAssetCurrencyA => 2200);
+	});
+}
+
+#[test]
+fn asset_to_asset_trade_fails_when_trader_transfer_fails() {
+	ExtBuilder::default()
+		.staking_asset_id(TRADE_ASSET_A_ID)
+		.build()
+		.execute_with(|| {
+			with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+			with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
+			let trader: AccountId = with_account!(CoreAssetCurrency => 2200, TradeAssetCurrencyA => 2200);
+			TradeAssetCurrencyA::set_lock(TEST_LOCK_ID, &trader, u128::max_value(), WithdrawReasons::all());
+
+			assert_err!(
+				CennzXSpot::sell_asset(
+					Origin::signed(trader.clone()),
+					None,
+					resolve_asset_id!(TradeAssetCurrencyA), // asset_sold
+					resolve_asset_id!(TradeAssetCurrencyB), // asset_bought
+					150,                                    // sell_amount
+					100,                                    // min buy limit for asset B
+				),
+				Error::<Test>::TradeTransferFailed
+			);
+
+			assert_exchange_balance_eq!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+			assert_exchange_balance_eq!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
+			assert_balance_eq!(trader, TradeAssetCurrencyA => 2200);
+			assert_balance_eq!(trader, TradeAssetCurrencyB => 0);
+		});
+}
+
+#[test]
+fn asset_to_asset_trade_rolls_back_when_core_transfer_fails() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 2200, TradeAssetCurrencyA => 2200);
+		let exchange_address =
+			<Test as Trait>::ExchangeAddressGenerator::exchange_address_for(resolve_asset_id!(TradeAssetCurrencyA));
+		CoreAssetCurrency::set_lock(
+			TEST_LOCK_ID,
+			&exchange_address,
+			u128::max_value(),
+			WithdrawReasons::all(),
+		);
+
+		assert_err!(
+			CennzXSpot::sell_asset(
+				Origin::signed(trader.clone()),
+				None,
+				resolve_asset_id!(TradeAssetCurrencyA), // asset_sold
+				resolve_asset_id!(TradeAssetCurrencyB), // asset_bought
+				150,                                    // sell_amount
+				100,                                    // min buy limit for asset B
+			),
+			Error::<Test>::TradeTransferFailed
+		);
+
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
+		assert_balance_eq!(trader, TradeAssetCurrencyA => 2200);
+		assert_balance_eq!(trader, TradeAssetCurrencyB => 0);
+	});
+}
+
+#[test]
+fn asset_to_asset_trade_rolls_back_when_payout_transfer_fails() {
+	ExtBuilder::default()
+		.staking_asset_id(TRADE_ASSET_B_ID)
+		.build()
+		.execute_with(|| {
+			with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+			with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
+			let trader: AccountId = with_account!(CoreAssetCurrency => 2200, TradeAssetCurrencyA => 2200);
+			let exchange_address =
+				<Test as Trait>::ExchangeAddressGenerator::exchange_address_for(resolve_asset_id!(TradeAssetCurrencyB));
+			TradeAssetCurrencyB::set_lock(
+				TEST_LOCK_ID,
+				&exchange_address,
+				u128::max_value(),
+				WithdrawReasons::all(),
+			);
+
+			assert_err!(
+				CennzXSpot::buy_asset(
+					Origin::signed(trader.clone()),
+					None,
+					resolve_asset_id!(TradeAssetCurrencyA), // asset_sold
+					resolve_asset_id!(TradeAssetCurrencyB), // asset_bought
+					150,                                    // buy_amount: T::Balance,
+					300,                                    // maximum asset A to sell
+				),
+				Error::<Test>::TradeTransferFailed
+			);
+
+			assert_exchange_balance_eq!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+			assert_exchange_balance_eq!(CoreAssetCurrency => 1000, TradeAssetCurrencyB => 1000);
+			assert_balance_eq!(trader, TradeAssetCurrencyA => 2200);
+			assert_balance_eq!(trader, TradeAssetCurrencyB => 0);
+		});
+}
+
+#[test]
+fn twap_averages_prices_over_window() {
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = CennzXSpot::set_fee_rate(Origin::ROOT, 0.into());
+		let investor: AccountId = with_account!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		let trader: AccountId = with_account!("bob", CoreAssetCurrency => 1000, TradeAssetCurrencyA => 0);
+
+		frame_system::Module::<Test>::set_block_number(1);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,    // min_liquidity: T::Balance,
+			1000, // max_asset_amount: T::Balance,
+			1000, // core_amount: T::Balance,
+		));
+
+		frame_system::Module::<Test>::set_block_number(11);
+		assert_ok!(CennzXSpot::sell_asset(
+			Origin::signed(trader),
+			None,
+			resolve_asset_id!(CoreAssetCurrency),   // asset_sold
+			resolve_asset_id!(TradeAssetCurrencyA), // asset_bought
+			1000,                                   // sell_amount
+			500,                                    // min buy limit for asset A
+		));
+		assert_exchange_balance_eq!(CoreAssetCurrency => 2000, TradeAssetCurrencyA => 500);
+
+		// The trade in the current block does not affect the price
+		let price = CennzXSpot::twap(resolve_asset_id!(TradeAssetCurrencyA), 10).unwrap();
+		assert_eq!(price.core, PRICE_SCALE);
+		assert_eq!(price.asset, PRICE_SCALE);
+
+		frame_system::Module::<Test>::set_block_number(21);
+		let price = CennzXSpot::twap(resolve_asset_id!(TradeAssetCurrencyA), 20).unwrap();
+		assert_eq!(price.core, PRICE_SCALE / 8 * 5);
+		assert_eq!(price.asset, PRICE_SCALE / 2 * 5);
+
+		let price = CennzXSpot::twap(resolve_asset_id!(TradeAssetCurrencyA), 10).unwrap();
+		assert_eq!(price.core, PRICE_SCALE / 4);
+		assert_eq!(price.asset, PRICE_SCALE * 4);
+
+		// The window starts between observations
+		let price = CennzXSpot::twap(resolve_asset_id!(TradeAssetCurrencyA), 15).unwrap();
+		assert_eq!(price.core, PRICE_SCALE / 2);
+		assert_eq!(price.asset, PRICE_SCALE * 3);
+	});
+}
+
+#[test]
+fn twap_fails_with_invalid_window() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+
+		// No observations yet
+		frame_system::Module::<Test>::set_block_number(1);
+		assert_err!(
+			CennzXSpot::twap(resolve_asset_id!(TradeAssetCurrencyA), 1),
+			Error::<Test>::InsufficientPriceHistory
+		);
+
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,    // min_liquidity: T::Balance,
+			1000, // max_asset_amount: T::Balance,
+			1000, // core_amount: T::Balance,
+		));
+
+		frame_system::Module::<Test>::set_block_number(5);
+		assert_err!(
+			CennzXSpot::twap(resolve_asset_id!(TradeAssetCurrencyA), 0),
+			Error::<Test>::InvalidPriceWindow
+		);
+		assert_err!(
+			CennzXSpot::twap(resolve_asset_id!(TradeAssetCurrencyA), 6),
+			Error::<Test>::InsufficientPriceHistory
+		);
+		// The window starts before the first observation
+		assert_err!(
+			CennzXSpot::twap(resolve_asset_id!(TradeAssetCurrencyA), 5),
+			Error::<Test>::InsufficientPriceHistory
+		);
+		assert_ok!(CennzXSpot::twap(resolve_asset_id!(TradeAssetCurrencyA), 4));
+	});
+}
+
+#[test]
+fn price_observations_are_limited() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+
+		frame_system::Module::<Test>::set_block_number(1);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,   // min_liquidity: T::Balance,
+			100, // max_asset_amount: T::Balance,
+			100, // core_amount: T::Balance,
+		));
+
+		// `MaxPriceObservations` is 3 in the mock, the fourth observation replaces the first
+		for block_number in 2..=4 {
+			frame_system::Module::<Test>::set_block_number(block_number);
+			// only one observation is made per block
+			for _ in 0..2 {
+				assert_ok!(CennzXSpot::add_liquidity(
+					Origin::signed(investor.clone()),
+					resolve_asset_id!(TradeAssetCurrencyA),
+					1,  // min_liquidity: T::Balance,
+					20, // max_asset_amount: T::Balance,
+					10, // core_amount: T::Balance,
+				));
+			}
+		}
+
+		assert_eq!(CennzXSpot::price_observation_index(&DEFAULT_EXCHANGE_KEY), (0, 3));
+		assert_eq!(
+			CennzXSpot::price_observation(&DEFAULT_EXCHANGE_KEY, 0).map(|o| o.block_number),
+			Some(4)
+		);
+
+		frame_system::Module::<Test>::set_block_number(5);
+		assert_ok!(CennzXSpot::twap(resolve_asset_id!(TradeAssetCurrencyA), 3));
+		assert_err!(
+			CennzXSpot::twap(resolve_asset_id!(TradeAssetCurrencyA), 4),
+			Error::<Test>::InsufficientPriceHistory
+		);
+	});
+}
+
+#[test]
+fn trade_fails_after_deadline() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 2200, TradeAssetCurrencyA => 2200);
+
+		frame_system::Module::<Test>::set_block_number(10);
+		assert_err!(
+			CennzXSpot::buy_asset_with_deadline(
+				Origin::signed(trader.clone()),
+				None,
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency),
+				50,  // buy_amount
+				100, // max_sale
+				9,   // valid_until
+			),
+			Error::<Test>::TradeExpired
+		);
+		assert_err!(
+			CennzXSpot::sell_asset_with_deadline(
+				Origin::signed(trader.clone()),
+				None,
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency),
+				50, // sell_amount
+				1,  // min buy
+				9,  // valid_until
+			),
+			Error::<Test>::TradeExpired
+		);
+		assert_err!(
+			CennzXSpot::sell_asset_via_path(
+				Origin::signed(trader.clone()),
+				None,
+				vec![
+					resolve_asset_id!(TradeAssetCurrencyA),
+					resolve_asset_id!(CoreAssetCurrency)
+				],
+				50,      // sell_amount
+				1,       // min buy
+				Some(9), // valid_until
+			),
+			Error::<Test>::TradeExpired
+		);
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+
+		// the deadline block is still valid
+		assert_ok!(CennzXSpot::sell_asset_with_deadline(
+			Origin::signed(trader.clone()),
+			None,
+			resolve_asset_id!(TradeAssetCurrencyA),
+			resolve_asset_id!(CoreAssetCurrency),
+			50, // sell_amount
+			1,  // min buy
+			10, // valid_until
+		));
+		assert_balance_eq!(trader, TradeAssetCurrencyA => 2150);
+	});
+}
+
+#[test]
+fn original_calls_keep_their_call_indices() {
+	// New calls are appended to the module, so existing clients still encode these calls correctly
+	let call_index = |call: Call<Test>| call.encode()[0];
+	assert_eq!(call_index(Call::buy_asset(None, 1, 0, 1, 1)), 0);
+	assert_eq!(call_index(Call::sell_asset(None, 1, 0, 1, 1)), 1);
+	assert_eq!(call_index(Call::add_liquidity(1, 1, 1, 1)), 2);
+	assert_eq!(call_index(Call::remove_liquidity(1, 1, 1, 1)), 3);
+	assert_eq!(call_index(Call::set_fee_rate(0.into())), 4);
+}
+
+#[test]
+fn place_limit_order_reserves_sell_amount() {
+	ExtBuilder::default().build().execute_with(|| {
+		let trader: AccountId = with_account!(CoreAssetCurrency => 0, TradeAssetCurrencyA => 100);
+
+		frame_system::Module::<Test>::set_block_number(1);
+		assert_ok!(CennzXSpot::place_limit_order(
+			Origin::signed(trader.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			resolve_asset_id!(CoreAssetCurrency),
+			100, // sell_amount
+			150, // minimum_buy
+			10,  // valid_until
+		));
+
+		assert_eq!(
+			CennzXSpot::limit_order(0),
+			Some(LimitOrder {
+				owner: trader.clone(),
+				asset_to_sell: resolve_asset_id!(TradeAssetCurrencyA),
+				asset_to_buy: resolve_asset_id!(CoreAssetCurrency),
+				sell_amount: 100,
+				minimum_buy: 150,
+				valid_until: 10,
+			})
+		);
+		assert_eq!(CennzXSpot::open_orders(), vec![0]);
+		assert_eq!(CennzXSpot::next_order_id(), 1);
+		assert_balance_eq!(trader, TradeAssetCurrencyA => 0);
+		assert_eq!(
+			<pallet_generic_asset::Module<Test>>::reserved_balance(&resolve_asset_id!(TradeAssetCurrencyA), &trader),
+			100
+		);
+		assert_eq!(
+			cennzx_events(),
+			vec![RawEvent::OrderPlaced(
+				0,
+				trader,
+				resolve_asset_id!(TradeAssetCurrencyA),
+				100,
+				resolve_asset_id!(CoreAssetCurrency),
+				150
+			)]
+		);
+	});
+}
+
+#[test]
+fn place_limit_order_fails_with_invalid_order() {
+	ExtBuilder::default().build().execute_with(|| {
+		let trader: AccountId = with_account!(CoreAssetCurrency => 0, TradeAssetCurrencyA => 100);
+		frame_system::Module::<Test>::set_block_number(5);
+
+		assert_err!(
+			CennzXSpot::place_limit_order(
+				Origin::signed(trader.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				10, // sell_amount
+				10, // minimum_buy
+				10, // valid_until
+			),
+			Error::<Test>::AssetCannotSwapForItself
+		);
+		assert_err!(
+			CennzXSpot::place_limit_order(
+				Origin::signed(trader.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency),
+				0,  // sell_amount
+				10, // minimum_buy
+				10, // valid_until
+			),
+			Error::<Test>::CannotTradeZero
+		);
+		assert_err!(
+			CennzXSpot::place_limit_order(
+				Origin::signed(trader.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency),
+				10, // sell_amount
+				10, // minimum_buy
+				4,  // valid_until
+			),
+			Error::<Test>::TradeExpired
+		);
+		assert!(CennzXSpot::place_limit_order(
+			Origin::signed(trader.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			resolve_asset_id!(CoreAssetCurrency),
+			101, // sell_amount
+			10,  // minimum_buy
+			10,  // valid_until
+		)
+		.is_err());
+
+		// `MaxOpenOrdersPerAccount` is 3 in the mock
+		for _ in 0..3 {
+			assert_ok!(CennzXSpot::place_limit_order(
+				Origin::signed(trader.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency),
+				10, // sell_amount
+				10, // minimum_buy
+				10, // valid_until
+			));
+		}
+		assert_err!(
+			CennzXSpot::place_limit_order(
+				Origin::signed(trader.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency),
+				10, // sell_amount
+				10, // minimum_buy
+				10, // valid_until
+			),
+			Error::<Test>::TooManyOpenOrdersForAccount
+		);
+
+		// `MaxOpenOrders` is 4 in the mock
+		let other: AccountId = with_account!("bob", CoreAssetCurrency => 0, TradeAssetCurrencyA => 100);
+		assert_ok!(CennzXSpot::place_limit_order(
+			Origin::signed(other.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			resolve_asset_id!(CoreAssetCurrency),
+			10, // sell_amount
+			10, // minimum_buy
+			10, // valid_until
+		));
+		assert_err!(
+			CennzXSpot::place_limit_order(
+				Origin::signed(other),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency),
+				10, // sell_amount
+				10, // minimum_buy
+				10, // valid_until
+			),
+			Error::<Test>::TooManyOpenOrders
+		);
+	});
+}
+
+#[test]
+fn closed_limit_orders_free_the_account_order_limit() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 0, TradeAssetCurrencyA => 100);
+
+		frame_system::Module::<Test>::set_block_number(1);
+		for valid_until in vec![1, 10, 10] {
+			assert_ok!(CennzXSpot::place_limit_order(
+				Origin::signed(trader.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency),
+				10,          // sell_amount
+				1000,        // minimum_buy
+				valid_until, // valid_until
+			));
+		}
+		assert_eq!(CennzXSpot::open_order_count(&trader), 3);
+
+		// order 0 expires and order 1 is cancelled
+		CennzXSpot::on_finalize(2);
+		assert_ok!(CennzXSpot::cancel_limit_order(Origin::signed(trader.clone()), 1));
+		assert_eq!(CennzXSpot::open_order_count(&trader), 1);
+
+		assert_ok!(CennzXSpot::place_limit_order(
+			Origin::signed(trader.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			resolve_asset_id!(CoreAssetCurrency),
+			10,   // sell_amount
+			1000, // minimum_buy
+			10,   // valid_until
+		));
+		assert_eq!(CennzXSpot::open_order_count(&trader), 2);
+	});
+}
+
+#[test]
+fn on_initialize_registers_weight_of_limit_orders_checked() {
+	ExtBuilder::default().build().execute_with(|| {
+		let trader: AccountId = with_account!(CoreAssetCurrency => 0, TradeAssetCurrencyA => 100);
+		assert_eq!(CennzXSpot::on_initialize(1), 0);
+
+		frame_system::Module::<Test>::set_block_number(1);
+		for _ in 0..3 {
+			assert_ok!(CennzXSpot::place_limit_order(
+				Origin::signed(trader.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency),
+				10, // sell_amount
+				10, // minimum_buy
+				10, // valid_until
+			));
+		}
+
+		// `MaxOrdersPerBlock` is 2 in the mock
+		assert_eq!(CennzXSpot::on_initialize(2), 2 * LIMIT_ORDER_WEIGHT);
+	});
+}
+
+#[test]
+fn limit_order_filled_when_price_reaches_limit() {
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = CennzXSpot::set_fee_rate(Origin::ROOT, 0.into());
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 0, TradeAssetCurrencyA => 100);
+		let whale: AccountId = with_account!("bob", CoreAssetCurrency => 1000, TradeAssetCurrencyA => 0);
+
+		frame_system::Module::<Test>::set_block_number(1);
+		assert_ok!(CennzXSpot::place_limit_order(
+			Origin::signed(trader.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			resolve_asset_id!(CoreAssetCurrency),
+			100, // sell_amount
+			200, // minimum_buy
+			10,  // valid_until
+		));
+
+		// 100 A sells for 90 core, the order stays open
+		CennzXSpot::on_finalize(1);
+		assert!(CennzXSpot::limit_order(0).is_some());
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+
+		frame_system::Module::<Test>::set_block_number(2);
+		assert_ok!(CennzXSpot::sell_asset(
+			Origin::signed(whale.clone()),
+			None,
+			resolve_asset_id!(CoreAssetCurrency),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1000, // sell_amount
+			1,    // min buy
+		));
+		assert_exchange_balance_eq!(CoreAssetCurrency => 2000, TradeAssetCurrencyA => 500);
+
+		// 100 A now sells for 333 core
+		CennzXSpot::on_finalize(2);
+		assert_eq!(CennzXSpot::limit_order(0), None);
+		assert!(CennzXSpot::open_orders().is_empty());
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1667, TradeAssetCurrencyA => 600);
+		assert_balance_eq!(trader, CoreAssetCurrency => 333);
+		assert_balance_eq!(trader, TradeAssetCurrencyA => 0);
+		assert_eq!(
+			<pallet_generic_asset::Module<Test>>::reserved_balance(&resolve_asset_id!(TradeAssetCurrencyA), &trader),
+			0
+		);
+		assert_eq!(
+			cennzx_events(),
+			vec![
+				RawEvent::OrderPlaced(
+					0,
+					trader.clone(),
+					resolve_asset_id!(TradeAssetCurrencyA),
+					100,
+					resolve_asset_id!(CoreAssetCurrency),
+					200
+				),
+				RawEvent::AssetPurchase(CORE_ASSET_ID, TRADE_ASSET_A_ID, whale, 1000, 500, 0),
+				RawEvent::AssetPurchase(TRADE_ASSET_A_ID, CORE_ASSET_ID, trader.clone(), 100, 333, 0),
+				RawEvent::OrderFilled(0, trader, 100, 333),
+			]
+		);
+	});
+}
+
+#[test]
+fn limit_order_expires_after_deadline() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 0, TradeAssetCurrencyA => 100);
+
+		frame_system::Module::<Test>::set_block_number(1);
+		assert_ok!(CennzXSpot::place_limit_order(
+			Origin::signed(trader.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			resolve_asset_id!(CoreAssetCurrency),
+			100,  // sell_amount
+			1000, // minimum_buy
+			2,    // valid_until
+		));
+
+		CennzXSpot::on_finalize(2);
+		assert!(CennzXSpot::limit_order(0).is_some());
+
+		CennzXSpot::on_finalize(3);
+		assert_eq!(CennzXSpot::limit_order(0), None);
+		assert!(CennzXSpot::open_orders().is_empty());
+		assert_balance_eq!(trader, TradeAssetCurrencyA => 100);
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		assert_eq!(
+			cennzx_events(),
+			vec![
+				RawEvent::OrderPlaced(
+					0,
+					trader.clone(),
+					resolve_asset_id!(TradeAssetCurrencyA),
+					100,
+					resolve_asset_id!(CoreAssetCurrency),
+					1000
+				),
+				RawEvent::OrderExpired(0, trader),
+			]
+		);
+	});
+}
+
+#[test]
+fn limit_orders_checked_per_block_are_limited() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1000, TradeAssetCurrencyA => 1000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 0, TradeAssetCurrencyA => 100);
+
+		frame_system::Module::<Test>::set_block_number(1);
+		for valid_until in vec![5, 1, 1] {
+			assert_ok!(CennzXSpot::place_limit_order(
+				Origin::signed(trader.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency),
+				10,          // sell_amount
+				1000,        // minimum_buy
+				valid_until, // valid_until
+			));
+		}
+
+		// `MaxOrdersPerBlock` is 2 in the mock, open orders move to the back of the queue
+		CennzXSpot::on_finalize(2);
+		assert_eq!(CennzXSpot::open_orders(), vec![2, 0]);
+		assert_eq!(CennzXSpot::limit_order(1), None);
+
+		CennzXSpot::on_finalize(3);
+		assert_eq!(CennzXSpot::open_orders(), vec![0]);
+		assert_eq!(CennzXSpot::limit_order(2), None);
+		assert_balance_eq!(trader, TradeAssetCurrencyA => 90);
+	});
+}
+
+#[test]
+fn cancel_limit_order() {
+	ExtBuilder::default().build().execute_with(|| {
+		let trader: AccountId = with_account!(CoreAssetCurrency => 0, TradeAssetCurrencyA => 100);
+		let other: AccountId = with_account!("bob", CoreAssetCurrency => 0, TradeAssetCurrencyA => 0);
+
+		frame_system::Module::<Test>::set_block_number(1);
+		assert_ok!(CennzXSpot::place_limit_order(
+			Origin::signed(trader.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			resolve_asset_id!(CoreAssetCurrency),
+			100, // sell_amount
+			150, // minimum_buy
+			10,  // valid_until
+		));
+
+		assert_err!(
+			CennzXSpot::cancel_limit_order(Origin::signed(other), 0),
+			Error::<Test>::NotOrderOwner
+		);
+		assert_err!(
+			CennzXSpot::cancel_limit_order(Origin::signed(trader.clone()), 1),
+			Error::<Test>::OrderNotFound
+		);
+
+		assert_ok!(CennzXSpot::cancel_limit_order(Origin::signed(trader.clone()), 0));
+		assert_eq!(CennzXSpot::limit_order(0), None);
+		assert!(CennzXSpot::open_orders().is_empty());
+		assert_balance_eq!(trader, TradeAssetCurrencyA => 100);
+		assert_eq!(cennzx_events().last(), Some(&RawEvent::OrderCancelled(0, trader)));
+	});
+}
+
+#[test]
+fn exchange_created_by_first_liquidity() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 100, TradeAssetCurrencyA => 100);
+		assert!(CennzXSpot::exchanges().is_empty());
+
+		frame_system::Module::<Test>::set_block_number(3);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			2,  // min_liquidity: T::Balance,
+			15, // max_asset_amount: T::Balance,
+			10, // core_amount: T::Balance,
+		));
+
+		assert_eq!(
+			CennzXSpot::active_exchanges(),
+			vec![resolve_asset_id!(TradeAssetCurrencyA)]
+		);
+		assert_eq!(CennzXSpot::exchange_created_at(&DEFAULT_EXCHANGE_KEY), Some(3));
+
+		// further liquidity does not recreate the exchange
+		frame_system::Module::<Test>::set_block_number(4);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			2,  // min_liquidity: T::Balance,
+			16, // max_asset_amount: T::Balance,
+			10, // core_amount: T::Balance,
+		));
+
+		let exchanges = CennzXSpot::exchanges();
+		assert_eq!(exchanges.len(), 1);
+		let exchange = &exchanges[0];
+		assert_eq!(exchange.asset_id, resolve_asset_id!(TradeAssetCurrencyA));
+		assert_eq!(
+			exchange.exchange_address,
+			<Test as Trait>::ExchangeAddressGenerator::exchange_address_for(resolve_asset_id!(TradeAssetCurrencyA))
+		);
+		assert_eq!(exchange.core_reserve, 20);
+		assert_eq!(exchange.asset_reserve, 31);
+		assert_eq!(exchange.total_liquidity, 20);
+		assert_eq!(exchange.fee_rate, CennzXSpot::fee_rate());
+		assert_eq!(exchange.created_at, 3);
+
+		// The exchange is created by the first of the two deposits
+		let events = cennzx_events();
+		assert_eq!(events.len(), 3);
+		assert_eq!(
+			events[0],
+			RawEvent::ExchangeCreated(
+				resolve_asset_id!(TradeAssetCurrencyA),
+				exchange.exchange_address.clone()
+			)
+		);
+	});
+}
+
+#[test]
+fn exchange_emptied_when_all_liquidity_removed() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 100, TradeAssetCurrencyA => 100);
+		let _ = with_account!(CoreAssetCurrency => 100, TradeAssetCurrencyB => 100);
+
+		frame_system::Module::<Test>::set_block_number(1);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			2,  // min_liquidity: T::Balance,
+			15, // max_asset_amount: T::Balance,
+			10, // core_amount: T::Balance,
+		));
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyB),
+			2,  // min_liquidity: T::Balance,
+			15, // max_asset_amount: T::Balance,
+			10, // core_amount: T::Balance,
+		));
+		assert_eq!(
+			CennzXSpot::active_exchanges(),
+			vec![
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(TradeAssetCurrencyB)
+			]
+		);
+
+		// partial withdrawal keeps the exchange active
+		assert_ok!(CennzXSpot::remove_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			5, // liquidity_withdrawn
+			1, // min_asset_withdraw
+			1, // min_core_withdraw
+		));
+		assert_eq!(CennzXSpot::active_exchanges().len(), 2);
+		assert!(!cennzx_events().contains(&RawEvent::ExchangeEmptied(resolve_asset_id!(TradeAssetCurrencyA))));
+
+		assert_ok!(CennzXSpot::remove_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			5, // liquidity_withdrawn
+			1, // min_asset_withdraw
+			1, // min_core_withdraw
+		));
+		assert_eq!(
+			CennzXSpot::active_exchanges(),
+			vec![resolve_asset_id!(TradeAssetCurrencyB)]
+		);
+		assert_eq!(CennzXSpot::exchange_created_at(&DEFAULT_EXCHANGE_KEY), None);
+		assert_eq!(
+			cennzx_events().last(),
+			Some(&RawEvent::ExchangeEmptied(resolve_asset_id!(TradeAssetCurrencyA)))
+		);
+
+		// adding liquidity again creates the exchange anew
+		frame_system::Module::<Test>::set_block_number(2);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			2,  // min_liquidity: T::Balance,
+			15, // max_asset_amount: T::Balance,
+			10, // core_amount: T::Balance,
+		));
+		assert_eq!(
+			CennzXSpot::active_exchanges(),
+			vec![
+				resolve_asset_id!(TradeAssetCurrencyB),
+				resolve_asset_id!(TradeAssetCurrencyA)
+			]
+		);
+		assert_eq!(CennzXSpot::exchange_created_at(&DEFAULT_EXCHANGE_KEY), Some(2));
+		let created = RawEvent::ExchangeCreated(
+			resolve_asset_id!(TradeAssetCurrencyA),
+			<Test as Trait>::ExchangeAddressGenerator::exchange_address_for(resolve_asset_id!(TradeAssetCurrencyA)),
+		);
+		assert_eq!(cennzx_events().iter().filter(|event| **event == created).count(), 2);
+	});
+}
+
+#[test]
+fn set_protocol_fee_share() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 100, TradeAssetCurrencyA => 100);
+		assert_noop!(
+			CennzXSpot::set_protocol_fee_share(Origin::signed(investor), 500_000.into()),
+			BadOrigin
+		);
+		assert_noop!(
+			CennzXSpot::set_protocol_fee_share(Origin::ROOT, 1_000_001.into()),
+			Error::<Test>::InvalidProtocolFeeShare
+		);
+
+		assert_ok!(CennzXSpot::set_protocol_fee_share(Origin::ROOT, 500_000.into()));
+		assert_eq!(CennzXSpot::protocol_fee_share(), 500_000.into());
+	});
+}
+
+#[test]
+fn trade_accrues_protocol_fee() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		with_exchange!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 100_000, TradeAssetCurrencyA => 0);
+		assert_ok!(CennzXSpot::set_protocol_fee_share(Origin::ROOT, 500_000.into()));
+
+		// The fee on 100_000 core is 300, half goes to the protocol
+		assert_eq!(
+			CennzXSpot::get_protocol_fee(
+				resolve_asset_id!(CoreAssetCurrency),
+				100_000,
+				resolve_asset_id!(TradeAssetCurrencyA)
+			),
+			Ok(150)
+		);
+		assert_ok!(CennzXSpot::sell_asset(
+			Origin::signed(trader.clone()),
+			None,
+			resolve_asset_id!(CoreAssetCurrency),   // asset_sold
+			resolve_asset_id!(TradeAssetCurrencyA), // asset_bought
+			100_000,                                // sell_amount
+			90_661,                                 // min buy limit for asset A
+		));
+		assert_balance_eq!(trader, TradeAssetCurrencyA => 90_661);
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1_100_000, TradeAssetCurrencyA => 909_339);
+
+		// Protocol fees are held by the exchange but are not part of its reserves
+		assert_eq!(
+			CennzXSpot::protocol_fees(&DEFAULT_EXCHANGE_KEY, resolve_asset_id!(CoreAssetCurrency)),
+			150
+		);
+		assert_eq!(
+			CennzXSpot::get_exchange_reserves(&resolve_asset_id!(TradeAssetCurrencyA)),
+			(1_099_850, 909_339)
+		);
+		assert_eq!(
+			cennzx_events(),
+			vec![RawEvent::AssetPurchase(
+				CORE_ASSET_ID,
+				TRADE_ASSET_A_ID,
+				trader,
+				100_000,
+				90_661,
+				150
+			)]
+		);
+	});
+}
+
+#[test]
+fn asset_to_asset_trade_accrues_protocol_fee_in_each_exchange() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		with_exchange!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		with_exchange!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyB => 1_000_000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 0, TradeAssetCurrencyA => 100_000);
+		assert_ok!(CennzXSpot::set_protocol_fee_share(Origin::ROOT, 500_000.into()));
+
+		assert_ok!(CennzXSpot::sell_asset(
+			Origin::signed(trader.clone()),
+			None,
+			resolve_asset_id!(TradeAssetCurrencyA), // asset_sold
+			resolve_asset_id!(TradeAssetCurrencyB), // asset_bought
+			100_000,                                // sell_amount
+			1,                                      // min buy limit for asset B
+		));
+
+		// 100_000 A sells for 90_661 core in the first exchange
+		assert_eq!(
+			CennzXSpot::protocol_fees(&DEFAULT_EXCHANGE_KEY, resolve_asset_id!(TradeAssetCurrencyA)),
+			150
+		);
+		assert_eq!(
+			CennzXSpot::protocol_fees(
+				&(
+					resolve_asset_id!(CoreAssetCurrency),
+					resolve_asset_id!(TradeAssetCurrencyB)
+				),
+				resolve_asset_id!(CoreAssetCurrency)
+			),
+			136
+		);
+
+		// The event reports the protocol fee in the asset sold
+		assert_eq!(
+			cennzx_events(),
+			vec![RawEvent::AssetPurchase(
+				TRADE_ASSET_A_ID,
+				TRADE_ASSET_B_ID,
+				trader,
+				100_000,
+				82_896,
+				150
+			)]
+		);
+	});
+}
+
+#[test]
+fn withdraw_protocol_fees() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 100_000, TradeAssetCurrencyA => 100_000);
+		assert_ok!(CennzXSpot::set_protocol_fee_share(Origin::ROOT, 500_000.into()));
+
+		assert_ok!(CennzXSpot::sell_asset(
+			Origin::signed(trader.clone()),
+			None,
+			resolve_asset_id!(CoreAssetCurrency),   // asset_sold
+			resolve_asset_id!(TradeAssetCurrencyA), // asset_bought
+			100_000,                                // sell_amount
+			1,                                      // min buy limit for asset A
+		));
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1_100_000, TradeAssetCurrencyA => 909_339);
+
+		assert_ok!(CennzXSpot::withdraw_protocol_fees(
+			Origin::signed(trader),
+			resolve_asset_id!(TradeAssetCurrencyA)
+		));
+
+		let recipient = <Test as Trait>::ProtocolFeeRecipient::get();
+		assert_balance_eq!(recipient, CoreAssetCurrency => 150);
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1_099_850, TradeAssetCurrencyA => 909_339);
+		assert_eq!(
+			CennzXSpot::protocol_fees(&DEFAULT_EXCHANGE_KEY, resolve_asset_id!(CoreAssetCurrency)),
+			0
+		);
+	});
+}
+
+#[test]
+fn withdraw_protocol_fees_is_all_or_nothing() {
+	// Only the staking asset can be locked
+	ExtBuilder::default()
+		.staking_asset_id(resolve_asset_id!(TradeAssetCurrencyA))
+		.build()
+		.execute_with(|| {
+			System::set_block_number(1);
+			with_exchange!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+			let trader: AccountId = with_account!(CoreAssetCurrency => 100_000, TradeAssetCurrencyA => 100_000);
+			assert_ok!(CennzXSpot::set_protocol_fee_share(Origin::ROOT, 500_000.into()));
+
+			// Accrue protocol fees in both assets
+			assert_ok!(CennzXSpot::sell_asset(
+				Origin::signed(trader.clone()),
+				None,
+				resolve_asset_id!(CoreAssetCurrency),   // asset_sold
+				resolve_asset_id!(TradeAssetCurrencyA), // asset_bought
+				100_000,                                // sell_amount
+				1,                                      // min buy limit for asset A
+			));
+			assert_ok!(CennzXSpot::sell_asset(
+				Origin::signed(trader.clone()),
+				None,
+				resolve_asset_id!(TradeAssetCurrencyA), // asset_sold
+				resolve_asset_id!(CoreAssetCurrency),   // asset_bought
+				100_000,                                // sell_amount
+				1,                                      // min buy limit for core asset
+			));
+			let core_fees = CennzXSpot::protocol_fees(&DEFAULT_EXCHANGE_KEY, resolve_asset_id!(CoreAssetCurrency));
+			let asset_fees = CennzXSpot::protocol_fees(&DEFAULT_EXCHANGE_KEY, resolve_asset_id!(TradeAssetCurrencyA));
+			assert!(core_fees > 0 && asset_fees > 0);
+
+			// The asset fees can't be paid out, so the core fees are not either
+			let exchange_address =
+				<Test as Trait>::ExchangeAddressGenerator::exchange_address_for(resolve_asset_id!(TradeAssetCurrencyA));
+			TradeAssetCurrencyA::set_lock(
+				TEST_LOCK_ID,
+				&exchange_address,
+				u128::max_value(),
+				WithdrawReasons::all(),
+			);
+			assert!(CennzXSpot::withdraw_protocol_fees(
+				Origin::signed(trader.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA)
+			)
+			.is_err());
+
+			let recipient = <Test as Trait>::ProtocolFeeRecipient::get();
+			assert_balance_eq!(recipient, CoreAssetCurrency => 0);
+			assert_balance_eq!(recipient, TradeAssetCurrencyA => 0);
+			assert_eq!(
+				CennzXSpot::protocol_fees(&DEFAULT_EXCHANGE_KEY, resolve_asset_id!(CoreAssetCurrency)),
+				core_fees
+			);
+			assert_eq!(
+				CennzXSpot::protocol_fees(&DEFAULT_EXCHANGE_KEY, resolve_asset_id!(TradeAssetCurrencyA)),
+				asset_fees
+			);
+
+			// Once the fees can be paid out, both are withdrawn by anyone
+			TradeAssetCurrencyA::remove_lock(TEST_LOCK_ID, &exchange_address);
+			assert_ok!(CennzXSpot::withdraw_protocol_fees(
+				Origin::signed(trader),
+				resolve_asset_id!(TradeAssetCurrencyA)
+			));
+			assert_balance_eq!(recipient, CoreAssetCurrency => core_fees);
+			assert_balance_eq!(recipient, TradeAssetCurrencyA => asset_fees);
+			assert_eq!(
+				cennzx_events().last(),
+				Some(&RawEvent::ProtocolFeesWithdrawn(
+					resolve_asset_id!(TradeAssetCurrencyA),
+					core_fees,
+					asset_fees
+				))
+			);
+		});
+}
+
+#[test]
+fn add_liquidity_single_sided_with_core_asset() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId =
+			with_account!("andrea", CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,         // min_liquidity: T::Balance,
+			1_000_000, // max_asset_amount: T::Balance,
+			1_000_000, // core_amount: T::Balance,
+		));
+		let depositor: AccountId = with_account!("bob", CoreAssetCurrency => 100_000, TradeAssetCurrencyA => 0);
+
+		// 48_881 core is sold for 46_469 of asset A, then added with 51_114 core at the new ratio
+		assert_ok!(CennzXSpot::add_liquidity_single_sided(
+			Origin::signed(depositor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			resolve_asset_id!(CoreAssetCurrency), // deposit_asset
+			100_000,                              // amount
+			48_731,                               // min_liquidity
+		));
+
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1_099_995, TradeAssetCurrencyA => 999_999);
+		assert_balance_eq!(depositor, CoreAssetCurrency => 5);
+		assert_balance_eq!(depositor, TradeAssetCurrencyA => 1);
+		assert_eq!(CennzXSpot::liquidity_balance(&DEFAULT_EXCHANGE_KEY, &depositor), 48_731);
+		assert_eq!(CennzXSpot::total_liquidity(&DEFAULT_EXCHANGE_KEY), 1_048_731);
+	});
+}
+
+#[test]
+fn add_liquidity_single_sided_with_trade_asset() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId =
+			with_account!("andrea", CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,         // min_liquidity: T::Balance,
+			1_000_000, // max_asset_amount: T::Balance,
+			1_000_000, // core_amount: T::Balance,
+		));
+		let depositor: AccountId = with_account!("bob", CoreAssetCurrency => 0, TradeAssetCurrencyA => 100_000);
+
+		// 48_881 of asset A is sold for 46_469 core, then added with 51_116 of asset A at the new ratio
+		assert_ok!(CennzXSpot::add_liquidity_single_sided(
+			Origin::signed(depositor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			resolve_asset_id!(TradeAssetCurrencyA), // deposit_asset
+			100_000,                                // amount
+			48_733,                                 // min_liquidity
+		));
+
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_099_997);
+		assert_balance_eq!(depositor, CoreAssetCurrency => 0);
+		assert_balance_eq!(depositor, TradeAssetCurrencyA => 3);
+		assert_eq!(CennzXSpot::liquidity_balance(&DEFAULT_EXCHANGE_KEY, &depositor), 48_733);
+	});
+}
+
+#[test]
+fn add_liquidity_single_sided_fails_with_too_high_min_liquidity() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId =
+			with_account!("andrea", CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,         // min_liquidity: T::Balance,
+			1_000_000, // max_asset_amount: T::Balance,
+			1_000_000, // core_amount: T::Balance,
+		));
+		let depositor: AccountId = with_account!("bob", CoreAssetCurrency => 100_000, TradeAssetCurrencyA => 0);
+
+		assert_err!(
+			CennzXSpot::add_liquidity_single_sided(
+				Origin::signed(depositor.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency), // deposit_asset
+				100_000,                              // amount
+				48_732,                               // min_liquidity
+			),
+			Error::<Test>::MinimumLiquidityRequirementNotMet
+		);
+
+		// Nothing was traded
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		assert_balance_eq!(depositor, CoreAssetCurrency => 100_000);
+	});
+}
+
+#[test]
+fn add_liquidity_single_sided_fails_with_invalid_deposit() {
+	ExtBuilder::default().build().execute_with(|| {
+		let depositor: AccountId = with_account!(CoreAssetCurrency => 100_000, TradeAssetCurrencyB => 100_000);
+
+		assert_err!(
+			CennzXSpot::add_liquidity_single_sided(
+				Origin::signed(depositor.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(TradeAssetCurrencyB), // deposit_asset
+				100_000,                                // amount
+				1,                                      // min_liquidity
+			),
+			Error::<Test>::InvalidAssetId
+		);
+		assert_err!(
+			CennzXSpot::add_liquidity_single_sided(
+				Origin::signed(depositor),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency), // deposit_asset
+				100_000,                              // amount
+				1,                                    // min_liquidity
+			),
+			Error::<Test>::EmptyExchangePool
+		);
+	});
+}
+
+#[test]
+fn add_liquidity_single_sided_fails_before_the_sale_when_the_deposit_would_fail() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId =
+			with_account!("andrea", CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,         // min_liquidity: T::Balance,
+			1_000_000, // max_asset_amount: T::Balance,
+			1_000_000, // core_amount: T::Balance,
+		));
+		let depositor: AccountId = with_account!("bob", CoreAssetCurrency => 100_000, TradeAssetCurrencyA => 0);
+		// The asset A bought by the sale can't be deposited
+		TradeAssetCurrencyA::set_lock(TEST_LOCK_ID, &depositor, u128::max_value(), WithdrawReasons::all());
+
+		assert_noop!(
+			CennzXSpot::add_liquidity_single_sided(
+				Origin::signed(depositor.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency), // deposit_asset
+				100_000,                              // amount
+				1,                                    // min_liquidity
+			),
+			pallet_generic_asset::Error::<Test>::LiquidityRestrictions
+		);
+
+		// Nothing was traded
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		assert_balance_eq!(depositor, CoreAssetCurrency => 100_000);
+		assert_balance_eq!(depositor, TradeAssetCurrencyA => 0);
+	});
+}
+
+#[test]
+fn add_liquidity_single_sided_fails_on_stable_swap_exchange() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(CennzXSpot::set_exchange_curve(
+			Origin::ROOT,
+			resolve_asset_id!(TradeAssetCurrencyA),
+			Curve::StableSwap(100)
+		));
+		let investor: AccountId =
+			with_account!("andrea", CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,         // min_liquidity: T::Balance,
+			1_000_000, // max_asset_amount: T::Balance,
+			1_000_000, // core_amount: T::Balance,
+		));
+		let depositor: AccountId = with_account!("bob", CoreAssetCurrency => 100_000, TradeAssetCurrencyA => 0);
+
+		assert_noop!(
+			CennzXSpot::add_liquidity_single_sided(
+				Origin::signed(depositor),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency), // deposit_asset
+				100_000,                              // amount
+				1,                                    // min_liquidity
+			),
+			Error::<Test>::UnsupportedPricingCurve
+		);
+	});
+}
+
+#[test]
+fn remove_liquidity_single_sided() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,         // min_liquidity: T::Balance,
+			1_000_000, // max_asset_amount: T::Balance,
+			1_000_000, // core_amount: T::Balance,
+		));
+
+		// 100_000 core and 100_000 of asset A are withdrawn, then the asset A is sold for 89_756 core
+		assert_ok!(CennzXSpot::remove_liquidity_single_sided(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			100_000,                              // liquidity_to_withdraw
+			resolve_asset_id!(CoreAssetCurrency), // withdraw_asset
+			189_756,                              // min_withdraw
+		));
+
+		assert_exchange_balance_eq!(CoreAssetCurrency => 810_244, TradeAssetCurrencyA => 1_000_000);
+		assert_balance_eq!(investor, CoreAssetCurrency => 189_756);
+		assert_balance_eq!(investor, TradeAssetCurrencyA => 0);
+		assert_eq!(CennzXSpot::liquidity_balance(&DEFAULT_EXCHANGE_KEY, &investor), 900_000);
+	});
+}
+
+#[test]
+fn remove_liquidity_single_sided_fails_min_withdraw() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,         // min_liquidity: T::Balance,
+			1_000_000, // max_asset_amount: T::Balance,
+			1_000_000, // core_amount: T::Balance,
+		));
+
+		assert_err!(
+			CennzXSpot::remove_liquidity_single_sided(
+				Origin::signed(investor.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				100_000,                              // liquidity_to_withdraw
+				resolve_asset_id!(CoreAssetCurrency), // withdraw_asset
+				189_757,                              // min_withdraw
+			),
+			Error::<Test>::MinimumCoreAssetRequirementNotMet
+		);
+		// Withdrawing all liquidity leaves no exchange to sell into
+		assert_err!(
+			CennzXSpot::remove_liquidity_single_sided(
+				Origin::signed(investor.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				1_000_000,                            // liquidity_to_withdraw
+				resolve_asset_id!(CoreAssetCurrency), // withdraw_asset
+				1,                                    // min_withdraw
+			),
+			Error::<Test>::EmptyExchangePool
+		);
+		assert_eq!(
+			CennzXSpot::liquidity_balance(&DEFAULT_EXCHANGE_KEY, &investor),
+			1_000_000
+		);
+	});
+}
+
+#[test]
+fn transfer_liquidity() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		let investor: AccountId = with_account!("andrea", CoreAssetCurrency => 100, TradeAssetCurrencyA => 100);
+		let recipient: AccountId = with_account!("bob", CoreAssetCurrency => 0, TradeAssetCurrencyA => 0);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			2,  // min_liquidity: T::Balance,
+			15, // max_asset_amount: T::Balance,
+			10, // core_amount: T::Balance,
+		));
+
+		assert_ok!(CennzXSpot::transfer_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			recipient.clone(),
+			4, // amount
+		));
+		assert_eq!(CennzXSpot::liquidity_balance(&DEFAULT_EXCHANGE_KEY, &investor), 6);
+		assert_eq!(CennzXSpot::liquidity_balance(&DEFAULT_EXCHANGE_KEY, &recipient), 4);
+		assert_eq!(CennzXSpot::total_liquidity(&DEFAULT_EXCHANGE_KEY), 10);
+		assert_eq!(
+			cennzx_events().last(),
+			Some(&RawEvent::LiquidityTransferred(
+				investor.clone(),
+				recipient.clone(),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				4
+			))
+		);
+
+		// The recipient can withdraw the transferred liquidity
+		assert_ok!(CennzXSpot::remove_liquidity(
+			Origin::signed(recipient.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			4, //`asset_amount` - Amount of exchange asset to burn
+			1, //`min_asset_withdraw` - The minimum trade asset withdrawn
+			1  //`min_core_withdraw` -  The minimum core asset withdrawn
+		));
+		assert_balance_eq!(recipient, CoreAssetCurrency => 4);
+		assert_balance_eq!(recipient, TradeAssetCurrencyA => 6);
+	});
+}
+
+#[test]
+fn transfer_liquidity_fails_on_overdraw_liquidity() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId = with_account!("andrea", CoreAssetCurrency => 100, TradeAssetCurrencyA => 100);
+		let recipient: AccountId = with_account!("bob", CoreAssetCurrency => 0, TradeAssetCurrencyA => 0);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			2,  // min_liquidity: T::Balance,
+			15, // max_asset_amount: T::Balance,
+			10, // core_amount: T::Balance,
+		));
+
+		assert_err!(
+			CennzXSpot::transfer_liquidity(
+				Origin::signed(investor.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				recipient.clone(),
+				11, // amount
+			),
+			Error::<Test>::InsufficientLiquidity
+		);
+		assert_eq!(CennzXSpot::liquidity_balance(&DEFAULT_EXCHANGE_KEY, &investor), 10);
+		assert_eq!(CennzXSpot::liquidity_balance(&DEFAULT_EXCHANGE_KEY, &recipient), 0);
+	});
+}
+
+#[test]
+fn batch_trade() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+		with_exchange!(CoreAssetCurrency => 10_000, TradeAssetCurrencyB => 10_000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 1_000, TradeAssetCurrencyA => 1_000);
+		System::set_block_number(1);
+
+		// The second trade is priced after the first, it would cost 210 of asset A on its own
+		assert_ok!(CennzXSpot::batch_trade(
+			Origin::signed(trader.clone()),
+			vec![
+				TradeInstruction::Sell {
+					recipient: None,
+					asset_to_sell: resolve_asset_id!(CoreAssetCurrency),
+					asset_to_buy: resolve_asset_id!(TradeAssetCurrencyA),
+					sell_amount: 500,
+					minimum_buy: 474,
+				},
+				TradeInstruction::Buy {
+					recipient: None,
+					asset_to_sell: resolve_asset_id!(TradeAssetCurrencyA),
+					asset_to_buy: resolve_asset_id!(TradeAssetCurrencyB),
+					buy_amount: 200,
+					maximum_sell: 190,
+				},
+			],
+			None, // valid_until
+		));
+
+		assert_exchange_balance_eq!(CoreAssetCurrency => 10_295, TradeAssetCurrencyA => 9_716);
+		assert_exchange_balance_eq!(CoreAssetCurrency => 10_205, TradeAssetCurrencyB => 9_800);
+		assert_balance_eq!(trader, CoreAssetCurrency => 500);
+		assert_balance_eq!(trader, TradeAssetCurrencyA => 1_284);
+		assert_balance_eq!(trader, TradeAssetCurrencyB => 200);
+		// One purchase is emitted for each trade, at the amounts it was checked at
+		assert_eq!(
+			cennzx_events(),
+			vec![
+				RawEvent::AssetPurchase(
+					resolve_asset_id!(CoreAssetCurrency),
+					resolve_asset_id!(TradeAssetCurrencyA),
+					trader.clone(),
+					500,
+					474,
+					0
+				),
+				RawEvent::AssetPurchase(
+					resolve_asset_id!(TradeAssetCurrencyA),
+					resolve_asset_id!(TradeAssetCurrencyB),
+					trader.clone(),
+					190,
+					200,
+					0
+				),
+				RawEvent::BatchTradeExecuted(trader, vec![(500, 474), (190, 200)]),
+			]
+		);
+	});
+}
+
+#[test]
+fn batch_trade_weight_scales_with_the_number_of_trades() {
+	let sell_core = TradeInstruction::Sell {
+		recipient: None,
+		asset_to_sell: resolve_asset_id!(CoreAssetCurrency),
+		asset_to_buy: resolve_asset_id!(TradeAssetCurrencyA),
+		sell_amount: 10,
+		minimum_buy: 1,
+	};
+	assert_eq!(
+		Call::<Test>::batch_trade(vec![sell_core.clone()], None)
+			.get_dispatch_info()
+			.weight,
+		BATCH_TRADE_WEIGHT
+	);
+	assert_eq!(
+		Call::<Test>::batch_trade(vec![sell_core; 3], None)
+			.get_dispatch_info()
+			.weight,
+		3 * BATCH_TRADE_WEIGHT
+	);
+}
+
+#[test]
+fn batch_trade_fails_before_any_trade_when_a_later_trade_is_locked() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+		with_exchange!(CoreAssetCurrency => 10_000, TradeAssetCurrencyB => 10_000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 1_000, TradeAssetCurrencyB => 1_000);
+		TradeAssetCurrencyB::set_lock(TEST_LOCK_ID, &trader, u128::max_value(), WithdrawReasons::all());
+
+		assert_noop!(
+			CennzXSpot::batch_trade(
+				Origin::signed(trader.clone()),
+				vec![
+					TradeInstruction::Sell {
+						recipient: None,
+						asset_to_sell: resolve_asset_id!(CoreAssetCurrency),
+						asset_to_buy: resolve_asset_id!(TradeAssetCurrencyA),
+						sell_amount: 500,
+						minimum_buy: 1,
+					},
+					TradeInstruction::Sell {
+						recipient: None,
+						asset_to_sell: resolve_asset_id!(TradeAssetCurrencyB),
+						asset_to_buy: resolve_asset_id!(CoreAssetCurrency),
+						sell_amount: 500,
+						minimum_buy: 1,
+					},
+				],
+				None, // valid_until
+			),
+			pallet_generic_asset::Error::<Test>::LiquidityRestrictions
+		);
+
+		// The first trade was not made
+		assert_exchange_balance_eq!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+		assert_balance_eq!(trader, CoreAssetCurrency => 1_000);
+		assert_balance_eq!(trader, TradeAssetCurrencyB => 1_000);
+	});
+}
+
+#[test]
+fn batch_trade_fails_as_a_whole() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+		with_exchange!(CoreAssetCurrency => 10_000, TradeAssetCurrencyB => 10_000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 1_000, TradeAssetCurrencyA => 1_000);
+
+		assert_err!(
+			CennzXSpot::batch_trade(
+				Origin::signed(trader.clone()),
+				vec![
+					TradeInstruction::Sell {
+						recipient: None,
+						asset_to_sell: resolve_asset_id!(CoreAssetCurrency),
+						asset_to_buy: resolve_asset_id!(TradeAssetCurrencyA),
+						sell_amount: 500,
+						minimum_buy: 474,
+					},
+					TradeInstruction::Buy {
+						recipient: None,
+						asset_to_sell: resolve_asset_id!(TradeAssetCurrencyA),
+						asset_to_buy: resolve_asset_id!(TradeAssetCurrencyB),
+						buy_amount: 200,
+						maximum_sell: 189,
+					},
+				],
+				None, // valid_until
+			),
+			Error::<Test>::MaximumSellRequirementNotMet
+		);
+
+		// The first trade was not made
+		assert_exchange_balance_eq!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+		assert_exchange_balance_eq!(CoreAssetCurrency => 10_000, TradeAssetCurrencyB => 10_000);
+		assert_balance_eq!(trader, CoreAssetCurrency => 1_000);
+		assert_balance_eq!(trader, TradeAssetCurrencyA => 1_000);
+	});
+}
+
+#[test]
+fn batch_trade_fails_with_insufficient_balance_for_later_trade() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 1_000, TradeAssetCurrencyA => 0);
+		let sell_core = TradeInstruction::Sell {
+			recipient: None,
+			asset_to_sell: resolve_asset_id!(CoreAssetCurrency),
+			asset_to_buy: resolve_asset_id!(TradeAssetCurrencyA),
+			sell_amount: 600,
+			minimum_buy: 1,
+		};
+
+		assert_err!(
+			CennzXSpot::batch_trade(
+				Origin::signed(trader.clone()),
+				vec![sell_core.clone(), sell_core],
+				None, // valid_until
+			),
+			Error::<Test>::InsufficientBalance
+		);
+		assert_balance_eq!(trader, CoreAssetCurrency => 1_000);
+	});
+}
+
+#[test]
+fn batch_trade_fails_with_invalid_batch_size() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 1_000, TradeAssetCurrencyA => 0);
+		let sell_core = TradeInstruction::Sell {
+			recipient: None,
+			asset_to_sell: resolve_asset_id!(CoreAssetCurrency),
+			asset_to_buy: resolve_asset_id!(TradeAssetCurrencyA),
+			sell_amount: 10,
+			minimum_buy: 1,
+		};
+
+		assert_err!(
+			CennzXSpot::batch_trade(Origin::signed(trader.clone()), vec![], None),
+			Error::<Test>::InvalidBatchSize
+		);
+		// `MaxBatchTrades` is 3 in the mock
+		assert_err!(
+			CennzXSpot::batch_trade(Origin::signed(trader), vec![sell_core; 4], None),
+			Error::<Test>::InvalidBatchSize
+		);
+	});
+}
+
+#[test]
+fn sell_quote() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+
+		let quote = CennzXSpot::get_sell_quote(
+			resolve_asset_id!(CoreAssetCurrency),
+			100_000,
+			resolve_asset_id!(TradeAssetCurrencyA),
+		)
+		.unwrap();
+
+		assert_eq!(
+			quote.route,
+			vec![
+				resolve_asset_id!(CoreAssetCurrency),
+				resolve_asset_id!(TradeAssetCurrencyA)
+			]
+		);
+		assert_eq!(quote.amount_in, 100_000);
+		assert_eq!(quote.amount_out, 90_661);
+		assert_eq!(quote.fee, 300);
+		assert_eq!(quote.spot_price_before, PRICE_SCALE);
+		// 1_100_000 core / 909_339 of asset A
+		assert_eq!(quote.spot_price_after, 1_209_669_881_089_450_688);
+		assert_eq!(quote.execution_price, 1_103_010_114_602_750_907);
+		assert_eq!(quote.price_impact, FeeRate::<PerMillion>::from(99_701));
+	});
+}
+
+#[test]
+fn buy_quote_between_trade_assets() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		with_exchange!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyB => 1_000_000);
+
+		let quote = CennzXSpot::get_buy_quote(
+			resolve_asset_id!(TradeAssetCurrencyB),
+			1_000,
+			resolve_asset_id!(TradeAssetCurrencyA),
+		)
+		.unwrap();
+
+		assert_eq!(
+			quote.route,
+			vec![
+				resolve_asset_id!(TradeAssetCurrencyA),
+				resolve_asset_id!(CoreAssetCurrency),
+				resolve_asset_id!(TradeAssetCurrencyB)
+			]
+		);
+		assert_eq!(quote.amount_in, 1_010);
+		assert_eq!(quote.amount_out, 1_000);
+		assert_eq!(quote.fee, 6);
+		assert_eq!(quote.spot_price_before, PRICE_SCALE);
+		assert_eq!(quote.spot_price_after, 1_004_028_082_306_802_420);
+		assert_eq!(quote.execution_price, 1_010_000_000_000_000_000);
+		assert_eq!(quote.price_impact, FeeRate::<PerMillion>::from(4_000));
+		// The quote matches the buy price
+		assert_eq!(
+			CennzXSpot::get_buy_price(
+				resolve_asset_id!(TradeAssetCurrencyB),
+				1_000,
+				resolve_asset_id!(TradeAssetCurrencyA)
+			),
+			Ok(1_010)
+		);
+	});
+}
+
+#[test]
+fn quote_fails_with_empty_exchange() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_err!(
+			CennzXSpot::get_sell_quote(
+				resolve_asset_id!(CoreAssetCurrency),
+				100,
+				resolve_asset_id!(TradeAssetCurrencyA),
+			),
+			Error::<Test>::EmptyExchangePool
+		);
+	});
+}
+
+#[test]
+fn fee_earnings_accrue_to_liquidity_providers() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId =
+			with_account!("andrea", CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		let trader: AccountId = with_account!("bob", CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		let late_investor: AccountId =
+			with_account!("charlie", CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,         // min_liquidity: T::Balance,
+			1_000_000, // max_asset_amount: T::Balance,
+			1_000_000, // core_amount: T::Balance,
+		));
+
+		// A 0.3% fee is charged in the asset sold
+		assert_ok!(CennzXSpot::sell_asset(
+			Origin::signed(trader.clone()),
+			None,
+			resolve_asset_id!(CoreAssetCurrency),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			100_300, // sell_amount: T::Balance,
+			1,       // min buy limit: T::Balance,
+		));
+		assert_ok!(CennzXSpot::sell_asset(
+			Origin::signed(trader.clone()),
+			None,
+			resolve_asset_id!(TradeAssetCurrencyA),
+			resolve_asset_id!(CoreAssetCurrency),
+			10_030, // sell_amount: T::Balance,
+			1,      // min buy limit: T::Balance,
+		));
+		assert_eq!(CennzXSpot::collected_fees(&DEFAULT_EXCHANGE_KEY), (300, 30));
+
+		let earnings = CennzXSpot::account_fee_earnings(&investor, resolve_asset_id!(TradeAssetCurrencyA));
+		assert_eq!(earnings.core_cost, 1_000_000);
+		assert_eq!(earnings.asset_cost, 1_000_000);
+		assert_eq!(earnings.unrealised_core_fees, 300);
+		assert_eq!(earnings.unrealised_asset_fees, 30);
+		assert_eq!(earnings.realised_core_fees, 0);
+		assert_eq!(earnings.realised_asset_fees, 0);
+
+		// Liquidity added after the trades earns none of their fees
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(late_investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,         // min_liquidity: T::Balance,
+			1_000_000, // max_asset_amount: T::Balance,
+			100_000,   // core_amount: T::Balance,
+		));
+		let earnings = CennzXSpot::account_fee_earnings(&late_investor, resolve_asset_id!(TradeAssetCurrencyA));
+		assert_eq!(earnings.core_cost, 100_000);
+		assert_eq!(earnings.unrealised_core_fees, 0);
+		assert_eq!(earnings.unrealised_asset_fees, 0);
+
+		// Withdrawing half the liquidity realises half the fees earned
+		assert_ok!(CennzXSpot::remove_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			500_000, //`asset_amount` - Amount of exchange asset to burn
+			1,       //`min_asset_withdraw` - The minimum trade asset withdrawn
+			1        //`min_core_withdraw` -  The minimum core asset withdrawn
+		));
+		let earnings = CennzXSpot::account_fee_earnings(&investor, resolve_asset_id!(TradeAssetCurrencyA));
+		assert_eq!(earnings.core_cost, 500_000);
+		assert_eq!(earnings.asset_cost, 500_000);
+		assert_eq!(earnings.unrealised_core_fees, 150);
+		assert_eq!(earnings.unrealised_asset_fees, 15);
+		assert_eq!(earnings.realised_core_fees, 150);
+		assert_eq!(earnings.realised_asset_fees, 15);
+	});
+}
+
+#[test]
+fn fee_earnings_move_with_transferred_liquidity() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId =
+			with_account!("andrea", CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		let trader: AccountId = with_account!("bob", CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 0);
+		let recipient: AccountId = with_account!("charlie", CoreAssetCurrency => 0, TradeAssetCurrencyA => 0);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,         // min_liquidity: T::Balance,
+			1_000_000, // max_asset_amount: T::Balance,
+			1_000_000, // core_amount: T::Balance,
+		));
+		let sell_core = || {
+			assert_ok!(CennzXSpot::sell_asset(
+				Origin::signed(trader.clone()),
+				None,
+				resolve_asset_id!(CoreAssetCurrency),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				100_300, // sell_amount: T::Balance,
+				1,       // min buy limit: T::Balance,
+			));
+		};
+
+		sell_core();
+		assert_ok!(CennzXSpot::transfer_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			recipient.clone(),
+			250_000, // amount
+		));
+		let earnings = CennzXSpot::account_fee_earnings(&investor, resolve_asset_id!(TradeAssetCurrencyA));
+		assert_eq!(earnings.core_cost, 750_000);
+		assert_eq!(earnings.unrealised_core_fees, 225);
+		let earnings = CennzXSpot::account_fee_earnings(&recipient, resolve_asset_id!(TradeAssetCurrencyA));
+		assert_eq!(earnings.core_cost, 250_000);
+		assert_eq!(earnings.asset_cost, 250_000);
+		assert_eq!(earnings.unrealised_core_fees, 75);
+
+		// Later fees are shared by the liquidity each account now holds
+		sell_core();
+		let earnings = CennzXSpot::account_fee_earnings(&investor, resolve_asset_id!(TradeAssetCurrencyA));
+		assert_eq!(earnings.unrealised_core_fees, 450);
+		let earnings = CennzXSpot::account_fee_earnings(&recipient, resolve_asset_id!(TradeAssetCurrencyA));
+		assert_eq!(earnings.unrealised_core_fees, 150);
+	});
+}
+
+#[test]
+fn pause_exchange_stops_trading() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		with_exchange!(CoreAssetCurrency => 1_000, TradeAssetCurrencyA => 1_000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 100, TradeAssetCurrencyA => 0);
+
+		assert_ok!(CennzXSpot::pause_exchange(
+			Origin::ROOT,
+			resolve_asset_id!(TradeAssetCurrencyA)
+		));
+		assert!(CennzXSpot::is_paused(&DEFAULT_EXCHANGE_KEY));
+		assert_eq!(
+			cennzx_events(),
+			vec![RawEvent::ExchangePaused(resolve_asset_id!(TradeAssetCurrencyA))]
+		);
+		assert_err!(
+			CennzXSpot::sell_asset(
+				Origin::signed(trader.clone()),
+				None,
+				resolve_asset_id!(CoreAssetCurrency),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				100, // sell_amount: T::Balance,
+				50,  // min buy limit: T::Balance,
+			),
+			Error::<Test>::ExchangePaused
+		);
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1_000, TradeAssetCurrencyA => 1_000);
+		assert_balance_eq!(trader, CoreAssetCurrency => 100);
+
+		assert_ok!(CennzXSpot::resume_exchange(
+			Origin::ROOT,
+			resolve_asset_id!(TradeAssetCurrencyA)
+		));
+		assert!(!CennzXSpot::is_paused(&DEFAULT_EXCHANGE_KEY));
+		assert_eq!(
+			cennzx_events().last(),
+			Some(&RawEvent::ExchangeResumed(resolve_asset_id!(TradeAssetCurrencyA)))
+		);
+		assert_ok!(CennzXSpot::sell_asset(
+			Origin::signed(trader.clone()),
+			None,
+			resolve_asset_id!(CoreAssetCurrency),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			100, // sell_amount: T::Balance,
+			50,  // min buy limit: T::Balance,
+		));
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1_100, TradeAssetCurrencyA => 910);
+	});
+}
+
+#[test]
+fn pause_exchange_allows_removing_liquidity() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 100, TradeAssetCurrencyA => 100);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			2,  // min_liquidity: T::Balance,
+			15, // max_asset_amount: T::Balance,
+			10, // core_amount: T::Balance,
+		));
+		assert_ok!(CennzXSpot::pause_exchange(
+			Origin::ROOT,
+			resolve_asset_id!(TradeAssetCurrencyA)
+		));
+
+		// Withdrawing a single asset requires a trade
+		assert_err!(
+			CennzXSpot::remove_liquidity_single_sided(
+				Origin::signed(investor.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				5, // liquidity_to_withdraw
+				resolve_asset_id!(CoreAssetCurrency),
+				1, // min_withdraw
+			),
+			Error::<Test>::ExchangePaused
+		);
+		assert_eq!(CennzXSpot::liquidity_balance(&DEFAULT_EXCHANGE_KEY, &investor), 10);
+
+		assert_ok!(CennzXSpot::remove_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			10, //`asset_amount` - Amount of exchange asset to burn
+			4,  //`min_asset_withdraw` - The minimum trade asset withdrawn
+			4   //`min_core_withdraw` -  The minimum core asset withdrawn
+		));
+		assert_balance_eq!(investor, CoreAssetCurrency => 100);
+		assert_balance_eq!(investor, TradeAssetCurrencyA => 100);
+	});
+}
+
+#[test]
+fn pause_exchange_requires_root() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 100, TradeAssetCurrencyA => 100);
+		assert_noop!(
+			CennzXSpot::pause_exchange(Origin::signed(investor.clone()), resolve_asset_id!(TradeAssetCurrencyA)),
+			BadOrigin
+		);
+		assert_noop!(
+			CennzXSpot::resume_exchange(Origin::signed(investor.clone()), resolve_asset_id!(TradeAssetCurrencyA)),
+			BadOrigin
+		);
+		assert_noop!(
+			CennzXSpot::set_max_price_movement(Origin::signed(investor), 100_000.into()),
+			BadOrigin
+		);
+	});
+}
+
+#[test]
+fn circuit_breaker_limits_price_movement_within_a_block() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 0);
+		// 10%
+		assert_ok!(CennzXSpot::set_max_price_movement(Origin::ROOT, 100_000.into()));
+		frame_system::Module::<Test>::set_block_number(1);
+
+		let sell_core = |amount| {
+			CennzXSpot::sell_asset(
+				Origin::signed(trader.clone()),
+				None,
+				resolve_asset_id!(CoreAssetCurrency),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				amount, // sell_amount: T::Balance,
+				1,      // min buy limit: T::Balance,
+			)
+		};
+
+		// Moves the price ~2%
+		assert_ok!(sell_core(10_000));
+		// Would move the price ~11% from the start of the block
+		assert_err!(sell_core(50_000), Error::<Test>::PriceMovementLimitExceeded);
+		assert_balance_eq!(trader, CoreAssetCurrency => 990_000);
+
+		// The limit applies from the price at the start of the next block, ~9%
+		frame_system::Module::<Test>::set_block_number(2);
+		assert_ok!(sell_core(50_000));
+		assert_balance_eq!(trader, CoreAssetCurrency => 940_000);
+	});
+}
+
+#[test]
+fn batch_trade_fails_with_price_movement_limit_exceeded() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 0);
+		// 10%
+		assert_ok!(CennzXSpot::set_max_price_movement(Origin::ROOT, 100_000.into()));
+		frame_system::Module::<Test>::set_block_number(1);
+
+		let sell_core = |amount| TradeInstruction::Sell {
+			recipient: None,
+			asset_to_sell: resolve_asset_id!(CoreAssetCurrency),
+			asset_to_buy: resolve_asset_id!(TradeAssetCurrencyA),
+			sell_amount: amount,
+			minimum_buy: 1,
+		};
+
+		// Each trade is within the limit alone, but not together
+		assert_err!(
+			CennzXSpot::batch_trade(
+				Origin::signed(trader.clone()),
+				vec![sell_core(10_000), sell_core(50_000)],
+				None
+			),
+			Error::<Test>::PriceMovementLimitExceeded
+		);
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		assert_balance_eq!(trader, CoreAssetCurrency => 1_000_000);
+	});
+}
+
+#[test]
+fn add_liquidity_locks_minimum_liquidity() {
+	ExtBuilder::default().minimum_liquidity(1_000).build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+		let exchange_address =
+			<Test as Trait>::ExchangeAddressGenerator::exchange_address_for(resolve_asset_id!(TradeAssetCurrencyA));
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,      // min_liquidity: T::Balance,
+			10_000, // max_asset_amount: T::Balance,
+			10_000, // core_amount: T::Balance,
+		));
+		assert_eq!(CennzXSpot::liquidity_balance(&DEFAULT_EXCHANGE_KEY, &investor), 9_000);
+		assert_eq!(
+			CennzXSpot::liquidity_balance(&DEFAULT_EXCHANGE_KEY, &exchange_address),
+			1_000
+		);
+		assert_eq!(CennzXSpot::total_liquidity(&DEFAULT_EXCHANGE_KEY), 10_000);
+
+		// The locked liquidity stays in the exchange when every investor withdraws
+		assert_ok!(CennzXSpot::remove_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			9_000, //`asset_amount` - Amount of exchange asset to burn
+			1,     //`min_asset_withdraw` - The minimum trade asset withdrawn
+			1      //`min_core_withdraw` -  The minimum core asset withdrawn
+		));
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1_000, TradeAssetCurrencyA => 1_000);
+		assert_eq!(CennzXSpot::total_liquidity(&DEFAULT_EXCHANGE_KEY), 1_000);
+	});
+}
+
+#[test]
+fn exchange_emptied_when_only_locked_liquidity_remains() {
+	ExtBuilder::default().minimum_liquidity(1_000).build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+		let exchange_address =
+			<Test as Trait>::ExchangeAddressGenerator::exchange_address_for(resolve_asset_id!(TradeAssetCurrencyA));
+		System::set_block_number(1);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,      // min_liquidity: T::Balance,
+			10_000, // max_asset_amount: T::Balance,
+			10_000, // core_amount: T::Balance,
+		));
+
+		assert_ok!(CennzXSpot::remove_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			9_000, //`asset_amount` - Amount of exchange asset to burn
+			1,     //`min_asset_withdraw` - The minimum trade asset withdrawn
+			1      //`min_core_withdraw` -  The minimum core asset withdrawn
+		));
+		assert!(CennzXSpot::active_exchanges().is_empty());
+		assert_eq!(CennzXSpot::exchange_created_at(&DEFAULT_EXCHANGE_KEY), None);
+		assert_eq!(
+			cennzx_events().last(),
+			Some(&RawEvent::ExchangeEmptied(resolve_asset_id!(TradeAssetCurrencyA)))
+		);
+
+		// Liquidity added to the remaining locked liquidity creates the exchange anew, without locking more
+		System::set_block_number(2);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,      // min_liquidity: T::Balance,
+			10_000, // max_asset_amount: T::Balance,
+			1_000,  // core_amount: T::Balance,
+		));
+		assert_eq!(
+			CennzXSpot::active_exchanges(),
+			vec![resolve_asset_id!(TradeAssetCurrencyA)]
+		);
+		assert_eq!(CennzXSpot::exchange_created_at(&DEFAULT_EXCHANGE_KEY), Some(2));
+		assert_eq!(
+			CennzXSpot::liquidity_balance(&DEFAULT_EXCHANGE_KEY, &exchange_address),
+			1_000
+		);
+		let created = RawEvent::ExchangeCreated(resolve_asset_id!(TradeAssetCurrencyA), exchange_address);
+		assert_eq!(cennzx_events().iter().filter(|event| **event == created).count(), 2);
+	});
+}
+
+#[test]
+fn add_liquidity_fails_with_insufficient_initial_liquidity() {
+	ExtBuilder::default().minimum_liquidity(1_000).build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+		assert_err!(
+			CennzXSpot::add_liquidity(
+				Origin::signed(investor.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				1,      // min_liquidity: T::Balance,
+				10_000, // max_asset_amount: T::Balance,
+				1_000,  // core_amount: T::Balance,
+			),
+			Error::<Test>::InsufficientInitialLiquidity
+		);
+		assert_eq!(CennzXSpot::total_liquidity(&DEFAULT_EXCHANGE_KEY), 0);
+		assert_balance_eq!(investor, CoreAssetCurrency => 10_000);
+	});
+}
+
+#[test]
+fn remove_liquidity_fails_below_minimum_reserve() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 1_000, TradeAssetCurrencyA => 1_000);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,     // min_liquidity: T::Balance,
+			1_000, // max_asset_amount: T::Balance,
+			1_000, // core_amount: T::Balance,
+		));
+		assert_noop!(
+			CennzXSpot::set_minimum_reserve(Origin::signed(investor.clone()), 500),
+			BadOrigin
+		);
+		assert_ok!(CennzXSpot::set_minimum_reserve(Origin::ROOT, 500));
+
+		assert_err!(
+			CennzXSpot::remove_liquidity(
+				Origin::signed(investor.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				600, //`asset_amount` - Amount of exchange asset to burn
+				1,   //`min_asset_withdraw` - The minimum trade asset withdrawn
+				1    //`min_core_withdraw` -  The minimum core asset withdrawn
+			),
+			Error::<Test>::MinimumReserveRequirementNotMet
+		);
+		// Selling the withdrawn trade asset would take the core reserve below the minimum
+		assert_err!(
+			CennzXSpot::remove_liquidity_single_sided(
+				Origin::signed(investor.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				400, // liquidity_to_withdraw
+				resolve_asset_id!(CoreAssetCurrency),
+				1, // min_withdraw
+			),
+			Error::<Test>::MinimumReserveRequirementNotMet
+		);
+		assert_eq!(CennzXSpot::liquidity_balance(&DEFAULT_EXCHANGE_KEY, &investor), 1_000);
+
+		assert_ok!(CennzXSpot::remove_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			400, //`asset_amount` - Amount of exchange asset to burn
+			1,   //`min_asset_withdraw` - The minimum trade asset withdrawn
+			1    //`min_core_withdraw` -  The minimum core asset withdrawn
+		));
+		assert_exchange_balance_eq!(CoreAssetCurrency => 600, TradeAssetCurrencyA => 600);
+	});
+}
+
+#[test]
+fn set_exchange_curve() {
+	ExtBuilder::default().build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 100, TradeAssetCurrencyA => 100);
+		assert_noop!(
+			CennzXSpot::set_exchange_curve(
+				Origin::signed(investor),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				Curve::StableSwap(100)
+			),
+			BadOrigin
+		);
+		assert_noop!(
+			CennzXSpot::set_exchange_curve(
+				Origin::ROOT,
+				resolve_asset_id!(TradeAssetCurrencyA),
+				Curve::StableSwap(0)
+			),
+			Error::<Test>::InvalidPricingCurve
+		);
+
+		assert_ok!(CennzXSpot::set_exchange_curve(
+			Origin::ROOT,
+			resolve_asset_id!(TradeAssetCurrencyA),
+			Curve::StableSwap(100)
+		));
+		assert_eq!(CennzXSpot::pricing_curve(&DEFAULT_EXCHANGE_KEY), Curve::StableSwap(100));
+		assert_eq!(
+			CennzXSpot::curve_for(&resolve_asset_id!(TradeAssetCurrencyB)),
+			Curve::ConstantProduct
+		);
+	});
+}
+
+#[test]
+fn set_exchange_curve_fails_on_funded_exchange() {
+	ExtBuilder::default().minimum_liquidity(1_000).build().execute_with(|| {
+		let investor: AccountId = with_account!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+		assert_ok!(CennzXSpot::add_liquidity(
+			Origin::signed(investor.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,      // min_liquidity: T::Balance,
+			10_000, // max_asset_amount: T::Balance,
+			10_000, // core_amount: T::Balance,
+		));
+
+		assert_noop!(
+			CennzXSpot::set_exchange_curve(
+				Origin::ROOT,
+				resolve_asset_id!(TradeAssetCurrencyA),
+				Curve::StableSwap(100)
+			),
+			Error::<Test>::ExchangeNotEmpty
+		);
+		assert_eq!(CennzXSpot::pricing_curve(&DEFAULT_EXCHANGE_KEY), Curve::ConstantProduct);
+
+		// Once only the locked liquidity remains the curve may be switched
+		assert_ok!(CennzXSpot::remove_liquidity(
+			Origin::signed(investor),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			9_000, //`asset_amount` - Amount of exchange asset to burn
+			1,     //`min_asset_withdraw` - The minimum trade asset withdrawn
+			1      //`min_core_withdraw` -  The minimum core asset withdrawn
+		));
+		assert_ok!(CennzXSpot::set_exchange_curve(
+			Origin::ROOT,
+			resolve_asset_id!(TradeAssetCurrencyA),
+			Curve::StableSwap(100)
+		));
+		assert_eq!(CennzXSpot::pricing_curve(&DEFAULT_EXCHANGE_KEY), Curve::StableSwap(100));
+	});
+}
+
+#[test]
+fn stable_swap_exchange_prices_trades_with_less_slippage() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyA => 1_000_000);
+		with_exchange!(CoreAssetCurrency => 1_000_000, TradeAssetCurrencyB => 1_000_000);
+		let trader: AccountId = with_account!(CoreAssetCurrency => 100_000, TradeAssetCurrencyA => 0);
+		let _ = CennzXSpot::set_fee_rate(Origin::ROOT, 0.into());
+		assert_ok!(CennzXSpot::set_exchange_curve(
+			Origin::ROOT,
+			resolve_asset_id!(TradeAssetCurrencyA),
+			Curve::StableSwap(100)
+		));
+
+		assert_eq!(
+			CennzXSpot::get_sell_price(
+				resolve_asset_id!(CoreAssetCurrency),
+				100_000,
+				resolve_asset_id!(TradeAssetCurrencyA),
+			),
+			Ok(99_949)
+		);
+		assert_eq!(
+			CennzXSpot::get_sell_price(
+				resolve_asset_id!(CoreAssetCurrency),
+				100_000,
+				resolve_asset_id!(TradeAssetCurrencyB),
+			),
+			Ok(90_909)
+		);
+		assert_eq!(
+			CennzXSpot::get_buy_price(
+				resolve_asset_id!(TradeAssetCurrencyA),
+				100_000,
+				resolve_asset_id!(CoreAssetCurrency),
+			),
+			Ok(100_051)
+		);
+
+		assert_ok!(CennzXSpot::sell_asset(
+			Origin::signed(trader.clone()),
+			None,
+			resolve_asset_id!(CoreAssetCurrency),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			100_000, // sell_amount: T::Balance,
+			99_949,  // min buy limit: T::Balance,
+		));
+		assert_exchange_balance_eq!(CoreAssetCurrency => 1_100_000, TradeAssetCurrencyA => 900_051);
+		assert_balance_eq!(trader, TradeAssetCurrencyA => 99_949);
+	});
+}
+
+#[test]
+fn flash_swap() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+		with_exchange!(CoreAssetCurrency => 10_000, TradeAssetCurrencyB => 10_000);
+		let borrower: AccountId = with_account!(CoreAssetCurrency => 1_000, TradeAssetCurrencyB => 0);
+		System::set_block_number(1);
+
+		// 500 core is borrowed from the exchange for asset A and sold for asset B,
+		// then 502 core is repaid from the 502 reserved up front
+		assert_ok!(CennzXSpot::flash_swap(
+			Origin::signed(borrower.clone()),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			500, // core_amount
+			0,   // asset_amount
+			Box::new(Call::sell_asset(
+				None,
+				resolve_asset_id!(CoreAssetCurrency),
+				resolve_asset_id!(TradeAssetCurrencyB),
+				500,
+				474
+			)),
+		));
+
+		assert_exchange_balance_eq!(CoreAssetCurrency => 10_002, TradeAssetCurrencyA => 10_000);
+		assert_exchange_balance_eq!(CoreAssetCurrency => 10_500, TradeAssetCurrencyB => 9_526);
+		assert_balance_eq!(borrower, CoreAssetCurrency => 498);
+		assert_balance_eq!(borrower, TradeAssetCurrencyB => 474);
+		assert_eq!(
+			<pallet_generic_asset::Module<Test>>::reserved_balance(&resolve_asset_id!(CoreAssetCurrency), &borrower),
+			0
+		);
+		assert_eq!(CennzXSpot::collected_fees(&DEFAULT_EXCHANGE_KEY), (2, 0));
+		assert!(!CennzXSpot::is_flash_swapping(&DEFAULT_EXCHANGE_KEY));
+		assert_eq!(
+			cennzx_events().last(),
+			Some(&RawEvent::FlashSwap(
+				borrower,
+				resolve_asset_id!(TradeAssetCurrencyA),
+				500,
+				0,
+				502,
+				0
+			))
+		);
+	});
+}
+
+#[test]
+fn flash_swap_repays_the_exchange_when_the_call_fails() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+		let borrower: AccountId = with_account!(CoreAssetCurrency => 1_000, TradeAssetCurrencyA => 0);
+
+		// The lent exchange can't be traded on until it is repaid
+		assert_err!(
+			CennzXSpot::flash_swap(
+				Origin::signed(borrower.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				500, // core_amount
+				0,   // asset_amount
+				Box::new(Call::sell_asset(
+					None,
+					resolve_asset_id!(CoreAssetCurrency),
+					resolve_asset_id!(TradeAssetCurrencyA),
+					500,
+					1
+				)),
+			),
+			Error::<Test>::FlashSwapInProgress
+		);
+
+		// The exchange was repaid with its fee all the same
+		assert_exchange_balance_eq!(CoreAssetCurrency => 10_002, TradeAssetCurrencyA => 10_000);
+		assert_balance_eq!(borrower, CoreAssetCurrency => 998);
+		assert_balance_eq!(borrower, TradeAssetCurrencyA => 0);
+		assert_eq!(
+			<pallet_generic_asset::Module<Test>>::reserved_balance(&resolve_asset_id!(CoreAssetCurrency), &borrower),
+			0
+		);
+		assert!(!CennzXSpot::is_flash_swapping(&DEFAULT_EXCHANGE_KEY));
+	});
+}
+
+#[test]
+fn flash_swap_fails_without_the_repayment() {
+	ExtBuilder::default().build().execute_with(|| {
+		with_exchange!(CoreAssetCurrency => 10_000, TradeAssetCurrencyA => 10_000);
+		let borrower: AccountId = with_account!(CoreAssetCurrency => 501, TradeAssetCurrencyA => 0);
+		let call = Box::new(Call::sell_asset(
+			None,
+			resolve_asset_id!(CoreAssetCurrency),
+			resolve_asset_id!(TradeAssetCurrencyA),
+			1,
+			0,
+		));
+
+		assert_noop!(
+			CennzXSpot::flash_swap(
+				Origin::signed(borrower.clone()),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				500, // core_amount
+				0,   // asset_amount
+				call.clone(),
+			),
+			Error::<Test>::InsufficientCoreAssetBalance
+		);
+		assert_noop!(
+			CennzXSpot::flash_swap(
+				Origin::signed(borrower),
+				resolve_asset_id!(TradeAssetCurrencyA),
+				0,      // core_amount
+				10_000, // asset_amount
+				call,
+			),
+			Error::<Test>::InsufficientExchangePoolReserve
+		);
+	});
+}
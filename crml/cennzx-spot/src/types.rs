@@ -24,20 +24,23 @@ use core::{
 pub use primitive_types::U256 as HighPrecisionUnsigned;
 pub use u128 as LowPrecisionUnsigned;
 
+/// The fixed point scale of prices reported by the price oracle
+pub const PRICE_SCALE: LowPrecisionUnsigned = 1_000_000_000_000_000_000;
+
 /// A trait for values which hold an implicit scale factor that needs to be taken into account in calculations
 pub trait Scaled {
 	const SCALE: LowPrecisionUnsigned;
 }
 
 /// Per millionth of unit price
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PerMillion {}
 impl Scaled for PerMillion {
 	const SCALE: LowPrecisionUnsigned = 1_000_000;
 }
 
 /// Per thousandth of unit price
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PerThousand {}
 impl Scaled for PerThousand {
 	const SCALE: LowPrecisionUnsigned = 1_000;
@@ -58,7 +61,7 @@ impl Into<&'static str> for FeeRateError {
 
 /// Inner type is `LowPrecisionUnsigned` in order to support compatibility with `pallet_generic_asset::Balance` type
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
-#[derive(Encode, Decode, Copy, Clone, Debug, PartialEq)]
+#[derive(Encode, Decode, Copy, Clone, Debug, PartialEq, Eq)]
 pub struct FeeRate<S: Scaled>(LowPrecisionUnsigned, PhantomData<S>);
 
 impl<S: Scaled> Default for FeeRate<S> {
@@ -133,6 +136,81 @@ impl<S: Scaled> From<FeeRate<S>> for LowPrecisionUnsigned {
 	}
 }
 
+/// A cumulative price observation of an exchange, the basis of the time weighted average price oracle.
+/// Cumulative prices are scaled by `PRICE_SCALE` and wrap on overflow, so only the difference
+/// between two observations is meaningful.
+#[derive(Encode, Decode, Default, Copy, Clone, Debug, PartialEq)]
+pub struct PriceObservation<BlockNumber> {
+	/// The block the observation was made in
+	pub block_number: BlockNumber,
+	/// Sum of the price of one core asset in trade asset, for each block
+	pub core_price_cumulative: LowPrecisionUnsigned,
+	/// Sum of the price of one trade asset in core asset, for each block
+	pub asset_price_cumulative: LowPrecisionUnsigned,
+}
+
+/// The liquidity held in an exchange by an account, valued at its cost and the trading fees it has earned.
+/// Fees are settled into the position whenever its liquidity balance changes.
+#[derive(Encode, Decode, Default, Copy, Clone, Debug, PartialEq)]
+pub struct LiquidityPosition<Balance> {
+	/// Core asset deposited for the liquidity held
+	pub core_cost: Balance,
+	/// Trade asset deposited for the liquidity held
+	pub asset_cost: Balance,
+	/// Core asset fees earned by the liquidity held, still in the exchange
+	pub unrealised_core_fees: Balance,
+	/// Trade asset fees earned by the liquidity held, still in the exchange
+	pub unrealised_asset_fees: Balance,
+	/// Core asset fees withdrawn with liquidity
+	pub realised_core_fees: Balance,
+	/// Trade asset fees withdrawn with liquidity
+	pub realised_asset_fees: Balance,
+	/// Core asset fee growth of the exchange when fees were last settled, scaled by `PRICE_SCALE`
+	pub core_fee_growth: LowPrecisionUnsigned,
+	/// Trade asset fee growth of the exchange when fees were last settled, scaled by `PRICE_SCALE`
+	pub asset_fee_growth: LowPrecisionUnsigned,
+}
+
+/// A resting order to sell an amount of one asset for at least an amount of another
+#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+pub struct LimitOrder<AccountId, AssetId, Balance, BlockNumber> {
+	/// The account which placed the order
+	pub owner: AccountId,
+	/// The asset to sell
+	pub asset_to_sell: AssetId,
+	/// The asset to buy
+	pub asset_to_buy: AssetId,
+	/// The amount of `asset_to_sell` to sell, reserved from `owner` while the order is open
+	pub sell_amount: Balance,
+	/// The minimum amount of `asset_to_buy` to receive
+	pub minimum_buy: Balance,
+	/// The last block the order may be filled in
+	pub valid_until: BlockNumber,
+}
+
+/// A trade made as part of a batch of trades
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub enum TradeInstruction<AccountId, AssetId, Balance> {
+	/// Buy an exact `buy_amount` of `asset_to_buy` for at most `maximum_sell` of `asset_to_sell`
+	Buy {
+		/// Account to receive `buy_amount`, defaults to the trader if None
+		recipient: Option<AccountId>,
+		asset_to_sell: AssetId,
+		asset_to_buy: AssetId,
+		buy_amount: Balance,
+		maximum_sell: Balance,
+	},
+	/// Sell an exact `sell_amount` of `asset_to_sell` for at least `minimum_buy` of `asset_to_buy`
+	Sell {
+		/// Account to receive the `asset_to_buy` bought, defaults to the trader if None
+		recipient: Option<AccountId>,
+		asset_to_sell: AssetId,
+		asset_to_buy: AssetId,
+		sell_amount: Balance,
+		minimum_buy: Balance,
+	},
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -0,0 +1,837 @@
+/* Copyright 2020 Centrality Investments Limited
+*
+* Licensed under the LGPL, Version 3.0 (the "License");
+* you may not use this file except in compliance with the License.
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific language governing permissions and
+* limitations under the License.
+* You may obtain a copy of the License at the root of this project source code,
+* or at:
+*     https://centrality.ai/licenses/gplv3.txt
+*     https://centrality.ai/licenses/lgplv3.txt
+*/
+
+//! # Fee Sponsorship Module
+//!
+//! A registry of accounts sponsoring the transaction fees of others.
+//!
+//! A sponsorship pays the fees of calls matching its call filters made by its beneficiaries, up to
+//! its spend limit each period. An account's sponsorships are checked in the order they were created,
+//! then the sponsorships open to anyone, and the first whose sponsor can afford a fee pays it.
+//!
+//! Sponsors reserve a deposit for each sponsorship, returned when they revoke it, and must name its
+//! beneficiaries. Only root may create sponsorships open to anyone.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage,
+	dispatch::DispatchError,
+	ensure,
+	traits::{Currency, Get, ReservableCurrency, WithdrawReason},
+	Parameter,
+};
+use frame_system::{ensure_root, ensure_signed};
+use sp_arithmetic::traits::BaseArithmetic;
+use sp_runtime::{
+	traits::{CheckedSub, Member, Saturating, Zero},
+	DispatchResult, RuntimeDebug,
+};
+use sp_std::prelude::*;
+
+/// The most sponsorships an account sponsors
+pub const MAX_SPONSORSHIPS_PER_SPONSOR: usize = 16;
+/// The most sponsorships naming an account as a beneficiary
+pub const MAX_SPONSORSHIPS_PER_BENEFICIARY: usize = 8;
+/// The most sponsorships open to anyone
+pub const MAX_OPEN_SPONSORSHIPS: usize = 8;
+/// The most call filters a sponsorship has
+pub const MAX_CALL_FILTERS: usize = 64;
+/// The most beneficiaries a sponsorship has
+pub const MAX_BENEFICIARIES: usize = 64;
+
+/// Identifies a sponsorship
+pub type SponsorshipId = u32;
+
+/// Matches calls by their indices in the runtime, the first two bytes of their encoding
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct CallFilter {
+	/// The index of the module of matching calls
+	pub module: u8,
+	/// The index of the method of matching calls, any method of `module` if `None`
+	pub method: Option<u8>,
+}
+
+impl CallFilter {
+	/// Whether the encoded `call` matches this filter
+	pub fn matches(&self, call: &[u8]) -> bool {
+		call.get(0) == Some(&self.module) && self.method.map_or(true, |method| call.get(1) == Some(&method))
+	}
+}
+
+/// A limit on the fees a sponsorship pays
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct SpendLimit<Balance, BlockNumber> {
+	/// The most fees paid in a period
+	pub amount: Balance,
+	/// The length of a period in blocks, e.g. one block or the blocks in an era
+	pub period: BlockNumber,
+}
+
+/// An account sponsoring the fees of calls made by others
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Sponsorship<AccountId, Balance, BlockNumber> {
+	/// The account paying the fees
+	pub sponsor: AccountId,
+	/// The calls sponsored
+	pub calls: Vec<CallFilter>,
+	/// The accounts whose calls are sponsored, anyone's if empty
+	pub beneficiaries: Vec<AccountId>,
+	/// The limit on the fees paid, unlimited if `None`
+	pub spend_limit: Option<SpendLimit<Balance, BlockNumber>>,
+}
+
+impl<AccountId: PartialEq, Balance, BlockNumber> Sponsorship<AccountId, Balance, BlockNumber> {
+	/// Whether this sponsors the encoded `call` made by `who`
+	pub fn covers(&self, who: &AccountId, call: &[u8]) -> bool {
+		self.calls.iter().any(|filter| filter.matches(call))
+			&& (self.beneficiaries.is_empty() || self.beneficiaries.contains(who))
+	}
+}
+
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The units fees are paid in.
+	type Balance: Parameter + Member + BaseArithmetic + Default + Copy;
+
+	/// The currency fees are paid in and sponsorship deposits are reserved in.
+	type Currency: ReservableCurrency<Self::AccountId, Balance = Self::Balance>;
+
+	/// The deposit reserved from a sponsor for each sponsorship they create.
+	type SponsorshipDeposit: Get<Self::Balance>;
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// There is no sponsorship with the given id
+		NoSponsorship,
+		/// Only the sponsor can revoke a sponsorship
+		NotSponsor,
+		/// A sponsorship must sponsor some calls
+		NoCallFilters,
+		/// A sponsorship has more than `MAX_CALL_FILTERS` call filters
+		TooManyCallFilters,
+		/// A sponsorship has more than `MAX_BENEFICIARIES` beneficiaries
+		TooManyBeneficiaries,
+		/// The sponsor already has `MAX_SPONSORSHIPS_PER_SPONSOR` sponsorships
+		TooManySponsorships,
+		/// A spend limit period must be at least one block
+		InvalidSpendLimit,
+		/// All sponsorship ids have been used
+		NoAvailableSponsorshipId,
+		/// A sponsorship must name its beneficiaries, only root may sponsor anyone
+		NoBeneficiaries,
+		/// A beneficiary is already named by `MAX_SPONSORSHIPS_PER_BENEFICIARY` sponsorships
+		TooManyBeneficiarySponsorships,
+		/// There are already `MAX_OPEN_SPONSORSHIPS` sponsorships open to anyone
+		TooManyOpenSponsorships,
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as FeeSponsorship {
+		/// The id of the next sponsorship created
+		pub NextSponsorshipId get(fn next_sponsorship_id): SponsorshipId;
+		/// Sponsorships by id
+		pub Sponsorships get(fn sponsorship): map hasher(twox_64_concat) SponsorshipId => Option<Sponsorship<T::AccountId, T::Balance, T::BlockNumber>>;
+		/// The ids of the sponsorships of a sponsor, in the order they were created
+		pub SponsorshipsOf get(fn sponsorships_of): map hasher(blake2_128_concat) T::AccountId => Vec<SponsorshipId>;
+		/// The ids of the sponsorships naming a beneficiary, in the order they are checked
+		pub SponsorshipsFor get(fn sponsorships_for): map hasher(blake2_128_concat) T::AccountId => Vec<SponsorshipId>;
+		/// The ids of the sponsorships open to anyone, in the order they are checked
+		pub OpenSponsorships get(fn open_sponsorships): Vec<SponsorshipId>;
+		/// The deposit reserved from the sponsor of a sponsorship
+		pub Deposits get(fn deposit): map hasher(twox_64_concat) SponsorshipId => T::Balance;
+		/// The fees paid by a sponsorship with a spend limit, as (start of the period, fees paid in the period)
+		pub Spending get(fn spending): map hasher(twox_64_concat) SponsorshipId => (T::BlockNumber, T::Balance);
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		<T as frame_system::Trait>::AccountId,
+		<T as Trait>::Balance,
+	{
+		/// A sponsorship has been created (id, sponsor)
+		SponsorshipCreated(SponsorshipId, AccountId),
+		/// A sponsorship has been revoked (id, sponsor)
+		SponsorshipRevoked(SponsorshipId, AccountId),
+		/// A sponsorship has paid a fee (id, beneficiary, fee)
+		FeeSponsored(SponsorshipId, AccountId, Balance),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// The deposit reserved from a sponsor for each sponsorship they create.
+		const SponsorshipDeposit: T::Balance = T::SponsorshipDeposit::get();
+
+		/// Sponsor the fees of calls matching `calls` made by `beneficiaries`
+		/// `SponsorshipDeposit` is reserved from the origin until the sponsorship is revoked.
+		///
+		/// `calls` - Filters of the calls sponsored
+		/// `beneficiaries` - The accounts whose calls are sponsored, at least one
+		/// `spend_limit` - The limit on the fees paid each period, unlimited if `None`
+		pub fn sponsor(
+			origin,
+			calls: Vec<CallFilter>,
+			beneficiaries: Vec<T::AccountId>,
+			spend_limit: Option<SpendLimit<T::Balance, T::BlockNumber>>
+		) -> DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			ensure!(!beneficiaries.is_empty(), Error::<T>::NoBeneficiaries);
+			Self::create_sponsorship(
+				Sponsorship { sponsor, calls, beneficiaries, spend_limit },
+				T::SponsorshipDeposit::get(),
+			)?;
+			Ok(())
+		}
+
+		/// Revoke the sponsorship `id` of the origin
+		pub fn revoke(origin, #[compact] id: SponsorshipId) -> DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			let sponsorship = Self::sponsorship(id).ok_or(Error::<T>::NoSponsorship)?;
+			ensure!(sponsorship.sponsor == sponsor, Error::<T>::NotSponsor);
+			Self::revoke_sponsorship(id);
+			Ok(())
+		}
+
+		/// Sponsor the fees of calls matching `calls` made by `beneficiaries` with `sponsor` (root only)
+		///
+		/// `sponsor` - The account paying the fees
+		/// `calls` - Filters of the calls sponsored
+		/// `beneficiaries` - The accounts whose calls are sponsored, anyone's if empty
+		/// `spend_limit` - The limit on the fees paid each period, unlimited if `None`
+		pub fn force_sponsor(
+			origin,
+			sponsor: T::AccountId,
+			calls: Vec<CallFilter>,
+			beneficiaries: Vec<T::AccountId>,
+			spend_limit: Option<SpendLimit<T::Balance, T::BlockNumber>>
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			Self::create_sponsorship(Sponsorship { sponsor, calls, beneficiaries, spend_limit }, Zero::zero())?;
+			Ok(())
+		}
+
+		/// Revoke the sponsorship `id` (root only)
+		pub fn force_revoke(origin, #[compact] id: SponsorshipId) -> DispatchResult {
+			ensure_root(origin)?;
+			ensure!(<Sponsorships<T>>::contains_key(id), Error::<T>::NoSponsorship);
+			Self::revoke_sponsorship(id);
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Add `sponsorship` to the registry, after the existing sponsorships, reserving `deposit` from its sponsor
+	pub fn create_sponsorship(
+		sponsorship: Sponsorship<T::AccountId, T::Balance, T::BlockNumber>,
+		deposit: T::Balance,
+	) -> Result<SponsorshipId, DispatchError> {
+		ensure!(!sponsorship.calls.is_empty(), Error::<T>::NoCallFilters);
+		ensure!(
+			sponsorship.calls.len() <= MAX_CALL_FILTERS,
+			Error::<T>::TooManyCallFilters
+		);
+		ensure!(
+			sponsorship.beneficiaries.len() <= MAX_BENEFICIARIES,
+			Error::<T>::TooManyBeneficiaries
+		);
+		if let Some(spend_limit) = &sponsorship.spend_limit {
+			ensure!(!spend_limit.period.is_zero(), Error::<T>::InvalidSpendLimit);
+		}
+		ensure!(
+			Self::sponsorships_of(&sponsorship.sponsor).len() < MAX_SPONSORSHIPS_PER_SPONSOR,
+			Error::<T>::TooManySponsorships
+		);
+		let mut beneficiaries = sponsorship.beneficiaries.clone();
+		beneficiaries.sort();
+		beneficiaries.dedup();
+		if beneficiaries.is_empty() {
+			ensure!(
+				Self::open_sponsorships().len() < MAX_OPEN_SPONSORSHIPS,
+				Error::<T>::TooManyOpenSponsorships
+			);
+		}
+		for beneficiary in &beneficiaries {
+			ensure!(
+				Self::sponsorships_for(beneficiary).len() < MAX_SPONSORSHIPS_PER_BENEFICIARY,
+				Error::<T>::TooManyBeneficiarySponsorships
+			);
+		}
+		let id = Self::next_sponsorship_id();
+		let next_id = id.checked_add(1).ok_or(Error::<T>::NoAvailableSponsorshipId)?;
+		if !deposit.is_zero() {
+			T::Currency::reserve(&sponsorship.sponsor, deposit)?;
+			<Deposits<T>>::insert(id, deposit);
+		}
+
+		NextSponsorshipId::put(next_id);
+		<SponsorshipsOf<T>>::mutate(&sponsorship.sponsor, |ids| ids.push(id));
+		if beneficiaries.is_empty() {
+			OpenSponsorships::mutate(|ids| ids.push(id));
+		}
+		for beneficiary in &beneficiaries {
+			<SponsorshipsFor<T>>::mutate(beneficiary, |ids| ids.push(id));
+		}
+		let sponsor = sponsorship.sponsor.clone();
+		<Sponsorships<T>>::insert(id, sponsorship);
+
+		Self::deposit_event(RawEvent::SponsorshipCreated(id, sponsor));
+		Ok(id)
+	}
+
+	/// Remove the sponsorship `id` from the registry, if it exists, returning its deposit to its sponsor
+	pub fn revoke_sponsorship(id: SponsorshipId) {
+		if let Some(sponsorship) = <Sponsorships<T>>::take(id) {
+			<SponsorshipsOf<T>>::mutate(&sponsorship.sponsor, |ids| ids.retain(|other| *other != id));
+			if sponsorship.beneficiaries.is_empty() {
+				OpenSponsorships::mutate(|ids| ids.retain(|other| *other != id));
+			}
+			for beneficiary in &sponsorship.beneficiaries {
+				<SponsorshipsFor<T>>::mutate(beneficiary, |ids| ids.retain(|other| *other != id));
+			}
+			<Spending<T>>::remove(id);
+			T::Currency::unreserve(&sponsorship.sponsor, <Deposits<T>>::take(id));
+			Self::deposit_event(RawEvent::SponsorshipRevoked(id, sponsorship.sponsor));
+		}
+	}
+
+	/// The first sponsorship able to pay `fee` for the encoded `call` made by `who`, and its sponsor
+	/// The sponsorships naming `who` are checked before those open to anyone, skipping any whose
+	/// sponsor can not afford `fee`
+	pub fn sponsor_for(who: &T::AccountId, call: &[u8], fee: T::Balance) -> Option<(SponsorshipId, T::AccountId)> {
+		Self::find_sponsorship(who, call, fee, |sponsor| Self::can_afford(sponsor, fee))
+	}
+
+	/// Note `payer` paid `fee` for the encoded `call` made by `who`, counting it against the spend limit
+	/// of the sponsorship which paid it, if any
+	pub fn note_fee_paid(who: &T::AccountId, call: &[u8], payer: &T::AccountId, fee: T::Balance) {
+		// The fee has been withdrawn already, so the sponsorship which paid it is the first of `payer`
+		let (id, _) = match Self::find_sponsorship(who, call, fee, |sponsor| sponsor == payer) {
+			Some(sponsorship) => sponsorship,
+			None => return,
+		};
+		if let Some(spend_limit) = Self::sponsorship(id).and_then(|sponsorship| sponsorship.spend_limit) {
+			let now = <frame_system::Module<T>>::block_number();
+			let spent = Self::spent_in_period(id, &spend_limit, now);
+			<Spending<T>>::insert(id, (Self::period_start(&spend_limit, now), spent.saturating_add(fee)));
+		}
+		Self::deposit_event(RawEvent::FeeSponsored(id, who.clone(), fee));
+	}
+
+	/// The first sponsorship covering the encoded `call` made by `who` within its spend limit for `fee`,
+	/// whose sponsor is accepted by `accept`
+	fn find_sponsorship(
+		who: &T::AccountId,
+		call: &[u8],
+		fee: T::Balance,
+		accept: impl Fn(&T::AccountId) -> bool,
+	) -> Option<(SponsorshipId, T::AccountId)> {
+		let now = <frame_system::Module<T>>::block_number();
+		Self::sponsorships_for(who)
+			.into_iter()
+			.chain(Self::open_sponsorships())
+			.find_map(|id| {
+				let sponsorship = Self::sponsorship(id)?;
+				if sponsorship.covers(who, call)
+					&& Self::can_pay(id, &sponsorship, fee, now)
+					&& accept(&sponsorship.sponsor)
+				{
+					Some((id, sponsorship.sponsor))
+				} else {
+					None
+				}
+			})
+	}
+
+	/// Whether `sponsor` can pay `fee` and keep their account alive
+	fn can_afford(sponsor: &T::AccountId, fee: T::Balance) -> bool {
+		T::Currency::free_balance(sponsor)
+			.checked_sub(&fee)
+			.map_or(false, |new_balance| {
+				new_balance >= T::Currency::minimum_balance()
+					&& T::Currency::ensure_can_withdraw(
+						sponsor,
+						fee,
+						WithdrawReason::TransactionPayment.into(),
+						new_balance,
+					)
+					.is_ok()
+			})
+	}
+
+	/// Whether the sponsorship `id` can pay `fee` at block `now`
+	fn can_pay(
+		id: SponsorshipId,
+		sponsorship: &Sponsorship<T::AccountId, T::Balance, T::BlockNumber>,
+		fee: T::Balance,
+		now: T::BlockNumber,
+	) -> bool {
+		match &sponsorship.spend_limit {
+			Some(spend_limit) => Self::spent_in_period(id, spend_limit, now).saturating_add(fee) <= spend_limit.amount,
+			None => true,
+		}
+	}
+
+	/// The fees paid by the sponsorship `id` in the period of `spend_limit` including block `now`
+	fn spent_in_period(
+		id: SponsorshipId,
+		spend_limit: &SpendLimit<T::Balance, T::BlockNumber>,
+		now: T::BlockNumber,
+	) -> T::Balance {
+		let (period_start, spent) = Self::spending(id);
+		if period_start == Self::period_start(spend_limit, now) {
+			spent
+		} else {
+			Zero::zero()
+		}
+	}
+
+	/// The first block of the period of `spend_limit` including block `now`
+	fn period_start(spend_limit: &SpendLimit<T::Balance, T::BlockNumber>, now: T::BlockNumber) -> T::BlockNumber {
+		now - now % spend_limit.period
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::{
+		additional_traits::DummyDispatchVerifier, assert_noop, assert_ok, impl_outer_origin, parameter_types,
+		weights::Weight,
+	};
+	use frame_system::RawOrigin;
+	use sp_core::H256;
+	use sp_runtime::{
+		testing::Header,
+		traits::{BadOrigin, BlakeTwo256, IdentityLookup},
+		Perbill,
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Call = ();
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type Doughnut = ();
+		type DelegatedDispatchVerifier = DummyDispatchVerifier<Self::Doughnut, Self::AccountId>;
+	}
+
+	parameter_types! {
+		pub const CreationFee: u64 = 0;
+		pub const ExistentialDeposit: u64 = 1;
+	}
+
+	impl pallet_balances::Trait for Test {
+		type Balance = u64;
+		type OnReapAccount = System;
+		type OnNewAccount = ();
+		type Event = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type CreationFee = CreationFee;
+	}
+
+	parameter_types! {
+		pub const SponsorshipDeposit: u64 = 10;
+	}
+
+	impl Trait for Test {
+		type Event = ();
+		type Balance = u64;
+		type Currency = Balances;
+		type SponsorshipDeposit = SponsorshipDeposit;
+	}
+
+	type Balances = pallet_balances::Module<Test>;
+	type FeeSponsorship = Module<Test>;
+	type System = frame_system::Module<Test>;
+
+	const SPONSOR: u64 = 1;
+	const ALICE: u64 = 2;
+	const BOB: u64 = 3;
+	const CHARLIE: u64 = 4;
+
+	// Encoded calls, the first two bytes are the module and method indices
+	const TRANSFER: &[u8] = &[4, 1, 69];
+	const TRANSFER_KEEP_ALIVE: &[u8] = &[4, 3, 69];
+	const UPSERT_VALUE: &[u8] = &[12, 0, 69];
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		pallet_balances::GenesisConfig::<Test> {
+			balances: vec![(SPONSOR, 1_000), (BOB, 1_000)],
+		}
+		.assimilate_storage(&mut storage)
+		.unwrap();
+		storage.into()
+	}
+
+	fn signed(who: u64) -> Origin {
+		RawOrigin::Signed(who).into()
+	}
+
+	fn module(module: u8) -> CallFilter {
+		CallFilter { module, method: None }
+	}
+
+	fn method(module: u8, method: u8) -> CallFilter {
+		CallFilter {
+			module,
+			method: Some(method),
+		}
+	}
+
+	#[test]
+	fn call_filter_matches_module_and_method() {
+		assert!(module(4).matches(TRANSFER));
+		assert!(module(4).matches(TRANSFER_KEEP_ALIVE));
+		assert!(!module(4).matches(UPSERT_VALUE));
+		assert!(method(4, 1).matches(TRANSFER));
+		assert!(!method(4, 1).matches(TRANSFER_KEEP_ALIVE));
+		assert!(!module(4).matches(&[]));
+	}
+
+	#[test]
+	fn sponsor_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(FeeSponsorship::sponsor(
+				signed(SPONSOR),
+				vec![module(12)],
+				vec![ALICE, BOB],
+				None
+			));
+			assert_ok!(FeeSponsorship::sponsor(
+				signed(BOB),
+				vec![method(4, 1)],
+				vec![ALICE],
+				None
+			));
+
+			assert_eq!(
+				FeeSponsorship::sponsorship(1),
+				Some(Sponsorship {
+					sponsor: BOB,
+					calls: vec![method(4, 1)],
+					beneficiaries: vec![ALICE],
+					spend_limit: None,
+				})
+			);
+			assert_eq!(FeeSponsorship::sponsorships_of(&SPONSOR), vec![0]);
+			assert_eq!(FeeSponsorship::sponsorships_for(&ALICE), vec![0, 1]);
+			assert_eq!(FeeSponsorship::sponsorships_for(&BOB), vec![0]);
+			assert!(FeeSponsorship::open_sponsorships().is_empty());
+
+			// Each sponsor reserves a deposit
+			assert_eq!(FeeSponsorship::deposit(0), 10);
+			assert_eq!(Balances::reserved_balance(&SPONSOR), 10);
+			assert_eq!(Balances::free_balance(&SPONSOR), 990);
+		});
+	}
+
+	#[test]
+	fn sponsor_rejects_invalid_sponsorships() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				FeeSponsorship::sponsor(signed(SPONSOR), vec![], vec![ALICE], None),
+				Error::<Test>::NoCallFilters
+			);
+			assert_noop!(
+				FeeSponsorship::sponsor(
+					signed(SPONSOR),
+					vec![module(4); MAX_CALL_FILTERS + 1],
+					vec![ALICE],
+					None
+				),
+				Error::<Test>::TooManyCallFilters
+			);
+			assert_noop!(
+				FeeSponsorship::sponsor(
+					signed(SPONSOR),
+					vec![module(4)],
+					vec![ALICE; MAX_BENEFICIARIES + 1],
+					None
+				),
+				Error::<Test>::TooManyBeneficiaries
+			);
+			assert_noop!(
+				FeeSponsorship::sponsor(
+					signed(SPONSOR),
+					vec![module(4)],
+					vec![ALICE],
+					Some(SpendLimit { amount: 100, period: 0 })
+				),
+				Error::<Test>::InvalidSpendLimit
+			);
+			assert_noop!(
+				FeeSponsorship::sponsor(signed(SPONSOR), vec![module(4)], vec![], None),
+				Error::<Test>::NoBeneficiaries
+			);
+			assert_noop!(
+				FeeSponsorship::force_sponsor(signed(SPONSOR), SPONSOR, vec![module(4)], vec![], None),
+				BadOrigin
+			);
+		});
+	}
+
+	#[test]
+	fn sponsor_requires_a_deposit() {
+		new_test_ext().execute_with(|| {
+			assert!(FeeSponsorship::sponsor(signed(ALICE), vec![module(4)], vec![BOB], None).is_err());
+			assert!(FeeSponsorship::sponsorships_of(&ALICE).is_empty());
+			assert!(FeeSponsorship::sponsorships_for(&BOB).is_empty());
+		});
+	}
+
+	#[test]
+	fn sponsorships_are_capped() {
+		new_test_ext().execute_with(|| {
+			// Per sponsor
+			for beneficiary in 0..MAX_SPONSORSHIPS_PER_SPONSOR as u64 {
+				assert_ok!(FeeSponsorship::sponsor(
+					signed(SPONSOR),
+					vec![module(4)],
+					vec![100 + beneficiary],
+					None
+				));
+			}
+			assert_noop!(
+				FeeSponsorship::sponsor(signed(SPONSOR), vec![module(4)], vec![ALICE], None),
+				Error::<Test>::TooManySponsorships
+			);
+
+			// Per beneficiary
+			for sponsor in 0..MAX_SPONSORSHIPS_PER_BENEFICIARY as u64 {
+				assert_ok!(FeeSponsorship::force_sponsor(
+					RawOrigin::Root.into(),
+					200 + sponsor,
+					vec![module(4)],
+					vec![ALICE],
+					None
+				));
+			}
+			assert_noop!(
+				FeeSponsorship::sponsor(signed(BOB), vec![module(4)], vec![CHARLIE, ALICE], None),
+				Error::<Test>::TooManyBeneficiarySponsorships
+			);
+
+			// Open to anyone
+			for sponsor in 0..MAX_OPEN_SPONSORSHIPS as u64 {
+				assert_ok!(FeeSponsorship::force_sponsor(
+					RawOrigin::Root.into(),
+					300 + sponsor,
+					vec![module(4)],
+					vec![],
+					None
+				));
+			}
+			assert_noop!(
+				FeeSponsorship::force_sponsor(RawOrigin::Root.into(), BOB, vec![module(4)], vec![], None),
+				Error::<Test>::TooManyOpenSponsorships
+			);
+
+			// Other accounts may still sponsor their beneficiaries
+			assert_ok!(FeeSponsorship::sponsor(
+				signed(BOB),
+				vec![module(4)],
+				vec![CHARLIE],
+				None
+			));
+			assert_eq!(FeeSponsorship::sponsor_for(&CHARLIE, TRANSFER, 10), Some((32, BOB)));
+		});
+	}
+
+	#[test]
+	fn sponsor_for_finds_the_first_covering_sponsorship() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(FeeSponsorship::sponsor(
+				signed(SPONSOR),
+				vec![module(12)],
+				vec![ALICE],
+				None
+			));
+			assert_ok!(FeeSponsorship::force_sponsor(
+				RawOrigin::Root.into(),
+				BOB,
+				vec![method(4, 1), module(12)],
+				vec![],
+				None
+			));
+
+			assert_eq!(
+				FeeSponsorship::sponsor_for(&ALICE, UPSERT_VALUE, 10),
+				Some((0, SPONSOR))
+			);
+			assert_eq!(FeeSponsorship::sponsor_for(&BOB, UPSERT_VALUE, 10), Some((1, BOB)));
+			assert_eq!(FeeSponsorship::sponsor_for(&ALICE, TRANSFER, 10), Some((1, BOB)));
+			assert_eq!(FeeSponsorship::sponsor_for(&ALICE, TRANSFER_KEEP_ALIVE, 10), None);
+		});
+	}
+
+	#[test]
+	fn sponsor_for_skips_sponsors_unable_to_pay() {
+		new_test_ext().execute_with(|| {
+			// An unfunded sponsor
+			assert_ok!(FeeSponsorship::force_sponsor(
+				RawOrigin::Root.into(),
+				CHARLIE,
+				vec![module(4)],
+				vec![ALICE],
+				None
+			));
+			assert_ok!(FeeSponsorship::sponsor(
+				signed(SPONSOR),
+				vec![module(4)],
+				vec![ALICE],
+				None
+			));
+
+			assert_eq!(FeeSponsorship::sponsor_for(&ALICE, TRANSFER, 100), Some((1, SPONSOR)));
+			// The sponsor's account is kept alive
+			assert_eq!(FeeSponsorship::sponsor_for(&ALICE, TRANSFER, 990), None);
+			assert_eq!(FeeSponsorship::sponsor_for(&ALICE, TRANSFER, 989), Some((1, SPONSOR)));
+
+			let _ = Balances::deposit_creating(&CHARLIE, 1_000);
+			assert_eq!(FeeSponsorship::sponsor_for(&ALICE, TRANSFER, 100), Some((0, CHARLIE)));
+		});
+	}
+
+	#[test]
+	fn spend_limit_resets_each_period() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(FeeSponsorship::force_sponsor(
+				RawOrigin::Root.into(),
+				SPONSOR,
+				vec![module(4)],
+				vec![],
+				Some(SpendLimit {
+					amount: 100,
+					period: 10
+				})
+			));
+
+			assert_eq!(FeeSponsorship::sponsor_for(&ALICE, TRANSFER, 60), Some((0, SPONSOR)));
+			FeeSponsorship::note_fee_paid(&ALICE, TRANSFER, &SPONSOR, 60);
+			assert_eq!(FeeSponsorship::spending(0), (0, 60));
+
+			// Over the limit for this period
+			System::set_block_number(9);
+			assert_eq!(FeeSponsorship::sponsor_for(&BOB, TRANSFER, 41), None);
+			assert_eq!(FeeSponsorship::sponsor_for(&BOB, TRANSFER, 40), Some((0, SPONSOR)));
+
+			// A new period
+			System::set_block_number(10);
+			assert_eq!(FeeSponsorship::sponsor_for(&BOB, TRANSFER, 100), Some((0, SPONSOR)));
+			FeeSponsorship::note_fee_paid(&BOB, TRANSFER, &SPONSOR, 100);
+			assert_eq!(FeeSponsorship::spending(0), (10, 100));
+		});
+	}
+
+	#[test]
+	fn note_fee_paid_ignores_fees_not_paid_by_sponsor() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(FeeSponsorship::sponsor(
+				signed(SPONSOR),
+				vec![module(4)],
+				vec![ALICE],
+				Some(SpendLimit {
+					amount: 100,
+					period: 10
+				})
+			));
+
+			FeeSponsorship::note_fee_paid(&ALICE, TRANSFER, &ALICE, 60);
+			FeeSponsorship::note_fee_paid(&ALICE, UPSERT_VALUE, &SPONSOR, 60);
+			FeeSponsorship::note_fee_paid(&BOB, TRANSFER, &SPONSOR, 60);
+			assert_eq!(FeeSponsorship::spending(0), (0, 0));
+		});
+	}
+
+	#[test]
+	fn revoke_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(FeeSponsorship::sponsor(
+				signed(SPONSOR),
+				vec![module(4)],
+				vec![ALICE],
+				None
+			));
+			assert_ok!(FeeSponsorship::force_sponsor(
+				RawOrigin::Root.into(),
+				SPONSOR,
+				vec![module(12)],
+				vec![],
+				None
+			));
+			assert_eq!(Balances::reserved_balance(&SPONSOR), 10);
+
+			assert_noop!(FeeSponsorship::revoke(signed(BOB), 0), Error::<Test>::NotSponsor);
+			assert_noop!(FeeSponsorship::revoke(signed(SPONSOR), 2), Error::<Test>::NoSponsorship);
+
+			assert_ok!(FeeSponsorship::revoke(signed(SPONSOR), 0));
+			assert_eq!(FeeSponsorship::sponsorship(0), None);
+			assert_eq!(FeeSponsorship::sponsorships_of(&SPONSOR), vec![1]);
+			assert!(FeeSponsorship::sponsorships_for(&ALICE).is_empty());
+			assert_eq!(FeeSponsorship::sponsor_for(&ALICE, TRANSFER, 10), None);
+			// The deposit is returned
+			assert_eq!(Balances::reserved_balance(&SPONSOR), 0);
+			assert_eq!(Balances::free_balance(&SPONSOR), 1_000);
+
+			assert_noop!(FeeSponsorship::force_revoke(signed(SPONSOR), 1), BadOrigin);
+			assert_ok!(FeeSponsorship::force_revoke(RawOrigin::Root.into(), 1));
+			assert!(FeeSponsorship::sponsorships_of(&SPONSOR).is_empty());
+			assert!(FeeSponsorship::open_sponsorships().is_empty());
+		});
+	}
+}
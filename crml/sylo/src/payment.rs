@@ -15,12 +15,34 @@
 
 //! Manage the authorized accounts set for the Sylo fee payment
 
-use frame_support::{decl_module, decl_storage, ensure, weights::SimpleDispatchInfo};
+use frame_support::{
+	decl_module, decl_storage, ensure,
+	weights::{SimpleDispatchInfo, Weight},
+};
 use frame_system::{ensure_root, ensure_signed};
-use sp_runtime::DispatchResult;
+use sp_runtime::{traits::Zero, DispatchResult};
 use sp_std::prelude::*;
 
-pub trait Trait: frame_system::Trait {}
+/// The weight of reading or writing a storage item
+pub const STORAGE_ACCESS_WEIGHT: Weight = 10_000;
+
+/// Something to notify when the account paying Sylo fees changes
+pub trait OnPaymentAccountChange<AccountId> {
+	/// The payment account has changed from `previous` to `current`, `None` if there is none
+	/// Returns the weight of handling the change
+	fn on_payment_account_change(previous: Option<AccountId>, current: Option<AccountId>) -> Weight;
+}
+
+impl<AccountId> OnPaymentAccountChange<AccountId> for () {
+	fn on_payment_account_change(_previous: Option<AccountId>, _current: Option<AccountId>) -> Weight {
+		Zero::zero()
+	}
+}
+
+pub trait Trait: frame_system::Trait {
+	/// Notified when the payment account changes e.g. to sponsor Sylo fees with it
+	type OnPaymentAccountChange: OnPaymentAccountChange<Self::AccountId>;
+}
 
 const NOT_SYLO_PAYER: &str = "You are not a Sylo payer!";
 
@@ -28,17 +50,31 @@ decl_storage! {
 	trait Store for Module<T: Trait> as SyloFeePayment {
 		/// Accounts which have authority to pay for Sylo fees on behalf of the users
 		AuthorisedPayers get(fn authorised_payers): Vec<T::AccountId>;
+		/// Whether the payment account set before `OnPaymentAccountChange` existed has been notified
+		PaymentAccountNotified get(fn payment_account_notified): bool;
 	}
 }
 
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin, system = frame_system {
 
+		/// Notify of the payment account set before the hook existed, on the first upgrade only
+		fn on_runtime_upgrade() -> Weight {
+			if Self::payment_account_notified() {
+				return STORAGE_ACCESS_WEIGHT;
+			}
+			PaymentAccountNotified::put(true);
+			// Reading and writing the flag and reading the payers, then the hook
+			(3 * STORAGE_ACCESS_WEIGHT).saturating_add(
+				T::OnPaymentAccountChange::on_payment_account_change(None, Self::payment_account())
+			)
+		}
+
 		/// Add `account_id` as an authorized Sylo fee payer. Only Sudo can set a payment account.
 		#[weight = SimpleDispatchInfo::FixedOperational(0)]
 		pub fn set_payment_account(origin, account_id: T::AccountId) -> DispatchResult {
 			ensure_root(origin)?;
-			<AuthorisedPayers<T>>::mutate(|v|{if !v.contains(&account_id) {v.push(account_id)}});
+			Self::update_payers(|v|{if !v.contains(&account_id) {v.push(account_id)}});
 			Ok(())
 		}
 
@@ -48,13 +84,23 @@ decl_module! {
 		pub fn revoke_payment_account_self(origin) -> DispatchResult {
 			let account_id = ensure_signed(origin)?;
 			ensure!(Self::authorised_payers().contains(&account_id), NOT_SYLO_PAYER);
-			<AuthorisedPayers<T>>::mutate(|v|v.retain(|x| *x != account_id));
+			Self::update_payers(|v|v.retain(|x| *x != account_id));
 			Ok(())
 		}
 	}
 }
 
 impl<T: Trait> Module<T> {
+	/// Update the authorised payers with `f`, notifying if the payment account changes
+	fn update_payers(f: impl FnOnce(&mut Vec<T::AccountId>)) {
+		let previous = Self::payment_account();
+		<AuthorisedPayers<T>>::mutate(f);
+		let current = Self::payment_account();
+		if previous != current {
+			T::OnPaymentAccountChange::on_payment_account_change(previous, current);
+		}
+	}
+
 	/// Return an account that is set for payment, or `None` when nothing is set.
 	/// In the future, we can make this function smart so it returns the account with enough money in it.
 	pub fn payment_account() -> Option<T::AccountId> {
@@ -66,14 +112,39 @@ impl<T: Trait> Module<T> {
 mod tests {
 	use super::*;
 	use crate::mock::{ExtBuilder, Origin, Test};
-	use frame_support::assert_ok;
+	use frame_support::{assert_ok, traits::OnRuntimeUpgrade};
 	use frame_system::RawOrigin;
 	use sp_core::H256;
 	use sp_runtime::DispatchError::Other;
+	use std::cell::RefCell;
 
 	type SyloModule = Module<Test>;
 
-	impl Trait for Test {}
+	const PAYMENT_ACCOUNT_CHANGE_WEIGHT: Weight = 1_000;
+
+	thread_local! {
+		static PAYMENT_ACCOUNT_CHANGES: RefCell<Vec<(Option<H256>, Option<H256>)>> = RefCell::new(Vec::new());
+	}
+
+	/// Records the payment account changes it is notified of
+	pub struct PaymentAccountChanges;
+
+	impl PaymentAccountChanges {
+		fn take() -> Vec<(Option<H256>, Option<H256>)> {
+			PAYMENT_ACCOUNT_CHANGES.with(|v| v.replace(Vec::new()))
+		}
+	}
+
+	impl OnPaymentAccountChange<H256> for PaymentAccountChanges {
+		fn on_payment_account_change(previous: Option<H256>, current: Option<H256>) -> Weight {
+			PAYMENT_ACCOUNT_CHANGES.with(|v| v.borrow_mut().push((previous, current)));
+			PAYMENT_ACCOUNT_CHANGE_WEIGHT
+		}
+	}
+
+	impl Trait for Test {
+		type OnPaymentAccountChange = PaymentAccountChanges;
+	}
 
 	#[test]
 	fn set_payment_account() {
@@ -140,4 +211,22 @@ mod tests {
 			assert!(!SyloModule::authorised_payers().contains(&payer_a));
 		});
 	}
+
+	#[test]
+	fn on_runtime_upgrade_notifies_of_the_payment_account_once() {
+		ExtBuilder::default().build().execute_with(|| {
+			let payer_a = H256::from_low_u64_be(2);
+			<AuthorisedPayers<Test>>::put(vec![payer_a]);
+			PaymentAccountChanges::take();
+
+			assert_eq!(
+				SyloModule::on_runtime_upgrade(),
+				3 * STORAGE_ACCESS_WEIGHT + PAYMENT_ACCOUNT_CHANGE_WEIGHT
+			);
+			assert_eq!(SyloModule::on_runtime_upgrade(), STORAGE_ACCESS_WEIGHT);
+
+			assert!(SyloModule::payment_account_notified());
+			assert_eq!(PaymentAccountChanges::take(), vec![(None, Some(payer_a))]);
+		});
+	}
 }
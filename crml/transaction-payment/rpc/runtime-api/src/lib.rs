@@ -0,0 +1,84 @@
+// Copyright 2020 Parity Technologies (UK) Ltd. and Centrality Investments Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API definition required by CENNZnet transaction payment RPC extensions.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use cennznet_primitives::types::FeePaymentError;
+use codec::{Codec, Decode, Encode};
+use frame_support::weights::{DispatchClass, Weight};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::{transaction_validity::TransactionValidityError, RuntimeDebug};
+use sp_std::prelude::*;
+
+/// The fee of an extrinsic, who pays it and what they pay it with
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct FeeInfo<AccountId, AssetId, Balance> {
+	/// Weight of the extrinsic's call
+	pub weight: Weight,
+	/// Class of the extrinsic's call
+	pub class: DispatchClass,
+	/// The account paying the fee, none for unsigned extrinsics
+	pub payer: Option<AccountId>,
+	/// The fee in the CENNZnet fee asset, including the tip
+	pub partial_fee: Balance,
+	/// The fixed base fee
+	pub base_fee: Balance,
+	/// The fee for the extrinsic's length
+	pub length_fee: Balance,
+	/// The fee for the call's weight
+	pub weight_fee: Balance,
+	/// The fee for recent network load
+	pub peak_adjustment_fee: Balance,
+	/// The tip offered by the extrinsic
+	pub tip: Balance,
+	/// The fee priced in the asset nominated by the extrinsic's `FeeExchange`, if it has one
+	pub fee_exchange: Option<FeeExchangeInfo<AssetId, Balance>>,
+}
+
+/// The price of a fee paid through CENNZX-Spot
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct FeeExchangeInfo<AssetId, Balance> {
+	/// The asset the fee is paid with
+	pub asset_id: AssetId,
+	/// The amount of `asset_id` sold to buy the fee
+	pub amount: Balance,
+}
+
+sp_api::decl_runtime_apis! {
+	/// The RPC API to query the fees of extrinsics with their CENNZnet fee payment options
+	pub trait FeeInfoApi<AccountId, AssetId, Balance> where
+		AccountId: Codec,
+		AssetId: Codec,
+		Balance: Codec,
+	{
+		/// Query the fee of `uxt`, which has an encoded length of `len`, who pays it and what they pay it with
+		/// Fails with the reason the extrinsic would be rejected if its fee can't be paid with its `FeeExchange`
+		fn query_fee_info(
+			uxt: Block::Extrinsic,
+			len: u32,
+		) -> Result<FeeInfo<AccountId, AssetId, Balance>, TransactionValidityError>;
+
+		/// The reason for the `InvalidTransaction::Custom` `code` of a fee which cannot be paid, if it is one
+		fn fee_payment_error(code: u8) -> Option<FeePaymentError>;
+	}
+}
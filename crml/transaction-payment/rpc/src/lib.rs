@@ -0,0 +1,142 @@
+// Copyright 2020 Parity Technologies (UK) Ltd. and Centrality Investments Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Node-specific RPC methods for querying CENNZnet transaction fees.
+
+use std::sync::Arc;
+
+use codec::{Codec, Decode};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{
+	generic::BlockId,
+	traits::Block as BlockT,
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+};
+
+pub use self::gen_client::Client as TransactionPaymentClient;
+pub use crml_transaction_payment_rpc_runtime_api::{
+	self as runtime_api, FeeExchangeInfo, FeeInfo, FeeInfoApi as FeeInfoRuntimeApi, FeePaymentError,
+};
+
+/// Transaction payment RPC methods.
+#[rpc]
+pub trait FeeInfoApi<BlockHash, AccountId, AssetId, Balance> {
+	/// Query the fee of the encoded extrinsic `encoded_xt`, who pays it and what they pay it with
+	#[rpc(name = "payment_queryFeeInfo")]
+	fn query_fee_info(&self, encoded_xt: Bytes, at: Option<BlockHash>) -> Result<FeeInfo<AccountId, AssetId, Balance>>;
+
+	/// The reason a transaction was rejected with the custom validity `code`, if its fee could not be paid
+	#[rpc(name = "payment_feePaymentError")]
+	fn fee_payment_error(&self, code: u8, at: Option<BlockHash>) -> Result<Option<FeePaymentError>>;
+}
+
+/// An implementation of CENNZnet transaction payment specific RPC methods.
+pub struct TransactionPayment<C, T> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<T>,
+}
+
+impl<C, T> TransactionPayment<C, T> {
+	/// Create new `TransactionPayment` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		TransactionPayment {
+			client,
+			_marker: Default::default(),
+		}
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The transaction was not decodable.
+	DecodeError,
+	/// The call to runtime failed.
+	Runtime,
+	/// The fee could not be paid, the reason is given in the error data.
+	CannotPayFee,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::Runtime => 1,
+			Error::DecodeError => 2,
+			Error::CannotPayFee => 3,
+		}
+	}
+}
+
+impl<C, Block, AccountId, AssetId, Balance> FeeInfoApi<<Block as BlockT>::Hash, AccountId, AssetId, Balance>
+	for TransactionPayment<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: FeeInfoRuntimeApi<Block, AccountId, AssetId, Balance>,
+	AccountId: Codec,
+	AssetId: Codec,
+	Balance: Codec,
+{
+	fn query_fee_info(
+		&self,
+		encoded_xt: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<FeeInfo<AccountId, AssetId, Balance>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let encoded_len = encoded_xt.len() as u32;
+		let uxt: Block::Extrinsic = Decode::decode(&mut &*encoded_xt).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::DecodeError.into()),
+			message: "Unable to query fee info.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?;
+
+		let result = api.query_fee_info(&at, uxt, encoded_len).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::Runtime.into()),
+			message: "Unable to query fee info.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?;
+		result.map_err(|e| {
+			// Explain fee payment errors by their reason rather than code
+			let reason = match e {
+				TransactionValidityError::Invalid(InvalidTransaction::Custom(code)) => {
+					api.fee_payment_error(&at, code).ok().flatten()
+				}
+				_ => None,
+			};
+			RpcError {
+				code: ErrorCode::ServerError(Error::CannotPayFee.into()),
+				message: "Cannot pay the transaction fee.".into(),
+				data: Some(reason.map_or_else(|| format!("{:?}", e), |r| format!("{:?}", r)).into()),
+			}
+		})
+	}
+
+	fn fee_payment_error(&self, code: u8, at: Option<<Block as BlockT>::Hash>) -> Result<Option<FeePaymentError>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.fee_payment_error(&at, code).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::Runtime.into()),
+			message: "Unable to query fee payment error.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+}
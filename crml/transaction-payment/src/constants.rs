@@ -19,6 +19,11 @@
 //! Transaction Payment Customized Error Code Constants
 
 pub mod error_code {
+	//! The codes of `InvalidTransaction::Custom` errors for fees which cannot be paid.
+	//! See `FeePaymentError` for the code table, and to turn a code into its reason.
+	use cennznet_primitives::types::FeePaymentError;
+
+	pub const INSUFFICIENT_BALANCE: u8 = 195;
 	pub const MINIMUM_BUY_REQUIREMENT_NOT_MET: u8 = 197;
 	pub const CANNOT_TRADE_ZERO: u8 = 199;
 	pub const INSUFFICIENT_FEE_ASSET_BALANCE: u8 = 200;
@@ -28,21 +33,63 @@ pub mod error_code {
 	pub const ASSET_CANNOT_SWAP_FOR_ITSELF: u8 = 204;
 	pub const INSUFFICIENT_EXCHANGE_POOL_RESERVE: u8 = 205;
 	pub const MAXIMUM_SELL_REQUIREMENT_NOT_MET: u8 = 206;
-	pub const INSUFFICIENT_BALANCE: u8 = 195;
+	pub const NO_FEE_EXCHANGE_OPTIONS: u8 = 207;
+	pub const TOO_MANY_FEE_EXCHANGE_OPTIONS: u8 = 208;
+	pub const EMPTY_EXCHANGE_POOL: u8 = 209;
+	pub const EXCHANGE_PAUSED: u8 = 210;
+	pub const PRICE_MOVEMENT_LIMIT_EXCEEDED: u8 = 211;
+
+	/// The reason for a fee payment error `code`, if it is one
+	pub fn fee_payment_error(code: u8) -> Option<FeePaymentError> {
+		FeePaymentError::from_code(code)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::error_code::*;
+	use cennznet_primitives::types::FeePaymentError;
 
-	// Matches and converts crml-cennzx-spot module errors, such that
-	// they are propagated in crml-transaction-payment module
-	pub fn buy_fee_asset_error_msg_to_code(message: &'static str) -> u8 {
-		match message {
-			"InsufficientBalance" => INSUFFICIENT_BALANCE,
-			"InsufficientExchangePoolReserve" => INSUFFICIENT_EXCHANGE_POOL_RESERVE,
-			"CannotTradeZero" => CANNOT_TRADE_ZERO,
-			"MinimumBuyRequirementNotMet" => MINIMUM_BUY_REQUIREMENT_NOT_MET,
-			"AssetCannotSwapForItself" => ASSET_CANNOT_SWAP_FOR_ITSELF,
-			"MaximumSellRequirementNotMet" => MAXIMUM_SELL_REQUIREMENT_NOT_MET,
-			"InvalidAssetId" => INVALID_ASSET_ID,
-			"InsufficientLiquidity" => INSUFFICIENT_LIQUIDITY,
-			_ => UNKNOWN_BUY_FEE_ASSET,
+	#[test]
+	fn error_codes_match_fee_payment_errors() {
+		for (code, reason) in &[
+			(INSUFFICIENT_BALANCE, FeePaymentError::InsufficientBalance),
+			(
+				MINIMUM_BUY_REQUIREMENT_NOT_MET,
+				FeePaymentError::MinimumBuyRequirementNotMet,
+			),
+			(CANNOT_TRADE_ZERO, FeePaymentError::CannotTradeZero),
+			(
+				INSUFFICIENT_FEE_ASSET_BALANCE,
+				FeePaymentError::InsufficientFeeAssetBalance,
+			),
+			(INVALID_ASSET_ID, FeePaymentError::InvalidAssetId),
+			(UNKNOWN_BUY_FEE_ASSET, FeePaymentError::Unknown),
+			(INSUFFICIENT_LIQUIDITY, FeePaymentError::InsufficientLiquidity),
+			(ASSET_CANNOT_SWAP_FOR_ITSELF, FeePaymentError::AssetCannotSwapForItself),
+			(
+				INSUFFICIENT_EXCHANGE_POOL_RESERVE,
+				FeePaymentError::InsufficientExchangePoolReserve,
+			),
+			(
+				MAXIMUM_SELL_REQUIREMENT_NOT_MET,
+				FeePaymentError::MaximumSellRequirementNotMet,
+			),
+			(NO_FEE_EXCHANGE_OPTIONS, FeePaymentError::NoFeeExchangeOptions),
+			(
+				TOO_MANY_FEE_EXCHANGE_OPTIONS,
+				FeePaymentError::TooManyFeeExchangeOptions,
+			),
+			(EMPTY_EXCHANGE_POOL, FeePaymentError::EmptyExchangePool),
+			(EXCHANGE_PAUSED, FeePaymentError::ExchangePaused),
+			(
+				PRICE_MOVEMENT_LIMIT_EXCEEDED,
+				FeePaymentError::PriceMovementLimitExceeded,
+			),
+		] {
+			assert_eq!(reason.code(), *code);
+			assert_eq!(fee_payment_error(*code), Some(*reason));
 		}
+		assert_eq!(fee_payment_error(0), None);
 	}
 }
@@ -37,19 +37,19 @@ use cennznet_primitives::{
 	types::FeeExchange,
 };
 use codec::{Decode, Encode};
+use crml_transaction_payment_rpc_runtime_api::{FeeExchangeInfo, FeeInfo};
 use frame_support::{
-	decl_module, decl_storage,
-	dispatch::DispatchError,
-	storage,
+	decl_event, decl_module, decl_storage,
 	traits::{Currency, ExistenceRequirement, Get, Imbalance, OnUnbalanced, WithdrawReason},
-	weights::{DispatchInfo, GetDispatchInfo, Weight},
+	weights::{DispatchClass, DispatchInfo, GetDispatchInfo, Weight},
 	Parameter,
 };
 use pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo;
 use sp_arithmetic::traits::BaseArithmetic;
 use sp_runtime::{
 	traits::{
-		CheckedSub, Convert, MaybeSerializeDeserialize, Member, SaturatedConversion, Saturating, SignedExtension, Zero,
+		CheckedSub, Convert, MaybeSerializeDeserialize, Member, One, SaturatedConversion, Saturating, SignedExtension,
+		Zero,
 	},
 	transaction_validity::{
 		InvalidTransaction, TransactionPriority, TransactionValidity, TransactionValidityError, ValidTransaction,
@@ -65,8 +65,6 @@ type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trai
 type NegativeImbalanceOf<T> =
 	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
 
-pub const GAS_FEE_EXCHANGE_KEY: &[u8] = b"gas-fee-exchange-key";
-
 /// This is an interface that can return the account id of a fee payer for a specific call.
 /// If there is no such an individual for a call, it returns None which means the submitter of
 /// the extrinsic is going to pay the fee.
@@ -77,9 +75,16 @@ pub trait FeePayer {
 	/// The user account identifier type for the runtime.
 	type AccountId;
 
-	/// Return the account id of the fee payer for `call`. Return None if the fee payer
+	/// The type to denote monetary values
+	type Balance;
+
+	/// Return the account id of the fee payer of `fee` for `call` submitted by `who`. Return None if the fee payer
 	/// is the same as the submitter of the call
-	fn fee_payer(call: &Self::Call) -> Option<Self::AccountId>;
+	fn fee_payer(who: &Self::AccountId, call: &Self::Call, fee: Self::Balance) -> Option<Self::AccountId>;
+
+	/// Note `payer` has paid `fee` for `call` submitted by `who`.
+	/// Fees refunded after dispatch are not noted.
+	fn note_fee_paid(_who: &Self::AccountId, _call: &Self::Call, _payer: &Self::AccountId, _fee: Self::Balance) {}
 }
 
 pub trait Trait: frame_system::Trait {
@@ -109,6 +114,9 @@ pub trait Trait: frame_system::Trait {
 	/// Update the multiplier of the next block, based on the previous block's weight.
 	type FeeMultiplierUpdate: Convert<Multiplier, Multiplier>;
 
+	/// The multiple of their fee operational transactions are prioritised as tipping.
+	type OperationalFeeMultiplier: Get<u8>;
+
 	/// A service which will buy fee assets if signalled by the extrinsic.
 	type BuyFeeAsset: BuyFeeAsset<
 		AccountId = Self::AccountId,
@@ -120,17 +128,36 @@ pub trait Trait: frame_system::Trait {
 	type GasMeteredCallResolver: IsGasMeteredCall<Call = <Self as frame_system::Trait>::Call>;
 
 	/// A fee payer, if specified for a call, is an account that can be different from the submitter of an extrinsic.
-	type FeePayer: FeePayer<Call = Self::Call, AccountId = Self::AccountId>;
+	type FeePayer: FeePayer<Call = Self::Call, AccountId = Self::AccountId, Balance = BalanceOf<Self>>;
+
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
 }
 
 decl_storage! {
 	trait Store for Module<T: Trait> as TransactionPayment {
 		pub NextFeeMultiplier get(fn next_fee_multiplier): Multiplier = Multiplier::from_parts(0);
+		/// The context of the signed extrinsic's call being dispatched.
+		/// It is put in `pre_dispatch` and taken in `post_dispatch`.
+		CurrentDispatch get(fn current_dispatch): Option<DispatchContext<T::AssetId, BalanceOf<T>>>;
 	}
 }
 
+decl_event!(
+	pub enum Event<T>
+	where
+		<T as frame_system::Trait>::AccountId,
+		Balance = BalanceOf<T>,
+	{
+		/// The fee for a transaction has been settled (payer, fee charged, fee refunded)
+		TransactionFeeSettled(AccountId, Balance, Balance),
+	}
+);
+
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
 		/// The fee to be paid for making a transaction; the base.
 		const TransactionBaseFee: BalanceOf<T> = T::TransactionBaseFee::get();
 
@@ -141,11 +168,36 @@ decl_module! {
 			NextFeeMultiplier::mutate(|fm| {
 				*fm = T::FeeMultiplierUpdate::convert(*fm)
 			});
+			// Nothing is left over for the next block
+			CurrentDispatch::<T>::kill();
 		}
 	}
 }
 
 impl<T: Trait> Module<T> {
+	/// Note `weight` declared by the call being dispatched as unused, so the fee paid for it is refunded after
+	/// dispatch. Calls which can't tell how much weight they use until they execute should declare their
+	/// worst case weight and note what they didn't use.
+	/// Only a signed extrinsic's call is refunded, weight noted at any other time is discarded.
+	pub fn note_unused_weight(weight: Weight) {
+		CurrentDispatch::<T>::mutate(|context| {
+			if let Some(context) = context {
+				context.unused_weight = context.unused_weight.saturating_add(weight);
+			}
+		});
+	}
+
+	/// The weight noted as unused by the call being dispatched so far
+	pub fn unused_weight() -> Weight {
+		Self::current_dispatch().map_or(0, |context| context.unused_weight)
+	}
+
+	/// The `FeeExchange` paying for the gas of the gas metered call being dispatched, with the transaction fee
+	/// deducted from its max payment. It is only set while a signed extrinsic's call is dispatched.
+	pub fn gas_fee_exchange() -> Option<FeeExchange<T::AssetId, BalanceOf<T>>> {
+		Self::current_dispatch().and_then(|context| context.gas_fee_exchange)
+	}
+
 	/// Query the data that we know about the fee of a given `call`.
 	///
 	/// As this module is not and cannot be aware of the internals of a signed extension, it only
@@ -153,11 +205,8 @@ impl<T: Trait> Module<T> {
 	///
 	/// All dispatchables must be annotated with weight and will have some fee info. This function
 	/// always returns.
-	// NOTE: we can actually make it understand `ChargeTransactionPayment`, but would be some hassle
-	// for sure. We have to make it aware of the index of `ChargeTransactionPayment` in `Extra`.
-	// Alternatively, we could actually execute the tx's per-dispatch and record the balance of the
-	// sender before and after the pipeline.. but this is way too much hassle for a very very little
-	// potential gain in the future.
+	// NOTE: see `query_fee_info` for the fee understanding `ChargeTransactionPayment`, which needs the
+	// runtime to pick `ChargeTransactionPayment` out of its `Extra`.
 	pub fn query_info<Extrinsic: GetDispatchInfo>(
 		unchecked_extrinsic: Extrinsic,
 		len: u32,
@@ -177,6 +226,88 @@ impl<T: Trait> Module<T> {
 			partial_fee,
 		}
 	}
+
+	/// Query the fee of `call`, who pays it and what they pay it with.
+	///
+	/// `signed` is the signer of the extrinsic and its `ChargeTransactionPayment`, if it is signed.
+	/// The fee is priced in the first of the extrinsic's `FeeExchange` currencies able to buy it, as
+	/// `ChargeTransactionPayment` will pay it, although the payer's balances are not checked.
+	pub fn query_fee_info(
+		call: &T::Call,
+		dispatch_info: DispatchInfo,
+		signed: Option<(T::AccountId, ChargeTransactionPayment<T>)>,
+		len: u32,
+	) -> Result<FeeInfo<T::AccountId, T::AssetId, BalanceOf<T>>, TransactionValidityError>
+	where
+		T: Send + Sync,
+		BalanceOf<T>: Send + Sync,
+	{
+		let (who, tip, fee_exchange) = match signed {
+			Some((who, charge)) => (Some(who), charge.tip, charge.fee_exchange),
+			None => (None, Zero::zero(), None),
+		};
+
+		let partial_fee = <ChargeTransactionPayment<T>>::compute_fee(len, dispatch_info, tip);
+		let payer = who.map(|who| T::FeePayer::fee_payer(&who, call, partial_fee).unwrap_or(who));
+		let (base_fee, fee_parts) = if dispatch_info.pays_fee {
+			(
+				T::TransactionBaseFee::get(),
+				<ChargeTransactionPayment<T>>::compute_fee_parts(len, dispatch_info),
+			)
+		} else {
+			(Zero::zero(), FeeParts::new(Zero::zero(), Zero::zero(), Zero::zero()))
+		};
+		let fee_exchange = match fee_exchange {
+			Some(exchange) if !partial_fee.is_zero() => Some(Self::price_fee(partial_fee, &exchange)?),
+			_ => None,
+		};
+		let DispatchInfo { weight, class, .. } = dispatch_info;
+
+		Ok(FeeInfo {
+			weight,
+			class,
+			payer,
+			partial_fee,
+			base_fee,
+			length_fee: fee_parts.length_fee,
+			weight_fee: fee_parts.weight_fee,
+			peak_adjustment_fee: fee_parts.peak_adjustment_fee,
+			tip,
+			fee_exchange,
+		})
+	}
+
+	/// Price `fee` in the first of the `exchange` fee currencies able to buy it
+	fn price_fee(
+		fee: BalanceOf<T>,
+		exchange: &FeeExchange<T::AssetId, BalanceOf<T>>,
+	) -> Result<FeeExchangeInfo<T::AssetId, BalanceOf<T>>, TransactionValidityError> {
+		exchange
+			.check_options()
+			.map_err(|e| InvalidTransaction::Custom(e.code()))?;
+		let mut code = error_code::UNKNOWN_BUY_FEE_ASSET;
+		for option in exchange.options() {
+			match T::BuyFeeAsset::buy_fee_asset_price(fee, &option) {
+				Ok(amount) => {
+					return Ok(FeeExchangeInfo {
+						asset_id: option.asset_id(),
+						amount,
+					})
+				}
+				Err(e) => code = T::BuyFeeAsset::fee_payment_error(e).code(),
+			}
+		}
+		Err(InvalidTransaction::Custom(code).into())
+	}
+}
+
+/// The context of a signed extrinsic's call while it is dispatched
+#[derive(Debug, Encode, Decode, Clone, Eq, PartialEq)]
+pub struct DispatchContext<AssetId, Balance> {
+	/// Weight declared by the call which it did not use
+	pub unused_weight: Weight,
+	/// The `FeeExchange` paying for gas, if the call is gas metered
+	pub gas_fee_exchange: Option<FeeExchange<AssetId, Balance>>,
 }
 
 #[derive(Debug, Encode, Decode, Clone, Eq, PartialEq)]
@@ -285,6 +416,137 @@ impl<T: Trait + Send + Sync> ChargeTransactionPayment<T> {
 			peak_adjustment_fee.saturating_sub(adjustable_fee),
 		);
 	}
+
+	/// Compute the priority of a transaction paying `fee`, tip included, in the transaction queue.
+	///
+	/// A transaction's fee is scaled by the number of such transactions fitting in a block by
+	/// weight or length, whichever is fewer, so transactions are ordered by the fee they pay for the
+	/// share of a block they fill rather than their total fee.
+	/// Operational transactions are prioritised as tipping `OperationalFeeMultiplier` times their fee.
+	pub fn get_priority(
+		length: u32,
+		info: <Self as SignedExtension>::DispatchInfo,
+		fee: BalanceOf<T>,
+	) -> TransactionPriority
+	where
+		BalanceOf<T>: Sync + Send,
+	{
+		let max_block_weight = <T as frame_system::Trait>::MaximumBlockWeight::get();
+		let max_block_length = <T as frame_system::Trait>::MaximumBlockLength::get();
+		let bounded_weight = info.weight.max(1).min(max_block_weight);
+		let bounded_length = length.max(1).min(max_block_length);
+		let max_tx_per_block = (max_block_weight / bounded_weight).min(max_block_length / bounded_length);
+		let scaled = |value: BalanceOf<T>| value.saturating_mul(<BalanceOf<T>>::from(max_tx_per_block));
+
+		// Free transactions are ordered by the share of a block they fill
+		let fee = fee.saturating_add(One::one());
+		let priority = match info.class {
+			DispatchClass::Operational => {
+				let virtual_tip = fee.saturating_mul(<BalanceOf<T>>::from(T::OperationalFeeMultiplier::get()));
+				scaled(fee).saturating_add(scaled(virtual_tip))
+			}
+			_ => scaled(fee),
+		};
+		priority.saturated_into::<TransactionPriority>()
+	}
+
+	/// Withdraw the fee for a transaction from its payer in the native fee currency, buying it with the user's
+	/// nominated fee currency first if a `FeeExchange` is set.
+	/// Returns the fee, the payer with the fee withdrawn from them unless the fee is zero, and the `FeeExchange`
+	/// left to pay for gas if the call is gas metered.
+	#[allow(clippy::type_complexity)]
+	fn withdraw_fee(
+		&self,
+		who: &T::AccountId,
+		call: &T::Call,
+		info: <Self as SignedExtension>::DispatchInfo,
+		len: usize,
+	) -> Result<
+		(
+			BalanceOf<T>,
+			Option<(T::AccountId, NegativeImbalanceOf<T>)>,
+			Option<FeeExchange<T::AssetId, BalanceOf<T>>>,
+		),
+		TransactionValidityError,
+	>
+	where
+		BalanceOf<T>: Sync + Send,
+	{
+		// Reject a `FeeExchange` nominating no fee assets or too many to try
+		if let Some(exchange) = &self.fee_exchange {
+			exchange
+				.check_options()
+				.map_err(|e| InvalidTransaction::Custom(e.code()))?;
+		}
+
+		let fee = Self::compute_fee(len as u32, info, self.tip);
+
+		// How much user nominated fee asset has been spent so far
+		// used for accounting the 'max payment' preference
+		let mut exchange_asset_spent: BalanceOf<T> = Zero::zero();
+		// The user nominated fee currency as a `FeeExchangeV1`, the most preferred one
+		// or the one which bought the fee if it was needed
+		let mut fee_exchange = self
+			.fee_exchange
+			.as_ref()
+			.and_then(|exchange| exchange.options().into_iter().next());
+
+		// The fee payer and the fee withdrawn from them
+		let mut payment = None;
+
+		// Only mess with balances if the fee is not zero.
+		if !fee.is_zero() {
+			let payer = T::FeePayer::fee_payer(who, call, fee).unwrap_or(who.clone());
+			if let Some(exchange) = &self.fee_exchange {
+				// Buy the CENNZnet fee currency paying with the first of the user's nominated fee currencies
+				// able to pay for it
+				let mut code = error_code::UNKNOWN_BUY_FEE_ASSET;
+				fee_exchange = None;
+				for option in exchange.options() {
+					match T::BuyFeeAsset::buy_fee_asset(&payer, fee, &option) {
+						Ok(spent) => {
+							exchange_asset_spent = spent;
+							fee_exchange = Some(option);
+							break;
+						}
+						Err(e) => code = T::BuyFeeAsset::fee_payment_error(e).code(),
+					}
+				}
+				if fee_exchange.is_none() {
+					return Err(InvalidTransaction::Custom(code).into());
+				}
+			}
+
+			let withdraw_reason = if self.tip.is_zero() {
+				WithdrawReason::TransactionPayment.into()
+			} else {
+				WithdrawReason::TransactionPayment | WithdrawReason::Tip
+			};
+			// Pay for the transaction `fee` in the native fee currency
+			let imbalance = match T::Currency::withdraw(&payer, fee, withdraw_reason, ExistenceRequirement::KeepAlive) {
+				Ok(imbalance) => imbalance,
+				Err(_) => return Err(InvalidTransaction::Custom(error_code::INSUFFICIENT_FEE_ASSET_BALANCE).into()),
+			};
+			T::FeePayer::note_fee_paid(who, call, &payer, fee);
+			payment = Some((payer, imbalance));
+		}
+
+		// Certain contract module calls require gas metering and special handling for
+		// multi-currency gas payment, gas is paid with what is left of the `FeeExchange`
+		let gas_fee_exchange = fee_exchange
+			.filter(|_| T::GasMeteredCallResolver::is_gas_metered(call))
+			.map(|exchange| {
+				FeeExchange::new_v1(
+					exchange.asset_id(),
+					exchange
+						.max_payment()
+						.checked_sub(&exchange_asset_spent)
+						.unwrap_or(0.into()),
+				)
+			});
+
+		Ok((fee, payment, gas_fee_exchange))
+	}
 }
 
 impl<T: Trait + Send + Sync> sp_std::fmt::Debug for ChargeTransactionPayment<T> {
@@ -308,7 +570,12 @@ where
 	type Call = T::Call;
 	type AdditionalSigned = ();
 	type DispatchInfo = DispatchInfo;
-	type Pre = ();
+	/// The fee payer, the tip and the fee withdrawn from them, if any, and the `FeeExchange` paying for gas,
+	/// if the call is gas metered
+	type Pre = (
+		Option<(Self::AccountId, BalanceOf<T>, NegativeImbalanceOf<T>)>,
+		Option<FeeExchange<T::AssetId, BalanceOf<T>>>,
+	);
 	fn additional_signed(&self) -> sp_std::result::Result<(), TransactionValidityError> {
 		Ok(())
 	}
@@ -320,71 +587,74 @@ where
 		info: Self::DispatchInfo,
 		len: usize,
 	) -> TransactionValidity {
-		let fee = Self::compute_fee(len as u32, info, self.tip);
+		let (fee, payment, _) = self.withdraw_fee(who, call, info, len)?;
+		if let Some((_, imbalance)) = payment {
+			let imbalances = imbalance.split(self.tip);
+			T::OnTransactionPayment::on_unbalanceds(Some(imbalances.0).into_iter().chain(Some(imbalances.1)));
+		}
 
-		// How much user nominated fee asset has been spent so far
-		// used for accounting the 'max payment' preference
-		let mut exchange_asset_spent: BalanceOf<T> = Zero::zero();
+		// The transaction is valid
+		let mut r = ValidTransaction::default();
+		r.priority = Self::get_priority(len as u32, info, fee);
+		Ok(r)
+	}
 
-		// Only mess with balances if the fee is not zero.
-		if !fee.is_zero() {
-			let payer = T::FeePayer::fee_payer(call).unwrap_or(who.clone());
-			if let Some(exchange) = &self.fee_exchange {
-				// Buy the CENNZnet fee currency paying with the user's nominated fee currency
-				exchange_asset_spent = T::BuyFeeAsset::buy_fee_asset(&payer, fee, &exchange).map_err(|e| {
-					let code = match e {
-						DispatchError::Module { message, .. } => error_code::buy_fee_asset_error_msg_to_code(
-							message.unwrap_or("Unknown buy fee asset error"),
-						),
-						_ => error_code::UNKNOWN_BUY_FEE_ASSET,
-					};
-					TransactionValidityError::Invalid(InvalidTransaction::Custom(code))
-				})?;
-			}
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: Self::DispatchInfo,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		let (_, payment, gas_fee_exchange) = self.withdraw_fee(who, call, info, len)?;
+		// Make the `FeeExchange` available to the gas handler while the call is dispatched,
+		// replacing the context of any earlier call which was not taken after its dispatch
+		CurrentDispatch::<T>::put(DispatchContext {
+			unused_weight: 0,
+			gas_fee_exchange: gas_fee_exchange.clone(),
+		});
+		Ok((
+			payment.map(|(payer, imbalance)| (payer, self.tip, imbalance)),
+			gas_fee_exchange,
+		))
+	}
 
-			let withdraw_reason = if self.tip.is_zero() {
-				WithdrawReason::TransactionPayment.into()
+	fn post_dispatch(pre: Self::Pre, info: Self::DispatchInfo, len: usize) {
+		let (payment, _gas_fee_exchange) = pre;
+		let unused_weight = CurrentDispatch::<T>::take()
+			.map_or(0, |context| context.unused_weight)
+			.min(info.weight);
+		if let Some((payer, tip, imbalance)) = payment {
+			// Refund the weight fee of any weight the call didn't use in the native fee currency,
+			// including to users who bought it with a `FeeExchange`
+			let mut refund = Zero::zero();
+			if !unused_weight.is_zero() {
+				let used_info = DispatchInfo {
+					weight: info.weight - unused_weight,
+					..info
+				};
+				refund = ChargeTransactionPayment::<T>::compute_fee(len as u32, info, tip)
+					.saturating_sub(ChargeTransactionPayment::<T>::compute_fee(len as u32, used_info, tip));
+			}
+			let imbalance = if refund.is_zero() {
+				imbalance
 			} else {
-				WithdrawReason::TransactionPayment | WithdrawReason::Tip
-			};
-			// Pay for the transaction `fee` in the native fee currency
-			let imbalance = match T::Currency::withdraw(&payer, fee, withdraw_reason, ExistenceRequirement::KeepAlive) {
-				Ok(imbalance) => imbalance,
-				Err(_) => return Err(InvalidTransaction::Custom(error_code::INSUFFICIENT_FEE_ASSET_BALANCE).into()),
+				match T::Currency::deposit_into_existing(&payer, refund) {
+					Ok(refund_imbalance) => imbalance
+						.offset(refund_imbalance)
+						.unwrap_or_else(|_| NegativeImbalanceOf::<T>::zero()),
+					Err(_) => {
+						refund = Zero::zero();
+						imbalance
+					}
+				}
 			};
 
-			let imbalances = imbalance.split(self.tip);
+			let fee = imbalance.peek();
+			let imbalances = imbalance.split(tip);
 			T::OnTransactionPayment::on_unbalanceds(Some(imbalances.0).into_iter().chain(Some(imbalances.1)));
+			<Module<T>>::deposit_event(RawEvent::TransactionFeeSettled(payer, fee, refund));
 		}
-
-		// Certain contract module calls require gas metering and special handling for
-		// multi-currency gas payment
-		if T::GasMeteredCallResolver::is_gas_metered(call) {
-			// Temporarily store the `FeeExchange` info so that it can be used later by the-
-			// custom `GasHandler` impl see `runtime/src/impls.rs`. This is a hack!
-			if let Some(exchange) = &self.fee_exchange {
-				storage::unhashed::put(
-					&GAS_FEE_EXCHANGE_KEY,
-					&FeeExchange::new_v1(
-						exchange.asset_id(),
-						exchange
-							.max_payment()
-							.checked_sub(&exchange_asset_spent)
-							.unwrap_or(0.into()),
-					),
-				);
-			} else {
-				// Pre-caution to ensure `FeeExchange` data is cleared
-				storage::unhashed::kill(&GAS_FEE_EXCHANGE_KEY)
-			};
-		}
-
-		// The transaction is valid
-		let mut r = ValidTransaction::default();
-		// NOTE: we probably want to maximize the _fee (of any type) per weight unit_ here, which
-		// will be a bit more than setting the priority to tip. For now, this is enough.
-		r.priority = fee.saturated_into::<TransactionPriority>();
-		Ok(r)
 	}
 }
 
@@ -394,6 +664,7 @@ mod tests {
 	use codec::Encode;
 	use frame_support::{
 		impl_outer_dispatch, impl_outer_origin, parameter_types,
+		traits::OnFinalize,
 		weights::{DispatchClass, DispatchInfo, GetDispatchInfo, Weight},
 	};
 	use pallet_balances::Call as BalancesCall;
@@ -409,7 +680,7 @@ mod tests {
 	use crate::{Module, Trait};
 	use cennznet_primitives::{
 		traits::{BuyFeeAsset, IsGasMeteredCall},
-		types::FeeExchange,
+		types::{FeeExchange, FeePaymentError, MAX_FEE_EXCHANGE_OPTIONS},
 	};
 	use frame_support::{
 		additional_traits::DummyDispatchVerifier,
@@ -468,7 +739,8 @@ mod tests {
 	impl FeePayer for MockCallResolver {
 		type Call = Call;
 		type AccountId = u64;
-		fn fee_payer(_call: &Self::Call) -> Option<Self::AccountId> {
+		type Balance = u64;
+		fn fee_payer(_who: &Self::AccountId, _call: &Self::Call, _fee: Self::Balance) -> Option<Self::AccountId> {
 			None
 		}
 	}
@@ -502,6 +774,34 @@ mod tests {
 			}
 			Ok(amount)
 		}
+		fn buy_fee_asset_price(
+			amount: Self::Balance,
+			exchange_op: &Self::FeeExchange,
+		) -> sp_std::result::Result<Self::Balance, DispatchError> {
+			if exchange_op.asset_id() != VALID_ASSET_TO_BUY_FEE {
+				return Err(DispatchError::Module {
+					index: 1,
+					error: 33,
+					message: Some("InvalidAssetId"),
+				});
+			}
+			if exchange_op.max_payment() < amount {
+				return Err(DispatchError::Module {
+					index: 1,
+					error: 15,
+					message: Some("MaximumSellRequirementNotMet"),
+				});
+			}
+			// price fee asset at a 1:1 ratio
+			Ok(amount)
+		}
+		fn fee_payment_error(error: DispatchError) -> FeePaymentError {
+			match error {
+				DispatchError::Module { error: 15, .. } => FeePaymentError::MaximumSellRequirementNotMet,
+				DispatchError::Module { error: 33, .. } => FeePaymentError::InvalidAssetId,
+				_ => FeePaymentError::Unknown,
+			}
+		}
 	}
 
 	impl frame_system::Trait for Runtime {
@@ -540,6 +840,10 @@ mod tests {
 		type ExistentialDeposit = ExistentialDeposit;
 		type CreationFee = CreationFee;
 	}
+	parameter_types! {
+		pub const OperationalFeeMultiplier: u8 = 5;
+	}
+
 	thread_local! {
 		static TRANSACTION_BASE_FEE: RefCell<u64> = RefCell::new(0);
 		static TRANSACTION_BYTE_FEE: RefCell<u64> = RefCell::new(1);
@@ -576,9 +880,11 @@ mod tests {
 		type TransactionByteFee = TransactionByteFee;
 		type WeightToFee = WeightToFee;
 		type FeeMultiplierUpdate = ();
+		type OperationalFeeMultiplier = OperationalFeeMultiplier;
 		type BuyFeeAsset = Module<Self>;
 		type GasMeteredCallResolver = MockCallResolver;
 		type FeePayer = MockCallResolver;
+		type Event = ();
 	}
 
 	type Balances = pallet_balances::Module<Runtime>;
@@ -683,6 +989,92 @@ mod tests {
 			});
 	}
 
+	#[test]
+	fn signed_extension_refunds_fee_for_unused_weight() {
+		ExtBuilder::default()
+			.balance_factor(10) // 100
+			.base_fee(5) // 5 fixed, 1 per byte, 1 per weight
+			.build()
+			.execute_with(|| {
+				let len = 10;
+				let info = info_from_weight(30);
+				let pre = ChargeTransactionPayment::<Runtime>::from(0, None)
+					.pre_dispatch(&1, CALL, info, len)
+					.unwrap();
+				assert_eq!(Balances::free_balance(&1), 100 - 5 - 10 - 30);
+
+				TransactionPayment::note_unused_weight(20);
+				ChargeTransactionPayment::<Runtime>::post_dispatch(pre, info, len);
+				assert_eq!(Balances::free_balance(&1), 100 - 5 - 10 - 10);
+				assert_eq!(TransactionPayment::unused_weight(), 0);
+			});
+	}
+
+	#[test]
+	fn signed_extension_refund_is_capped_at_declared_weight() {
+		ExtBuilder::default()
+			.balance_factor(10) // 100
+			.base_fee(5) // 5 fixed, 1 per byte, 1 per weight
+			.build()
+			.execute_with(|| {
+				let len = 10;
+				let info = info_from_weight(30);
+				let pre = ChargeTransactionPayment::<Runtime>::from(5, /* tipped */ None)
+					.pre_dispatch(&1, CALL, info, len)
+					.unwrap();
+				assert_eq!(Balances::free_balance(&1), 100 - 5 - 10 - 30 - 5);
+
+				// more unused weight than was declared only refunds the weight portion of the fee
+				TransactionPayment::note_unused_weight(50);
+				ChargeTransactionPayment::<Runtime>::post_dispatch(pre, info, len);
+				assert_eq!(Balances::free_balance(&1), 100 - 5 - 10 - 5);
+				assert_eq!(TransactionPayment::unused_weight(), 0);
+			});
+	}
+
+	#[test]
+	fn signed_extension_does_not_refund_weight_noted_outside_its_dispatch() {
+		ExtBuilder::default()
+			.balance_factor(10) // 100
+			.base_fee(5) // 5 fixed, 1 per byte, 1 per weight
+			.build()
+			.execute_with(|| {
+				let len = 10;
+				let info = info_from_weight(30);
+
+				// e.g. noted in `on_initialize` or by an unsigned extrinsic
+				TransactionPayment::note_unused_weight(20);
+				let pre = ChargeTransactionPayment::<Runtime>::from(0, None)
+					.pre_dispatch(&1, CALL, info, len)
+					.unwrap();
+				assert_eq!(TransactionPayment::unused_weight(), 0);
+				ChargeTransactionPayment::<Runtime>::post_dispatch(pre, info, len);
+				assert_eq!(Balances::free_balance(&1), 100 - 5 - 10 - 30);
+
+				// and none is left for the next block
+				TransactionPayment::note_unused_weight(20);
+				<TransactionPayment as OnFinalize<u64>>::on_finalize(1);
+				assert_eq!(TransactionPayment::unused_weight(), 0);
+			});
+	}
+
+	#[test]
+	fn signed_extension_does_not_refund_without_unused_weight() {
+		ExtBuilder::default()
+			.balance_factor(10) // 100
+			.base_fee(5) // 5 fixed, 1 per byte, 1 per weight
+			.build()
+			.execute_with(|| {
+				let len = 10;
+				let info = info_from_weight(30);
+				let pre = ChargeTransactionPayment::<Runtime>::from(0, None)
+					.pre_dispatch(&1, CALL, info, len)
+					.unwrap();
+				ChargeTransactionPayment::<Runtime>::post_dispatch(pre, info, len);
+				assert_eq!(Balances::free_balance(&1), 100 - 5 - 10 - 30);
+			});
+	}
+
 	#[test]
 	fn signed_extension_transaction_payment_is_bounded() {
 		ExtBuilder::default()
@@ -736,6 +1128,42 @@ mod tests {
 			});
 	}
 
+	#[test]
+	fn signed_extension_priority_is_fee_per_block_share() {
+		ExtBuilder::default().balance_factor(100).build().execute_with(|| {
+			let len = 10;
+			let priority = |tip, info| {
+				ChargeTransactionPayment::<Runtime>::from(tip, None)
+					.validate(&1, CALL, info, len)
+					.unwrap()
+					.priority
+			};
+
+			// fee = len + weight, scaled by min(1024 / weight, 2048 / len) transactions per block
+			let large = info_from_weight(100);
+			let small = info_from_weight(10);
+			assert_eq!(priority(0, large), (110 + 1) * 10);
+			assert_eq!(priority(0, small), (20 + 1) * 102);
+			assert_eq!(priority(5, small), (25 + 1) * 102);
+
+			// Operational transactions tip 5x their fee virtually
+			let operational = DispatchInfo {
+				weight: 100,
+				class: DispatchClass::Operational,
+				pays_fee: true,
+			};
+			assert_eq!(priority(0, operational), (110 + 1) * 10 + (110 + 1) * 5 * 10);
+
+			// Free transactions are ordered by the share of a block they fill
+			let free = DispatchInfo {
+				weight: 100,
+				class: DispatchClass::Operational,
+				pays_fee: false,
+			};
+			assert_eq!(priority(0, free), 10 + 5 * 10);
+		});
+	}
+
 	#[test]
 	fn signed_ext_length_fee_is_also_updated_per_congestion() {
 		ExtBuilder::default()
@@ -786,6 +1214,93 @@ mod tests {
 			});
 	}
 
+	#[test]
+	fn query_fee_info_works() {
+		let call = Call::Balances(pallet_balances::Call::transfer(2, 69));
+		let info = call.get_dispatch_info();
+		let len = 10;
+		ExtBuilder::default()
+			.base_fee(5)
+			.weight_fee(2)
+			.build()
+			.execute_with(|| {
+				// all fees should be x1.5
+				NextFeeMultiplier::put(Fixed64::from_rational(1, 2));
+				let weight_fee = info.weight.min(MaximumBlockWeight::get()) as u64 * 2;
+				let fee = 5 + (len as u64 + weight_fee) * 3 / 2 + 3;
+
+				assert_eq!(
+					TransactionPayment::query_fee_info(
+						&call,
+						info,
+						Some((
+							1,
+							ChargeTransactionPayment::from(
+								3,
+								Some(FeeExchange::new_v2(vec![
+									(INVALID_ASSET_TO_BUY_FEE, 100_000),
+									(VALID_ASSET_TO_BUY_FEE, 100_000)
+								]))
+							)
+						)),
+						len,
+					),
+					Ok(FeeInfo {
+						weight: info.weight,
+						class: info.class,
+						payer: Some(1),
+						partial_fee: fee,
+						base_fee: 5,
+						length_fee: len as u64,
+						weight_fee,
+						peak_adjustment_fee: (len as u64 + weight_fee) / 2,
+						tip: 3,
+						fee_exchange: Some(FeeExchangeInfo {
+							asset_id: VALID_ASSET_TO_BUY_FEE,
+							amount: fee,
+						}),
+					})
+				);
+			});
+	}
+
+	#[test]
+	fn query_fee_info_for_unsigned_extrinsic() {
+		let call = Call::Balances(pallet_balances::Call::transfer(2, 69));
+		let info = call.get_dispatch_info();
+		let len = 10;
+		ExtBuilder::default().base_fee(5).build().execute_with(|| {
+			let fee_info = TransactionPayment::query_fee_info(&call, info, None, len).unwrap();
+			assert_eq!(fee_info.payer, None);
+			assert_eq!(fee_info.tip, 0);
+			assert_eq!(fee_info.fee_exchange, None);
+			assert_eq!(
+				fee_info.partial_fee,
+				5 + len as u64 + info.weight.min(MaximumBlockWeight::get()) as u64
+			);
+		});
+	}
+
+	#[test]
+	fn query_fee_info_fails_when_fee_exchange_cannot_pay() {
+		let call = Call::Balances(pallet_balances::Call::transfer(2, 69));
+		let info = call.get_dispatch_info();
+		ExtBuilder::default().base_fee(5).build().execute_with(|| {
+			assert_eq!(
+				TransactionPayment::query_fee_info(
+					&call,
+					info,
+					Some((
+						1,
+						ChargeTransactionPayment::from(0, Some(FeeExchange::new_v1(VALID_ASSET_TO_BUY_FEE, 1)))
+					)),
+					10,
+				),
+				Err(InvalidTransaction::Custom(error_code::MAXIMUM_SELL_REQUIREMENT_NOT_MET).into())
+			);
+		});
+	}
+
 	#[test]
 	fn compute_fee_works_without_multiplier() {
 		ExtBuilder::default()
@@ -850,7 +1365,7 @@ mod tests {
 			.execute_with(|| {
 				// Add a next fee multiplier
 				NextFeeMultiplier::put(Fixed64::from_rational(1, 2)); // = 1/2 = .5
-													  // Base fee is unaffected by multiplier
+														  // Base fee is unaffected by multiplier
 				let dispatch_info = DispatchInfo {
 					weight: 0,
 					class: DispatchClass::Operational,
@@ -928,12 +1443,9 @@ mod tests {
 				let len = 10;
 				let fee_exchange = FeeExchange::new_v1(INVALID_ASSET_TO_BUY_FEE, 100_000);
 				assert_eq!(
-					ChargeTransactionPayment::<Runtime>::from(10, Some(fee_exchange)).pre_dispatch(
-						&1,
-						CALL,
-						info_from_weight(3),
-						len
-					),
+					ChargeTransactionPayment::<Runtime>::from(10, Some(fee_exchange))
+						.pre_dispatch(&1, CALL, info_from_weight(3), len)
+						.map(|_| ()),
 					error_from_code(error_code::INVALID_ASSET_ID)
 				);
 			})
@@ -949,19 +1461,93 @@ mod tests {
 				let len = 10;
 				let fee_exchange = FeeExchange::new_v1(VALID_ASSET_TO_BUY_FEE, 0);
 				assert_eq!(
-					ChargeTransactionPayment::<Runtime>::from(10, Some(fee_exchange)).pre_dispatch(
-						&1,
-						CALL,
-						info_from_weight(3),
-						len
-					),
+					ChargeTransactionPayment::<Runtime>::from(10, Some(fee_exchange))
+						.pre_dispatch(&1, CALL, info_from_weight(3), len)
+						.map(|_| ()),
 					error_from_code(error_code::MAXIMUM_SELL_REQUIREMENT_NOT_MET)
 				);
 			})
 	}
 
 	#[test]
-	fn fee_exchange_temporary_storage_for_gas_metered_calls() {
+	fn uses_next_currency_fee_exchange_when_preferred_fails() {
+		ExtBuilder::default()
+			.base_fee(5)
+			.balance_factor(1)
+			.build()
+			.execute_with(|| {
+				let len = 10;
+				let fee_exchange = FeeExchange::new_v2(vec![
+					(INVALID_ASSET_TO_BUY_FEE, 100_000),
+					(VALID_ASSET_TO_BUY_FEE, 100_000),
+				]);
+				assert!(ChargeTransactionPayment::<Runtime>::from(10, Some(fee_exchange))
+					.pre_dispatch(&1, CALL, info_from_weight(3), len)
+					.is_ok());
+			})
+	}
+
+	#[test]
+	fn rejects_fee_exchange_when_no_currency_can_pay() {
+		ExtBuilder::default()
+			.base_fee(5)
+			.balance_factor(1)
+			.build()
+			.execute_with(|| {
+				let len = 10;
+				// The error of the last currency tried is returned
+				let fee_exchange =
+					FeeExchange::new_v2(vec![(VALID_ASSET_TO_BUY_FEE, 0), (INVALID_ASSET_TO_BUY_FEE, 100_000)]);
+				assert_eq!(
+					ChargeTransactionPayment::<Runtime>::from(10, Some(fee_exchange))
+						.pre_dispatch(&1, CALL, info_from_weight(3), len)
+						.map(|_| ()),
+					error_from_code(error_code::INVALID_ASSET_ID)
+				);
+			})
+	}
+
+	#[test]
+	fn rejects_fee_exchange_without_options_or_with_too_many() {
+		ExtBuilder::default()
+			.base_fee(5)
+			.balance_factor(1)
+			.build()
+			.execute_with(|| {
+				let len = 10;
+				let fee_exchange = FeeExchange::new_v2(vec![]);
+				assert_eq!(
+					ChargeTransactionPayment::<Runtime>::from(10, Some(fee_exchange))
+						.validate(&1, CALL, info_from_weight(3), len)
+						.map(|_| ()),
+					error_from_code(error_code::NO_FEE_EXCHANGE_OPTIONS)
+				);
+
+				let fee_exchange =
+					FeeExchange::new_v2(vec![(VALID_ASSET_TO_BUY_FEE, 100_000); MAX_FEE_EXCHANGE_OPTIONS + 1]);
+				assert_eq!(
+					ChargeTransactionPayment::<Runtime>::from(10, Some(fee_exchange.clone()))
+						.validate(&1, CALL, info_from_weight(3), len)
+						.map(|_| ()),
+					error_from_code(error_code::TOO_MANY_FEE_EXCHANGE_OPTIONS)
+				);
+				assert_eq!(
+					ChargeTransactionPayment::<Runtime>::from(10, Some(fee_exchange))
+						.pre_dispatch(&1, CALL, info_from_weight(3), len)
+						.map(|_| ()),
+					error_from_code(error_code::TOO_MANY_FEE_EXCHANGE_OPTIONS)
+				);
+
+				let fee_exchange =
+					FeeExchange::new_v2(vec![(VALID_ASSET_TO_BUY_FEE, 100_000); MAX_FEE_EXCHANGE_OPTIONS]);
+				assert!(ChargeTransactionPayment::<Runtime>::from(10, Some(fee_exchange))
+					.pre_dispatch(&1, CALL, info_from_weight(3), len)
+					.is_ok());
+			})
+	}
+
+	#[test]
+	fn gas_fee_exchange_uses_currency_paid_with() {
 		ExtBuilder::default()
 			.base_fee(5)
 			.balance_factor(1000)
@@ -970,31 +1556,76 @@ mod tests {
 				let len: u64 = 10;
 				let base_fee = ChargeTransactionPayment::<Runtime>::compute_fee(0, info_from_weight(3), len);
 
-				// `GAS_FEE_EXCHANGE_KEY` temporary storage should be set
+				let fee_exchange =
+					FeeExchange::new_v2(vec![(INVALID_ASSET_TO_BUY_FEE, 222), (VALID_ASSET_TO_BUY_FEE, 111)]);
+				assert!(ChargeTransactionPayment::<Runtime>::from(0, Some(fee_exchange))
+					.pre_dispatch(&1, METERED_CALL, info_from_weight(3), len as usize)
+					.is_ok());
+				assert_eq!(
+					TransactionPayment::gas_fee_exchange(),
+					Some(FeeExchange::new_v1(VALID_ASSET_TO_BUY_FEE, 111 - base_fee))
+				);
+			});
+	}
+
+	#[test]
+	fn gas_fee_exchange_for_gas_metered_calls() {
+		ExtBuilder::default()
+			.base_fee(5)
+			.balance_factor(1000)
+			.build()
+			.execute_with(|| {
+				let len: u64 = 10;
+				let info = info_from_weight(3);
+				let base_fee = ChargeTransactionPayment::<Runtime>::compute_fee(0, info, len);
+
+				// The gas fee exchange is only set for dispatch, not validation
 				let fee_exchange = FeeExchange::new_v1(VALID_ASSET_TO_BUY_FEE, 111);
 				let transaction_payment_with_fee_exchange =
 					ChargeTransactionPayment::<Runtime>::from(0, Some(fee_exchange.clone()));
 				assert!(transaction_payment_with_fee_exchange
-					.pre_dispatch(&1, METERED_CALL, info_from_weight(3), len as usize)
+					.validate(&1, METERED_CALL, info, len as usize)
 					.is_ok());
+				assert_eq!(TransactionPayment::gas_fee_exchange(), None);
+
+				let pre = transaction_payment_with_fee_exchange
+					.pre_dispatch(&1, METERED_CALL, info, len as usize)
+					.unwrap();
 				// fee exchange `max_payment` is decremented by the payment cost
 				assert_eq!(
-					FeeExchange::new_v1(fee_exchange.asset_id(), fee_exchange.max_payment() - base_fee),
-					storage::unhashed::get(&GAS_FEE_EXCHANGE_KEY).expect("it is stored")
+					TransactionPayment::gas_fee_exchange(),
+					Some(FeeExchange::new_v1(
+						fee_exchange.asset_id(),
+						fee_exchange.max_payment() - base_fee
+					))
 				);
+				// and carried to `post_dispatch`
+				assert_eq!(pre.1, TransactionPayment::gas_fee_exchange());
 
-				// `GAS_FEE_EXCHANGE_KEY` temporary storage should not be set without fee_exchange
-				let transaction_payment_without_fee_exchange = ChargeTransactionPayment::<Runtime>::from(0, None);
-				assert!(transaction_payment_without_fee_exchange
-					.pre_dispatch(&1, METERED_CALL, info_from_weight(3), len as usize)
+				// The gas fee exchange is cleared after dispatch
+				ChargeTransactionPayment::<Runtime>::post_dispatch(pre, info, len as usize);
+				assert_eq!(TransactionPayment::gas_fee_exchange(), None);
+
+				// The gas fee exchange is not set without a fee exchange
+				assert!(ChargeTransactionPayment::<Runtime>::from(0, None)
+					.pre_dispatch(&1, METERED_CALL, info, len as usize)
+					.is_ok());
+				assert_eq!(TransactionPayment::gas_fee_exchange(), None);
+
+				// Nor is it left over from a previous dispatch
+				assert!(ChargeTransactionPayment::<Runtime>::from(0, Some(fee_exchange.clone()))
+					.pre_dispatch(&1, METERED_CALL, info, len as usize)
+					.is_ok());
+				assert!(TransactionPayment::gas_fee_exchange().is_some());
+				assert!(ChargeTransactionPayment::<Runtime>::from(0, None)
+					.pre_dispatch(&1, METERED_CALL, info, len as usize)
 					.is_ok());
-				let stored: Option<()> = storage::unhashed::get(&GAS_FEE_EXCHANGE_KEY);
-				assert!(stored.is_none());
+				assert_eq!(TransactionPayment::gas_fee_exchange(), None);
 			});
 	}
 
 	#[test]
-	fn fee_exchange_temporary_storage_unused_for_normal_calls() {
+	fn gas_fee_exchange_unused_for_normal_calls() {
 		ExtBuilder::default()
 			.base_fee(5)
 			.balance_factor(1000)
@@ -1002,21 +1633,16 @@ mod tests {
 			.execute_with(|| {
 				let len: u64 = 10;
 
-				// `GAS_FEE_EXCHANGE_KEY` temporary storage should be unset
 				let fee_exchange = FeeExchange::new_v1(VALID_ASSET_TO_BUY_FEE, 111);
-				let transaction_payment_with_fee_exchange =
-					ChargeTransactionPayment::<Runtime>::from(0, Some(fee_exchange.clone()));
-				assert!(transaction_payment_with_fee_exchange
+				assert!(ChargeTransactionPayment::<Runtime>::from(0, Some(fee_exchange))
 					.pre_dispatch(&1, CALL, info_from_weight(3), len as usize)
 					.is_ok());
-				assert!(storage::unhashed::get::<Option<()>>(&GAS_FEE_EXCHANGE_KEY).is_none());
+				assert_eq!(TransactionPayment::gas_fee_exchange(), None);
 
-				// `GAS_FEE_EXCHANGE_KEY` temporary storage should be unset
-				let transaction_payment_without_fee_exchange = ChargeTransactionPayment::<Runtime>::from(0, None);
-				assert!(transaction_payment_without_fee_exchange
+				assert!(ChargeTransactionPayment::<Runtime>::from(0, None)
 					.pre_dispatch(&1, CALL, info_from_weight(3), len as usize)
 					.is_ok());
-				assert!(storage::unhashed::get::<Option<()>>(&GAS_FEE_EXCHANGE_KEY).is_none());
+				assert_eq!(TransactionPayment::gas_fee_exchange(), None);
 			});
 	}
 
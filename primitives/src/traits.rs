@@ -15,6 +15,7 @@
 
 //! Common traits used by CENNZnet node.
 
+use crate::types::FeePaymentError;
 use frame_support::dispatch::DispatchError;
 
 /// A trait which enables buying some fee asset using another asset.
@@ -36,6 +37,17 @@ pub trait BuyFeeAsset {
 		amount: Self::Balance,
 		fee_exchange: &Self::FeeExchange,
 	) -> Result<Self::Balance, DispatchError>;
+
+	/// Price `amount` of fee asset in the asset from `fee_exchange` without buying it.
+	/// Return Ok with the amount `buy_fee_asset` would sell, or Err if it would fail to buy the fee asset
+	/// for reasons other than the buyer's balance.
+	fn buy_fee_asset_price(
+		amount: Self::Balance,
+		fee_exchange: &Self::FeeExchange,
+	) -> Result<Self::Balance, DispatchError>;
+
+	/// The reason `error`, returned by `buy_fee_asset` or `buy_fee_asset_price`, means a fee cannot be paid.
+	fn fee_payment_error(error: DispatchError) -> FeePaymentError;
 }
 
 /// Something that can resolve if an extrinsic call requires a gas meter or not
@@ -17,6 +17,9 @@
 //! Low-level types used by CENNZnet node.
 
 use codec::{Decode, Encode};
+use frame_support::sp_std::{prelude::*, vec};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
 use sp_runtime::{
 	generic,
 	traits::{BlakeTwo256, IdentifyAccount, Verify},
@@ -76,6 +79,8 @@ pub enum FeeExchange<AssetId, Balance> {
 	/// A V1 FeeExchange
 	#[codec(compact)]
 	V1(FeeExchangeV1<AssetId, Balance>),
+	/// A V2 FeeExchange
+	V2(FeeExchangeV2<AssetId, Balance>),
 }
 
 /// A v1 FeeExchange
@@ -99,7 +104,27 @@ impl<AssetId, Balance> FeeExchangeV1<AssetId, Balance> {
 	}
 }
 
-impl<AssetId: Copy, Balance: Copy> FeeExchange<AssetId, Balance> {
+/// The most fee assets a `FeeExchangeV2` may nominate
+pub const MAX_FEE_EXCHANGE_OPTIONS: usize = 4;
+
+/// A v2 FeeExchange
+/// Signals a fee payment requiring the CENNZX-Spot exchange, nominating several input assets.
+/// Each asset is tried in order, the first able to buy the fee within its max. limit pays for it.
+/// At least one and at most `MAX_FEE_EXCHANGE_OPTIONS` assets must be nominated.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Debug)]
+pub struct FeeExchangeV2<AssetId, Balance> {
+	/// The Asset IDs to exchange for network fee asset and their max. payment limits, in order of preference
+	pub options: Vec<FeeExchangeV1<AssetId, Balance>>,
+}
+
+impl<AssetId, Balance> FeeExchangeV2<AssetId, Balance> {
+	/// Create a new FeeExchangeV2
+	pub fn new(options: Vec<FeeExchangeV1<AssetId, Balance>>) -> Self {
+		Self { options }
+	}
+}
+
+impl<AssetId: Copy + Default, Balance: Copy + Default> FeeExchange<AssetId, Balance> {
 	/// Create a `FeeExchangeV1`
 	pub fn new_v1(id: AssetId, balance: Balance) -> Self {
 		FeeExchange::V1(FeeExchangeV1 {
@@ -108,17 +133,157 @@ impl<AssetId: Copy, Balance: Copy> FeeExchange<AssetId, Balance> {
 		})
 	}
 
-	/// Return the nominated fee asset id
+	/// Create a `FeeExchangeV2` from `(asset_id, max_payment)` pairs in order of preference
+	pub fn new_v2(options: Vec<(AssetId, Balance)>) -> Self {
+		FeeExchange::V2(FeeExchangeV2 {
+			options: options
+				.into_iter()
+				.map(|(asset_id, max_payment)| FeeExchangeV1::new(asset_id, max_payment))
+				.collect(),
+		})
+	}
+
+	/// Check a `FeeExchangeV2` nominates at least one and at most `MAX_FEE_EXCHANGE_OPTIONS` fee assets
+	pub fn check_options(&self) -> Result<(), FeePaymentError> {
+		match self {
+			FeeExchange::V1(_) => Ok(()),
+			FeeExchange::V2(x) if x.options.is_empty() => Err(FeePaymentError::NoFeeExchangeOptions),
+			FeeExchange::V2(x) if x.options.len() > MAX_FEE_EXCHANGE_OPTIONS => {
+				Err(FeePaymentError::TooManyFeeExchangeOptions)
+			}
+			FeeExchange::V2(_) => Ok(()),
+		}
+	}
+
+	/// Return the nominated fee asset id, the most preferred one for a `FeeExchangeV2`
+	/// An empty `FeeExchangeV2` fails `check_options` and is never used to pay fees
 	pub fn asset_id(&self) -> AssetId {
 		match self {
 			FeeExchange::V1(x) => x.asset_id,
+			FeeExchange::V2(x) => x.options.first().map(|o| o.asset_id).unwrap_or_default(),
 		}
 	}
 
-	/// Return the max. payment limit
+	/// Return the max. payment limit, that of the most preferred asset for a `FeeExchangeV2`
+	/// An empty `FeeExchangeV2` fails `check_options` and is never used to pay fees
 	pub fn max_payment(&self) -> Balance {
 		match self {
 			FeeExchange::V1(x) => x.max_payment,
+			FeeExchange::V2(x) => x.options.first().map(|o| o.max_payment).unwrap_or_default(),
+		}
+	}
+
+	/// Return each nominated fee asset as a `FeeExchangeV1`, in order of preference
+	pub fn options(&self) -> Vec<Self> {
+		match self {
+			FeeExchange::V1(_) => vec![self.clone()],
+			FeeExchange::V2(x) => x
+				.options
+				.iter()
+				.map(|o| Self::new_v1(o.asset_id, o.max_payment))
+				.collect(),
+		}
+	}
+}
+
+/// The reason a transaction fee could not be paid.
+///
+/// Transactions are rejected with `InvalidTransaction::Custom(code)`, where each reason has a stable code:
+///
+/// | code | reason                          |
+/// |------|---------------------------------|
+/// | 195  | InsufficientBalance             |
+/// | 197  | MinimumBuyRequirementNotMet     |
+/// | 199  | CannotTradeZero                 |
+/// | 200  | InsufficientFeeAssetBalance     |
+/// | 201  | InvalidAssetId                  |
+/// | 202  | Unknown                         |
+/// | 203  | InsufficientLiquidity           |
+/// | 204  | AssetCannotSwapForItself        |
+/// | 205  | InsufficientExchangePoolReserve |
+/// | 206  | MaximumSellRequirementNotMet    |
+/// | 207  | NoFeeExchangeOptions            |
+/// | 208  | TooManyFeeExchangeOptions       |
+/// | 209  | EmptyExchangePool               |
+/// | 210  | ExchangePaused                  |
+/// | 211  | PriceMovementLimitExceeded      |
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum FeePaymentError {
+	/// The payer's balance of the asset nominated to buy the fee is too low
+	InsufficientBalance,
+	/// The fee asset bought would be less than the fee
+	MinimumBuyRequirementNotMet,
+	/// The fee asset cannot be bought for a zero amount
+	CannotTradeZero,
+	/// The payer's balance of the fee asset is too low to pay the fee
+	InsufficientFeeAssetBalance,
+	/// The asset nominated to buy the fee does not exist
+	InvalidAssetId,
+	/// The fee asset could not be bought for another reason
+	Unknown,
+	/// The exchange has too little liquidity to buy the fee asset
+	InsufficientLiquidity,
+	/// The asset nominated to buy the fee is the fee asset
+	AssetCannotSwapForItself,
+	/// The exchange pool reserve is too low to buy the fee asset
+	InsufficientExchangePoolReserve,
+	/// Buying the fee asset costs more than the nominated maximum payment
+	MaximumSellRequirementNotMet,
+	/// The `FeeExchangeV2` nominates no fee assets
+	NoFeeExchangeOptions,
+	/// The `FeeExchangeV2` nominates more than `MAX_FEE_EXCHANGE_OPTIONS` fee assets
+	TooManyFeeExchangeOptions,
+	/// The exchange pool of the asset nominated to buy the fee has no liquidity
+	EmptyExchangePool,
+	/// The exchange of the asset nominated to buy the fee is paused
+	ExchangePaused,
+	/// Buying the fee asset would move the exchange price beyond its limit
+	PriceMovementLimitExceeded,
+}
+
+impl FeePaymentError {
+	/// The code of this reason, as reported by `InvalidTransaction::Custom`
+	pub fn code(self) -> u8 {
+		match self {
+			FeePaymentError::InsufficientBalance => 195,
+			FeePaymentError::MinimumBuyRequirementNotMet => 197,
+			FeePaymentError::CannotTradeZero => 199,
+			FeePaymentError::InsufficientFeeAssetBalance => 200,
+			FeePaymentError::InvalidAssetId => 201,
+			FeePaymentError::Unknown => 202,
+			FeePaymentError::InsufficientLiquidity => 203,
+			FeePaymentError::AssetCannotSwapForItself => 204,
+			FeePaymentError::InsufficientExchangePoolReserve => 205,
+			FeePaymentError::MaximumSellRequirementNotMet => 206,
+			FeePaymentError::NoFeeExchangeOptions => 207,
+			FeePaymentError::TooManyFeeExchangeOptions => 208,
+			FeePaymentError::EmptyExchangePool => 209,
+			FeePaymentError::ExchangePaused => 210,
+			FeePaymentError::PriceMovementLimitExceeded => 211,
+		}
+	}
+
+	/// The reason with `code`, if it is a fee payment error code
+	pub fn from_code(code: u8) -> Option<Self> {
+		match code {
+			195 => Some(FeePaymentError::InsufficientBalance),
+			197 => Some(FeePaymentError::MinimumBuyRequirementNotMet),
+			199 => Some(FeePaymentError::CannotTradeZero),
+			200 => Some(FeePaymentError::InsufficientFeeAssetBalance),
+			201 => Some(FeePaymentError::InvalidAssetId),
+			202 => Some(FeePaymentError::Unknown),
+			203 => Some(FeePaymentError::InsufficientLiquidity),
+			204 => Some(FeePaymentError::AssetCannotSwapForItself),
+			205 => Some(FeePaymentError::InsufficientExchangePoolReserve),
+			206 => Some(FeePaymentError::MaximumSellRequirementNotMet),
+			207 => Some(FeePaymentError::NoFeeExchangeOptions),
+			208 => Some(FeePaymentError::TooManyFeeExchangeOptions),
+			209 => Some(FeePaymentError::EmptyExchangePool),
+			210 => Some(FeePaymentError::ExchangePaused),
+			211 => Some(FeePaymentError::PriceMovementLimitExceeded),
+			_ => None,
 		}
 	}
 }
@@ -85,7 +85,8 @@ where
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
-	C::Api: crml_cennzx_spot_rpc::CennzxSpotRuntimeApi<Block, AssetId, Balance, AccountId>,
+	C::Api: crml_cennzx_spot_rpc::CennzxSpotRuntimeApi<Block, AssetId, Balance, AccountId, BlockNumber>,
+	C::Api: crml_transaction_payment_rpc::FeeInfoRuntimeApi<Block, AccountId, AssetId, Balance>,
 	C::Api: pallet_generic_asset_rpc::AssetMetaApi<Block, AssetId>,
 	C::Api: BabeApi<Block>,
 	<C::Api as sp_api::ApiErrorExt>::Error: fmt::Debug,
@@ -94,6 +95,7 @@ where
 	SC: SelectChain<Block> + 'static,
 {
 	use crml_cennzx_spot_rpc::{CennzxSpot, CennzxSpotApi};
+	use crml_transaction_payment_rpc::{FeeInfoApi, TransactionPayment as CennznetTransactionPayment};
 	use pallet_contracts_rpc::{Contracts, ContractsApi};
 	use pallet_generic_asset_rpc::{GenericAsset, GenericAssetApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
@@ -128,6 +130,7 @@ where
 		select_chain,
 	)));
 	io.extend_with(CennzxSpotApi::to_delegate(CennzxSpot::new(client.clone())));
+	io.extend_with(FeeInfoApi::to_delegate(CennznetTransactionPayment::new(client.clone())));
 	io.extend_with(GenericAssetApi::to_delegate(GenericAsset::new(client)));
 
 	io
@@ -108,3 +108,13 @@ pub mod fee {
 	/// The minimum weight of a transaction in practice.
 	pub const MIN_WEIGHT: u128 = 10_000;
 }
+
+/// Indices of modules in the runtime `Call`, the first byte of an encoded call.
+/// Modules without calls e.g. `TransactionPayment` take no index, so these can differ from `ModuleToIndex`.
+pub mod call_index {
+	pub const SYLO_GROUPS: u8 = 22;
+	pub const SYLO_E2EE: u8 = 23;
+	pub const SYLO_INBOX: u8 = 25;
+	pub const SYLO_RESPONSE: u8 = 26;
+	pub const SYLO_VAULT: u8 = 27;
+}
@@ -17,18 +17,21 @@
 //! Some configurable implementations as associated type for the substrate runtime.
 
 use crate::{
-	constants::fee::{MAX_WEIGHT, MIN_WEIGHT},
-	sylo_payment, Call, MaximumBlockWeight, NegativeImbalance, Runtime, System,
+	constants::{
+		call_index,
+		fee::{MAX_WEIGHT, MIN_WEIGHT},
+	},
+	sylo_payment, Call, FeeSponsorship, MaximumBlockWeight, NegativeImbalance, Runtime, System,
 };
 use cennznet_primitives::{
 	traits::{BuyFeeAsset, IsGasMeteredCall},
-	types::{Balance, FeeExchange},
+	types::{AccountId, Balance, BlockNumber, FeeExchange},
 };
 use cennznut::{CENNZnut, RuntimeDomain, ValidationErr};
-use codec::Decode;
-use crml_transaction_payment::GAS_FEE_EXCHANGE_KEY;
+use codec::{Decode, Encode};
+use crml_fee_sponsorship::{CallFilter, Sponsorship, SponsorshipId};
 use frame_support::{
-	additional_traits, storage,
+	additional_traits,
 	traits::{Currency, ExistenceRequirement, Get, Imbalance, OnUnbalanced, WithdrawReason},
 	weights::Weight,
 };
@@ -61,6 +64,15 @@ impl OnUnbalanced<NegativeImbalance> for SplitToAllValidators {
 	}
 }
 
+/// The account of the treasury module, which receives the protocol share of CENNZX-Spot trading fees.
+pub struct TreasuryAccount;
+
+impl Get<AccountId> for TreasuryAccount {
+	fn get() -> AccountId {
+		pallet_treasury::Module::<Runtime>::account_id()
+	}
+}
+
 /// Struct that handles the conversion of Balance -> `u64`. This is used for staking's election
 /// calculation.
 pub struct CurrencyToVoteHandler;
@@ -168,6 +180,16 @@ impl<T: Get<Perbill>> Convert<Fixed64, Fixed64> for TargetedFeeAdjustment<T> {
 /// Handles gas payment post contract execution (before deferring runtime calls) via CENNZX-Spot exchange.
 pub struct GasHandler;
 
+/// The fee exchange nominated to pay for gas by the extrinsic being dispatched, if any
+fn gas_fee_exchange<T: pallet_generic_asset::Trait>() -> Option<FeeExchange<T::AssetId, T::Balance>> {
+	crml_transaction_payment::Module::<Runtime>::gas_fee_exchange().map(|exchange| {
+		FeeExchange::new_v1(
+			T::AssetId::from(exchange.asset_id()),
+			T::Balance::unique_saturated_from(exchange.max_payment()),
+		)
+	})
+}
+
 impl<T> pallet_contracts::GasHandler<T> for GasHandler
 where
 	T: pallet_contracts::Trait + pallet_generic_asset::Trait + crml_cennzx_spot::Trait,
@@ -196,7 +218,7 @@ where
 		};
 
 		// Check if a fee exchange has been specified by the user
-		let fee_exchange: Option<FeeExchange<T::AssetId, T::Balance>> = storage::unhashed::get(&GAS_FEE_EXCHANGE_KEY);
+		let fee_exchange = gas_fee_exchange::<T>();
 
 		if fee_exchange.is_none() {
 			// User will pay for gas in CENNZnet's native fee currency
@@ -252,15 +274,15 @@ where
 	/// The process is as follows:
 	/// - Default case: refund unused gas tokens to the user (`transactor`) in CENNZnet's native fee currency as the current gas price
 	/// - FeeExchange case: Gas spent will be charged to the user in their nominated fee currency at the current gas price
+	/// Unused gas is refunded here, not as unused weight: the call's weight is fixed and doesn't scale with its gas limit.
 	fn empty_unused_gas(transactor: &T::AccountId, gas_meter: GasMeter<T>) {
 		// TODO: Update `GasSpent` for the block
 		let gas_left = gas_meter.gas_left();
 		let gas_price = Contracts::<T>::gas_price();
 		let gas_spent = gas_meter.spent();
 
-		// The `take()` function ensures the entry is killed after access
-		if let Some(exchange_op) = storage::unhashed::take::<FeeExchange<T::AssetId, T::Balance>>(&GAS_FEE_EXCHANGE_KEY)
-		{
+		// The gas fee exchange is cleared by transaction payment after dispatch
+		if let Some(exchange_op) = gas_fee_exchange::<T>() {
 			// Pay for `gas_spent` in a user nominated currency using the CENNZX spot exchange
 			// Payment can never fail as liquidity is verified before filling the meter
 			if let Some(used_gas_cost) = gas_price.checked_mul(&gas_spent.saturated_into()) {
@@ -304,26 +326,90 @@ impl IsGasMeteredCall for GasMeteredCallResolver {
 	}
 }
 
-/// The type that implements FeePayer for the cennznet-runtime Call(s)
+/// The type that implements FeePayer for the cennznet-runtime Call(s), backed by the fee sponsorship registry
 pub struct FeePayerResolver;
 impl crml_transaction_payment::FeePayer for FeePayerResolver {
 	type Call = Call;
-	type AccountId = <Runtime as frame_system::Trait>::AccountId;
-	fn fee_payer(call: &Self::Call) -> Option<<Runtime as frame_system::Trait>::AccountId> {
-		let is_sylo = match call {
-			Call::SyloGroups(_) => true,
-			Call::SyloE2EE(_) => true,
-			Call::SyloDevice(_) => true,
-			Call::SyloInbox(_) => true,
-			Call::SyloResponse(_) => true,
-			Call::SyloVault(_) => true,
-			_ => false,
-		};
-		if is_sylo {
-			sylo_payment::Module::<Runtime>::payment_account()
-		} else {
-			None
+	type AccountId = AccountId;
+	type Balance = Balance;
+	fn fee_payer(who: &AccountId, call: &Self::Call, fee: Balance) -> Option<AccountId> {
+		FeeSponsorship::sponsor_for(who, &call.encode(), fee).map(|(_, sponsor)| sponsor)
+	}
+	fn note_fee_paid(who: &AccountId, call: &Self::Call, payer: &AccountId, fee: Balance) {
+		FeeSponsorship::note_fee_paid(who, &call.encode(), payer, fee)
+	}
+}
+
+/// Keeps a sponsorship of all Sylo calls by the Sylo payment account in the fee sponsorship registry
+pub struct SyloFeeSponsorship;
+impl SyloFeeSponsorship {
+	/// Filters matching all calls of the Sylo modules
+	fn calls() -> Vec<CallFilter> {
+		[
+			call_index::SYLO_GROUPS,
+			call_index::SYLO_E2EE,
+			call_index::SYLO_INBOX,
+			call_index::SYLO_RESPONSE,
+			call_index::SYLO_VAULT,
+		]
+		.iter()
+		.map(|&module| CallFilter { module, method: None })
+		.collect()
+	}
+
+	/// Whether `sponsorship` is the sponsorship of Sylo calls, whatever the order of its filters
+	fn is_sylo_sponsorship(sponsorship: &Sponsorship<AccountId, Balance, BlockNumber>) -> bool {
+		let calls = Self::calls();
+		sponsorship.calls.len() == calls.len()
+			&& calls.iter().all(|filter| sponsorship.calls.contains(filter))
+			&& sponsorship.beneficiaries.is_empty()
+			&& sponsorship.spend_limit.is_none()
+	}
+
+	/// The ids of the sponsorships of Sylo calls by `sponsor`, and the storage accesses made to find them
+	fn sponsorships_of(sponsor: &AccountId) -> (Vec<SponsorshipId>, Weight) {
+		let ids = FeeSponsorship::sponsorships_of(sponsor);
+		let accesses = 1 + ids.len() as Weight;
+		let ids = ids
+			.into_iter()
+			.filter(|id| FeeSponsorship::sponsorship(id).map_or(false, |s| Self::is_sylo_sponsorship(&s)))
+			.collect();
+		(ids, accesses)
+	}
+}
+
+/// The storage accesses made by revoking or creating a sponsorship open to anyone without a deposit
+const SPONSORSHIP_CHANGE_ACCESSES: Weight = 10;
+
+impl sylo_payment::OnPaymentAccountChange<AccountId> for SyloFeeSponsorship {
+	fn on_payment_account_change(previous: Option<AccountId>, current: Option<AccountId>) -> Weight {
+		let mut accesses: Weight = 0;
+		if let Some(previous) = previous {
+			let (ids, reads) = Self::sponsorships_of(&previous);
+			accesses = accesses
+				.saturating_add(reads)
+				.saturating_add(SPONSORSHIP_CHANGE_ACCESSES.saturating_mul(ids.len() as Weight));
+			ids.into_iter().for_each(FeeSponsorship::revoke_sponsorship);
+		}
+		if let Some(sponsor) = current {
+			let (ids, reads) = Self::sponsorships_of(&sponsor);
+			accesses = accesses.saturating_add(reads);
+			if ids.is_empty() {
+				accesses = accesses.saturating_add(SPONSORSHIP_CHANGE_ACCESSES);
+				// Sylo calls go unsponsored if the sponsorships open to anyone are full,
+				// until root revokes some of them
+				let _ = FeeSponsorship::create_sponsorship(
+					Sponsorship {
+						sponsor,
+						calls: Self::calls(),
+						beneficiaries: Vec::new(),
+						spend_limit: None,
+					},
+					Zero::zero(),
+				);
+			}
 		}
+		accesses.saturating_mul(sylo_payment::STORAGE_ACCESS_WEIGHT)
 	}
 }
 
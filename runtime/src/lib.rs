@@ -21,14 +21,17 @@
 #![recursion_limit = "256"]
 #![allow(array_into_iter)]
 
-use cennznet_primitives::types::{AccountId, AssetId, Balance, BlockNumber, Hash, Index, Moment, Signature};
+use cennznet_primitives::types::{
+	AccountId, AssetId, Balance, BlockNumber, FeePaymentError, Hash, Index, Moment, Signature,
+};
 pub use crml_cennzx_spot::{ExchangeAddressGenerator, FeeRate, PerMillion, PerThousand};
-use crml_cennzx_spot_rpc_runtime_api::CennzxSpotResult;
+use crml_cennzx_spot_rpc_runtime_api::{CennzxSpotResult, ExchangeInfo, FeeEarnings, TradeQuote};
+use crml_transaction_payment_rpc_runtime_api::FeeInfo;
 use frame_support::{
 	additional_traits::MultiCurrencyAccounting,
 	construct_runtime, debug, parameter_types,
 	traits::{Randomness, SplitTwoWays},
-	weights::Weight,
+	weights::{GetDispatchInfo, Weight},
 };
 use frame_system::offchain::TransactionSubmitter;
 pub use pallet_contracts::Gas;
@@ -47,7 +50,7 @@ use sp_core::OpaqueMetadata;
 use sp_inherents::{CheckInherentsResult, InherentData};
 use sp_runtime::curve::PiecewiseLinear;
 use sp_runtime::traits::{self, BlakeTwo256, Block as BlockT, IdentityLookup, OpaqueKeys, SaturatedConversion};
-use sp_runtime::transaction_validity::{TransactionSource, TransactionValidity};
+use sp_runtime::transaction_validity::{TransactionSource, TransactionValidity, TransactionValidityError};
 use sp_runtime::{create_runtime_str, generic, impl_opaque_keys, ApplyExtrinsicResult, Perbill, Percent, Permill};
 use sp_std::prelude::*;
 #[cfg(any(feature = "std", test))]
@@ -72,7 +75,7 @@ pub use crml_sylo::vault as sylo_vault;
 pub mod impls;
 use impls::{
 	CENNZnetDispatchVerifier, CurrencyToVoteHandler, FeePayerResolver, GasHandler, GasMeteredCallResolver,
-	ScaledWeightToFee, SplitToAllValidators, TargetedFeeAdjustment,
+	ScaledWeightToFee, SplitToAllValidators, SyloFeeSponsorship, TargetedFeeAdjustment, TreasuryAccount,
 };
 
 /// Constant values used within the runtime.
@@ -92,8 +95,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set `impl_version` to equal spec_version. If only runtime
 	// implementation changes and behavior does not, then leave `spec_version` as
 	// is and increment `impl_version`.
-	spec_version: 36,
-	impl_version: 36,
+	spec_version: 37,
+	impl_version: 37,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -162,12 +165,30 @@ impl pallet_utility::Trait for Runtime {
 	type MaxSignatories = MaxSignatories;
 }
 
+parameter_types! {
+	pub const MaxPriceObservations: u32 = HOURS;
+	pub const MaxOpenOrders: u32 = 1_000;
+	pub const MaxOpenOrdersPerAccount: u32 = 10;
+	pub const MaxOrdersPerBlock: u32 = 50;
+	pub const MaxBatchTrades: u32 = 20;
+	pub const MinimumLiquidity: Balance = 10 * MICROS;
+}
+
 impl crml_cennzx_spot::Trait for Runtime {
 	type Call = Call;
 	type Event = Event;
 	type ExchangeAddressGenerator = ExchangeAddressGenerator<Self>;
 	type BalanceToUnsignedInt = Balance;
 	type UnsignedIntToBalance = Balance;
+	type MaxPriceObservations = MaxPriceObservations;
+	type MaxOpenOrders = MaxOpenOrders;
+	type MaxOpenOrdersPerAccount = MaxOpenOrdersPerAccount;
+	type MaxOrdersPerBlock = MaxOrdersPerBlock;
+	type MaxBatchTrades = MaxBatchTrades;
+	type ProtocolFeeRecipient = TreasuryAccount;
+	type PauseOrigin =
+		pallet_collective::EnsureProportionMoreThan<_1, _2, AccountId, Self::Doughnut, CouncilCollective>;
+	type MinimumLiquidity = MinimumLiquidity;
 }
 
 impl prml_attestation::Trait for Runtime {
@@ -180,7 +201,19 @@ impl crml_sylo::device::Trait for Runtime {}
 impl crml_sylo::response::Trait for Runtime {}
 impl crml_sylo::inbox::Trait for Runtime {}
 impl crml_sylo::vault::Trait for Runtime {}
-impl crml_sylo::payment::Trait for Runtime {}
+impl crml_sylo::payment::Trait for Runtime {
+	type OnPaymentAccountChange = SyloFeeSponsorship;
+}
+
+parameter_types! {
+	pub const SponsorshipDeposit: Balance = 1 * DOLLARS;
+}
+impl crml_fee_sponsorship::Trait for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type Currency = SpendingAssetCurrency<Self>;
+	type SponsorshipDeposit = SponsorshipDeposit;
+}
 
 parameter_types! {
 	pub const EpochDuration: u64 = EPOCH_DURATION_IN_SLOTS;
@@ -205,6 +238,7 @@ parameter_types! {
 	pub const TransactionMaxWeightFee: Balance = 10 * DOLLARS;
 	// for a sane configuration, this should always be less than `AvailableBlockRatio`.
 	pub const TargetBlockFullness: Perbill = Perbill::from_percent(25);
+	pub const OperationalFeeMultiplier: u8 = 5;
 }
 
 pub type PositiveImbalance = <GenericAsset as MultiCurrencyAccounting>::PositiveImbalance;
@@ -228,9 +262,11 @@ impl crml_transaction_payment::Trait for Runtime {
 	type TransactionByteFee = TransactionByteFee;
 	type WeightToFee = ScaledWeightToFee<TransactionMinWeightFee, TransactionMaxWeightFee>;
 	type FeeMultiplierUpdate = TargetedFeeAdjustment<TargetBlockFullness>;
+	type OperationalFeeMultiplier = OperationalFeeMultiplier;
 	type BuyFeeAsset = CennzxSpot;
 	type GasMeteredCallResolver = GasMeteredCallResolver;
 	type FeePayer = FeePayerResolver;
+	type Event = Event;
 }
 
 parameter_types! {
@@ -553,7 +589,7 @@ construct_runtime!(
 		Babe: pallet_babe::{Module, Call, Storage, Config, Inherent(Timestamp)},
 		Timestamp: pallet_timestamp::{Module, Call, Storage, Inherent},
 		Attestation: prml_attestation::{Module, Call, Storage, Event<T>},
-		TransactionPayment: crml_transaction_payment::{Module, Storage},
+		TransactionPayment: crml_transaction_payment::{Module, Storage, Event<T>},
 		GenericAsset: pallet_generic_asset::{Module, Call, Storage, Event<T>, Config<T>},
 		Staking: crml_staking::{Module, Call, Config<T>, Storage, Event<T>},
 		Session: pallet_session::{Module, Call, Storage, Event, Config<T>},
@@ -579,6 +615,7 @@ construct_runtime!(
 		SyloVault: sylo_vault::{Module, Call, Storage},
 		SyloPayment: sylo_payment::{Module, Call, Storage},
 		CennzxSpot: crml_cennzx_spot::{Module, Call, Storage, Config<T>, Event<T>},
+		FeeSponsorship: crml_fee_sponsorship::{Module, Call, Storage, Event<T>},
 	}
 );
 
@@ -763,11 +800,28 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl crml_transaction_payment_rpc_runtime_api::FeeInfoApi<Block, AccountId, AssetId, Balance> for Runtime {
+		fn query_fee_info(
+			uxt: <Block as BlockT>::Extrinsic,
+			len: u32,
+		) -> Result<FeeInfo<AccountId, AssetId, Balance>, TransactionValidityError> {
+			let dispatch_info = uxt.get_dispatch_info();
+			// `ChargeTransactionPayment` is the 7th `SignedExtra`
+			let signed = uxt.signature.map(|(signer, _, extra)| (signer, extra.6));
+			TransactionPayment::query_fee_info(&uxt.function, dispatch_info, signed, len)
+		}
+
+		fn fee_payment_error(code: u8) -> Option<FeePaymentError> {
+			crml_transaction_payment::constants::error_code::fee_payment_error(code)
+		}
+	}
+
 	impl crml_cennzx_spot_rpc_runtime_api::CennzxSpotApi<
 		Block,
 		AssetId,
 		Balance,
 		AccountId,
+		BlockNumber,
 	> for Runtime {
 		fn buy_price(
 			buy_asset: AssetId,
@@ -777,7 +831,7 @@ impl_runtime_apis! {
 			let result = CennzxSpot::get_buy_price(buy_asset, buy_amount, sell_asset);
 			match result {
 				Ok(value) => CennzxSpotResult::Success(value),
-				Err(_) => CennzxSpotResult::Error,
+				Err(e) => CennzxSpotResult::Error(e.into()),
 			}
 		}
 
@@ -789,7 +843,29 @@ impl_runtime_apis! {
 			let result = CennzxSpot::get_sell_price(sell_asset, sell_amount, buy_asset);
 			match result {
 				Ok(value) => CennzxSpotResult::Success(value),
-				Err(_) => CennzxSpotResult::Error,
+				Err(e) => CennzxSpotResult::Error(e.into()),
+			}
+		}
+
+		fn buy_price_via_path(
+			path: Vec<AssetId>,
+			buy_amount: Balance,
+		) -> CennzxSpotResult<Balance> {
+			let result = CennzxSpot::get_buy_price_via_path(&path, buy_amount);
+			match result {
+				Ok(value) => CennzxSpotResult::Success(value),
+				Err(e) => CennzxSpotResult::Error(e.into()),
+			}
+		}
+
+		fn sell_price_via_path(
+			path: Vec<AssetId>,
+			sell_amount: Balance,
+		) -> CennzxSpotResult<Balance> {
+			let result = CennzxSpot::get_sell_price_via_path(&path, sell_amount);
+			match result {
+				Ok(value) => CennzxSpotResult::Success(value),
+				Err(e) => CennzxSpotResult::Error(e.into()),
 			}
 		}
 
@@ -808,6 +884,101 @@ impl_runtime_apis! {
 			let value = CennzxSpot::liquidity_price(asset_id, liquidity_to_buy);
 			(value.core, value.asset)
 		}
+
+		fn twap(
+			asset_id: AssetId,
+			window: BlockNumber,
+		) -> CennzxSpotResult<(Balance, Balance)> {
+			let result = CennzxSpot::twap(asset_id, window);
+			match result {
+				Ok(value) => CennzxSpotResult::Success((value.core, value.asset)),
+				Err(e) => CennzxSpotResult::Error(e.into()),
+			}
+		}
+
+		fn protocol_fee(
+			sell_asset: AssetId,
+			sell_amount: Balance,
+			buy_asset: AssetId,
+		) -> CennzxSpotResult<Balance> {
+			let result = CennzxSpot::get_protocol_fee(sell_asset, sell_amount, buy_asset);
+			match result {
+				Ok(value) => CennzxSpotResult::Success(value),
+				Err(e) => CennzxSpotResult::Error(e.into()),
+			}
+		}
+
+		fn buy_quote(
+			buy_asset: AssetId,
+			buy_amount: Balance,
+			sell_asset: AssetId,
+		) -> CennzxSpotResult<TradeQuote<AssetId, Balance>> {
+			let result = CennzxSpot::get_buy_quote(buy_asset, buy_amount, sell_asset);
+			match result {
+				Ok(quote) => CennzxSpotResult::Success(TradeQuote {
+					route: quote.route,
+					amount_in: quote.amount_in,
+					amount_out: quote.amount_out,
+					fee: quote.fee,
+					spot_price_before: quote.spot_price_before,
+					spot_price_after: quote.spot_price_after,
+					execution_price: quote.execution_price,
+					price_impact: quote.price_impact.into(),
+				}),
+				Err(e) => CennzxSpotResult::Error(e.into()),
+			}
+		}
+
+		fn sell_quote(
+			sell_asset: AssetId,
+			sell_amount: Balance,
+			buy_asset: AssetId,
+		) -> CennzxSpotResult<TradeQuote<AssetId, Balance>> {
+			let result = CennzxSpot::get_sell_quote(sell_asset, sell_amount, buy_asset);
+			match result {
+				Ok(quote) => CennzxSpotResult::Success(TradeQuote {
+					route: quote.route,
+					amount_in: quote.amount_in,
+					amount_out: quote.amount_out,
+					fee: quote.fee,
+					spot_price_before: quote.spot_price_before,
+					spot_price_after: quote.spot_price_after,
+					execution_price: quote.execution_price,
+					price_impact: quote.price_impact.into(),
+				}),
+				Err(e) => CennzxSpotResult::Error(e.into()),
+			}
+		}
+
+		fn fee_earnings(
+			account: AccountId,
+			asset_id: AssetId,
+		) -> FeeEarnings<Balance> {
+			let position = CennzxSpot::account_fee_earnings(&account, asset_id);
+			FeeEarnings {
+				core_cost: position.core_cost,
+				asset_cost: position.asset_cost,
+				unrealised_core_fees: position.unrealised_core_fees,
+				unrealised_asset_fees: position.unrealised_asset_fees,
+				realised_core_fees: position.realised_core_fees,
+				realised_asset_fees: position.realised_asset_fees,
+			}
+		}
+
+		fn exchanges() -> Vec<ExchangeInfo<AssetId, Balance, AccountId, BlockNumber>> {
+			CennzxSpot::exchanges()
+				.into_iter()
+				.map(|exchange| ExchangeInfo {
+					asset_id: exchange.asset_id,
+					exchange_address: exchange.exchange_address,
+					core_reserve: exchange.core_reserve,
+					asset_reserve: exchange.asset_reserve,
+					total_liquidity: exchange.total_liquidity,
+					fee_rate: exchange.fee_rate.into(),
+					created_at: exchange.created_at,
+				})
+				.collect()
+		}
 	}
 
 	impl sp_session::SessionKeys<Block> for Runtime {
@@ -15,22 +15,24 @@
 
 //! Extrinsic extension integration tests (doughnut, fee exchange)
 
-use cennznet_primitives::types::{AccountId, FeeExchange, FeeExchangeV1};
+use cennznet_primitives::types::{AccountId, Balance, FeeExchange, FeeExchangeV1};
 use cennznet_runtime::{
 	constants::{asset::*, currency::*},
+	impls::GasHandler,
 	Call, CennzxSpot, CheckedExtrinsic, ContractTransactionBaseFee, Event, Executive, GenericAsset, Origin, Runtime,
+	TransactionPayment,
 };
 use cennznet_testing::keyring::{alice, bob, charlie, dave, ferdie, signed_extra};
 use codec::Encode;
-use crml_transaction_payment::constants::error_code::*;
+use crml_transaction_payment::{constants::error_code::*, ChargeTransactionPayment, RawEvent as FeeEvent};
 use frame_support::{
 	additional_traits::MultiCurrencyAccounting as MultiCurrency,
-	weights::{DispatchClass, DispatchInfo},
+	weights::{DispatchClass, DispatchInfo, GetDispatchInfo},
 };
 use frame_system::{EventRecord, Phase};
 use pallet_contracts::{ContractAddressFor, RawEvent, Schedule};
 use sp_runtime::{
-	traits::Hash,
+	traits::{Hash, SignedExtension},
 	transaction_validity::{InvalidTransaction, TransactionValidityError},
 	DispatchError,
 };
@@ -45,7 +47,7 @@ use common::mock::{
 };
 use doughnut::{make_contract_cennznut, make_doughnut, make_runtime_cennznut};
 
-/// Setup a contract on-chain, return it's deployed address
+/// Setup a contract on-chain, return it's deployed address, code hash and (`put_code`, `instantiate`) fees
 /// This does the `put_code` and `instantiate` steps
 /// Note: It will also initialize the block and requires `TestExternalities` to succeed
 /// `contract_wabt` is the contract WABT to be deployed
@@ -54,7 +56,11 @@ use doughnut::{make_contract_cennznut, make_doughnut, make_runtime_cennznut};
 fn setup_contract(
 	contract_wabt: &'static str,
 	contract_deployer: AccountId,
-) -> (AccountId, <<Runtime as frame_system::Trait>::Hashing as Hash>::Output) {
+) -> (
+	AccountId,
+	<<Runtime as frame_system::Trait>::Hashing as Hash>::Output,
+	(Balance, Balance),
+) {
 	let wasm = wabt::wat2wasm(contract_wabt).unwrap();
 	let code_hash = <Runtime as frame_system::Trait>::Hashing::hash(&wasm);
 
@@ -66,6 +72,7 @@ fn setup_contract(
 		signed: Some((contract_deployer.clone(), signed_extra(0, 0, None, None))),
 		function: put_code_call,
 	});
+	let put_code_fee = extrinsic_fee_for(&put_code_extrinsic);
 	let put_code_result = Executive::apply_extrinsic(put_code_extrinsic);
 	println!(
 		"{:?}, CPAY Balance: {:?}",
@@ -85,6 +92,7 @@ fn setup_contract(
 		signed: Some((contract_deployer, signed_extra(1, 0, None, None))),
 		function: instantiate_call,
 	});
+	let instantiate_fee = extrinsic_fee_for(&instantiate_extrinsic);
 	let instantiate_result = Executive::apply_extrinsic(instantiate_extrinsic);
 	println!(
 		"{:?}, CPAY Balance: {:?}",
@@ -96,6 +104,7 @@ fn setup_contract(
 	(
 		<Runtime as pallet_contracts::Trait>::DetermineContractAddress::contract_address_for(&code_hash, &[], &alice()),
 		code_hash,
+		(put_code_fee, instantiate_fee),
 	)
 }
 
@@ -196,6 +205,87 @@ fn generic_asset_transfer_works_with_fee_exchange() {
 		});
 }
 
+#[test]
+fn generic_asset_transfer_falls_back_to_next_fee_exchange_asset() {
+	let initial_balance = 100 * DOLLARS;
+	let initial_liquidity = 50 * DOLLARS;
+	let transfer_amount = 25 * MICROS;
+
+	let runtime_call = Call::GenericAsset(pallet_generic_asset::Call::transfer(
+		CENTRAPAY_ASSET_ID,
+		bob(),
+		transfer_amount,
+	));
+
+	ExtBuilder::default()
+		.initial_balance(initial_balance)
+		.build()
+		.execute_with(|| {
+			// Alice sets up CENNZ <> CPAY liquidity, there is no PLUG <> CPAY liquidity
+			assert!(CennzxSpot::add_liquidity(
+				Origin::signed(alice()),
+				CENNZ_ASSET_ID,
+				0,                 // min liquidity
+				initial_liquidity, // liquidity CENNZ
+				initial_liquidity, // liquidity CPAY
+			)
+			.is_ok());
+
+			// Prefer paying the transaction fee in PLUG, falling back to CENNZ
+			let fee_exchange = FeeExchange::new_v2(vec![(PLUG_ASSET_ID, 5 * DOLLARS), (CENNZ_ASSET_ID, 5 * DOLLARS)]);
+			let xt = sign(CheckedExtrinsic {
+				signed: Some((alice(), signed_extra(0, 0, None, Some(fee_exchange)))),
+				function: runtime_call,
+			});
+			let cennz_sold_amount =
+				CennzxSpot::get_asset_to_core_buy_price(&CENNZ_ASSET_ID, extrinsic_fee_for(&xt)).unwrap();
+
+			Executive::initialize_block(&header());
+			let r = Executive::apply_extrinsic(xt);
+			assert!(r.is_ok());
+
+			assert_eq!(
+				<GenericAsset as MultiCurrency>::free_balance(&alice(), Some(PLUG_ASSET_ID)),
+				initial_balance
+			);
+			assert_eq!(
+				<GenericAsset as MultiCurrency>::free_balance(&alice(), Some(CENNZ_ASSET_ID)),
+				initial_balance - initial_liquidity - cennz_sold_amount,
+			);
+			assert_eq!(
+				<GenericAsset as MultiCurrency>::free_balance(&alice(), Some(CENTRAPAY_ASSET_ID)),
+				initial_balance - initial_liquidity - transfer_amount
+			);
+		});
+}
+
+#[test]
+fn generic_asset_transfer_fails_when_no_fee_exchange_asset_can_pay() {
+	let runtime_call = Call::GenericAsset(pallet_generic_asset::Call::transfer(
+		CENTRAPAY_ASSET_ID,
+		bob(),
+		25 * MICROS,
+	));
+
+	ExtBuilder::default()
+		.initial_balance(100 * DOLLARS)
+		.build()
+		.execute_with(|| {
+			// There is no liquidity for either asset
+			let fee_exchange = FeeExchange::new_v2(vec![(PLUG_ASSET_ID, 5 * DOLLARS), (CENNZ_ASSET_ID, 5 * DOLLARS)]);
+			let xt = sign(CheckedExtrinsic {
+				signed: Some((alice(), signed_extra(0, 0, None, Some(fee_exchange)))),
+				function: runtime_call,
+			});
+
+			Executive::initialize_block(&header());
+			assert_eq!(
+				Executive::apply_extrinsic(xt),
+				Err(InvalidTransaction::Custom(EMPTY_EXCHANGE_POOL).into())
+			);
+		});
+}
+
 #[test]
 fn contract_fails() {
 	ExtBuilder::default()
@@ -203,7 +293,7 @@ fn contract_fails() {
 		.gas_price(1)
 		.build()
 		.execute_with(|| {
-			let (contract_address, _) = setup_contract(CONTRACT_WITH_TRAP, dave());
+			let (contract_address, _, _) = setup_contract(CONTRACT_WITH_TRAP, dave());
 
 			// Call the newly instantiated contract. The contract is expected to dispatch a call
 			// and then trap.
@@ -262,7 +352,8 @@ fn contract_dispatches_runtime_call_funds_are_safu() {
 				.encode(),
 				&encoded_ga_transfer
 			);
-			let (contract_address, code_hash) = setup_contract(CONTRACT_WITH_GA_TRANSFER, alice());
+			let (contract_address, code_hash, (put_code_fee, instantiate_fee)) =
+				setup_contract(CONTRACT_WITH_GA_TRANSFER, alice());
 
 			// Call the newly instantiated contract. The contract is expected to dispatch a call
 			// and then trap.
@@ -293,6 +384,11 @@ fn contract_dispatches_runtime_call_funds_are_safu() {
 					event: Event::pallet_contracts(RawEvent::CodeStored(code_hash.into())),
 					topics: vec![],
 				},
+				EventRecord {
+					phase: Phase::ApplyExtrinsic(0),
+					event: Event::crml_transaction_payment(FeeEvent::TransactionFeeSettled(alice(), put_code_fee, 0)),
+					topics: vec![],
+				},
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(0),
 					event: Event::frame_system(frame_system::Event::ExtrinsicSuccess(DispatchInfo {
@@ -312,6 +408,15 @@ fn contract_dispatches_runtime_call_funds_are_safu() {
 					event: Event::pallet_contracts(RawEvent::Instantiated(alice(), contract_address.clone())),
 					topics: vec![],
 				},
+				EventRecord {
+					phase: Phase::ApplyExtrinsic(1),
+					event: Event::crml_transaction_payment(FeeEvent::TransactionFeeSettled(
+						alice(),
+						instantiate_fee,
+						0,
+					)),
+					topics: vec![],
+				},
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(1),
 					event: Event::frame_system(frame_system::Event::ExtrinsicSuccess(DispatchInfo {
@@ -332,6 +437,8 @@ fn contract_dispatches_runtime_call_funds_are_safu() {
 						1636,
 						// CPAY to buy
 						extrinsic_fee_for(&contract_call_extrinsic),
+						// protocol fee
+						0,
 					)),
 					topics: vec![],
 				},
@@ -353,6 +460,8 @@ fn contract_dispatches_runtime_call_funds_are_safu() {
 							// it's not clear where this additional 360 gas cost comes from
 							// prepare_code(CONTRACT_WITH_GA_TRANSFER) will show the implementation code...
 							+ 360,
+						// protocol fee
+						0,
 					)),
 					topics: vec![],
 				},
@@ -362,6 +471,16 @@ fn contract_dispatches_runtime_call_funds_are_safu() {
 					event: Event::pallet_contracts(RawEvent::Dispatched(contract_address.clone(), false)),
 					topics: vec![],
 				},
+				// Settles transaction fees, nothing is refunded
+				EventRecord {
+					phase: Phase::ApplyExtrinsic(2),
+					event: Event::crml_transaction_payment(FeeEvent::TransactionFeeSettled(
+						bob(),
+						extrinsic_fee_for(&contract_call_extrinsic),
+						0,
+					)),
+					topics: vec![],
+				},
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(2),
 					event: Event::frame_system(frame_system::Event::ExtrinsicSuccess(DispatchInfo {
@@ -497,6 +616,56 @@ fn contract_call_works_without_fee_exchange() {
 		});
 }
 
+#[test]
+fn contract_call_unused_gas_is_not_refunded_as_unused_weight() {
+	let balance_amount = 100 * DOLLARS;
+	let gas_limit_amount = 5 * DOLLARS;
+
+	let contract_call = Call::Contracts(pallet_contracts::Call::call::<Runtime>(
+		bob(),
+		0,
+		gas_limit_amount as u64,
+		vec![],
+	));
+
+	ExtBuilder::default()
+		.initial_balance(balance_amount)
+		.gas_price(1)
+		.build()
+		.execute_with(|| {
+			Executive::initialize_block(&header());
+			let info = contract_call.get_dispatch_info();
+			let len = contract_call.encode().len();
+			let pre = ChargeTransactionPayment::<Runtime>::from(0, None)
+				.pre_dispatch(&alice(), &contract_call, info, len)
+				.unwrap();
+			let fee = ChargeTransactionPayment::<Runtime>::compute_fee(len as u32, info, 0);
+
+			// None of the gas limit is spent
+			let gas_meter =
+				<GasHandler as pallet_contracts::GasHandler<Runtime>>::fill_gas(&alice(), gas_limit_amount as u64)
+					.unwrap();
+			<GasHandler as pallet_contracts::GasHandler<Runtime>>::empty_unused_gas(&alice(), gas_meter);
+
+			// The unused gas is refunded by the gas handler, and only once
+			assert_eq!(TransactionPayment::unused_weight(), 0);
+			assert_eq!(
+				<GenericAsset as MultiCurrency>::free_balance(&alice(), Some(CENTRAPAY_ASSET_ID)),
+				balance_amount - fee,
+			);
+
+			ChargeTransactionPayment::<Runtime>::post_dispatch(pre, info, len);
+			assert_eq!(
+				<GenericAsset as MultiCurrency>::free_balance(&alice(), Some(CENTRAPAY_ASSET_ID)),
+				balance_amount - fee,
+			);
+			assert!(frame_system::Module::<Runtime>::events()
+				.iter()
+				.any(|record| record.event
+					== Event::crml_transaction_payment(FeeEvent::TransactionFeeSettled(alice(), fee, 0))));
+		});
+}
+
 #[test]
 fn contract_call_works_with_fee_exchange() {
 	let initial_balance = 1_000 * DOLLARS;
@@ -15,17 +15,21 @@
 
 //! Fee integration tests
 
+use cennznet_primitives::types::{AccountId, Balance};
 use cennznet_runtime::{
 	constants::{asset::*, currency::*, fee::MAX_WEIGHT},
-	Call, CheckedExtrinsic, Runtime, TransactionBaseFee, TransactionMaxWeightFee, TransactionMinWeightFee,
-	UncheckedExtrinsic,
+	sylo_payment, sylo_vault, Call, CheckedExtrinsic, Executive, Runtime, TransactionBaseFee, TransactionMaxWeightFee,
+	TransactionMinWeightFee, UncheckedExtrinsic,
 };
-use cennznet_testing::keyring::{alice, bob, sign, signed_extra};
+use cennznet_testing::keyring::{alice, bob, charlie, dave, sign, signed_extra};
 use codec::Encode;
 use crml_transaction_payment::ChargeTransactionPayment;
 use frame_support::weights::{DispatchClass, DispatchInfo, GetDispatchInfo};
+use sp_runtime::transaction_validity::{TransactionPriority, TransactionSource};
+use std::cmp::Reverse;
 
 mod common;
+use common::helpers::{self, extrinsic_fee_for};
 use common::mock::ExtBuilder;
 
 // Make signed transaction given a `Call`
@@ -72,6 +76,73 @@ fn fee_scaling_min_weight() {
 	});
 }
 
+// Make a signed transaction from `who` tipping `tip` given a `Call`, valid in the test runtime
+fn tipped_tx(who: AccountId, tip: Balance, call: Call) -> UncheckedExtrinsic {
+	helpers::sign(CheckedExtrinsic {
+		signed: Some((who, signed_extra(0, tip, None, None))),
+		function: call,
+	})
+}
+
+// The priority of `xt` in the transaction pool
+fn pool_priority(xt: UncheckedExtrinsic) -> TransactionPriority {
+	Executive::validate_transaction(TransactionSource::External, xt)
+		.expect("transaction is valid")
+		.priority
+}
+
+fn small_call() -> Call {
+	Call::SyloVault(sylo_vault::Call::upsert_value(b"key".to_vec(), b"value".to_vec()))
+}
+
+fn large_call() -> Call {
+	Call::SyloVault(sylo_vault::Call::upsert_value(b"key".to_vec(), vec![1_u8; 10_000]))
+}
+
+#[test]
+fn small_tipping_transaction_is_prioritised_over_large_transaction() {
+	ExtBuilder::default()
+		.initial_balance(100 * DOLLARS)
+		.build()
+		.execute_with(|| {
+			let tip = 1 * DOLLARS;
+			let small_tx = tipped_tx(alice(), tip, small_call());
+			let large_tx = tipped_tx(bob(), 0, large_call());
+
+			// The large transaction pays the larger fee in total...
+			assert!(extrinsic_fee_for(&large_tx) > extrinsic_fee_for(&small_tx) + tip);
+			// ...but less for the share of a block it fills
+			assert!(pool_priority(small_tx) > pool_priority(large_tx));
+		});
+}
+
+#[test]
+fn transaction_pool_orders_mixed_load_by_fee_per_block_share() {
+	ExtBuilder::default()
+		.initial_balance(100 * DOLLARS)
+		.build()
+		.execute_with(|| {
+			let operational_call = Call::SyloPayment(sylo_payment::Call::revoke_payment_account_self());
+			let transactions = vec![
+				("large", tipped_tx(alice(), 0, large_call())),
+				("small", tipped_tx(bob(), 0, small_call())),
+				("operational", tipped_tx(charlie(), 0, operational_call)),
+				("small tipping", tipped_tx(dave(), 1 * DOLLARS, small_call())),
+			];
+
+			let mut queue: Vec<(&str, TransactionPriority)> = transactions
+				.into_iter()
+				.map(|(name, xt)| (name, pool_priority(xt)))
+				.collect();
+			queue.sort_by_key(|(_, priority)| Reverse(*priority));
+
+			assert_eq!(
+				queue.into_iter().map(|(name, _)| name).collect::<Vec<_>>(),
+				vec!["operational", "small tipping", "small", "large"],
+			);
+		});
+}
+
 // These following tests may be used to inspect transaction fee values.
 // They are not required to assert correctness.
 #[test]
@@ -0,0 +1,110 @@
+/* Copyright 2020 Centrality Investments Limited
+*
+* Licensed under the LGPL, Version 3.0 (the "License");
+* you may not use this file except in compliance with the License.
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific language governing permissions and
+* limitations under the License.
+* You may obtain a copy of the License at the root of this project source code,
+* or at:
+*     https://centrality.ai/licenses/gplv3.txt
+*     https://centrality.ai/licenses/lgplv3.txt
+*/
+
+//! Fee sponsorship tests
+
+use cennznet_primitives::types::{AccountId, Balance};
+use cennznet_runtime::{
+	constants::asset::*, sylo_vault, Call, CheckedExtrinsic, Executive, FeeSponsorship, GenericAsset, Origin,
+	SyloPayment, TransactionMaxWeightFee, UncheckedExtrinsic,
+};
+use cennznet_testing::keyring::{bob, charlie, dave, signed_extra};
+use codec::Encode;
+use crml_fee_sponsorship::{CallFilter, SpendLimit};
+use frame_support::{additional_traits::MultiCurrencyAccounting as MultiCurrency, assert_ok};
+
+mod common;
+
+use common::helpers::{extrinsic_fee_for, header, sign};
+use common::mock::ExtBuilder;
+
+fn signed_xt(origin: AccountId, nonce: u32, call: Call) -> UncheckedExtrinsic {
+	sign(CheckedExtrinsic {
+		signed: Some((origin, signed_extra(nonce, 0, None, None))),
+		function: call,
+	})
+}
+
+fn spending_balance(who: &AccountId) -> Balance {
+	<GenericAsset as MultiCurrency>::free_balance(who, Some(GenericAsset::spending_asset_id()))
+}
+
+#[test]
+fn sponsored_call_is_paid_by_sponsor_up_to_spend_limit() {
+	let call = Call::GenericAsset(pallet_generic_asset::Call::transfer(CENNZ_ASSET_ID, dave(), 100));
+	let encoded_call = call.encode();
+
+	ExtBuilder::default()
+		.initial_balance(TransactionMaxWeightFee::get())
+		.build()
+		.execute_with(|| {
+			let first_xt = signed_xt(charlie(), 0, call.clone());
+			let second_xt = signed_xt(charlie(), 1, call);
+			let fee = extrinsic_fee_for(&first_xt);
+
+			assert_ok!(FeeSponsorship::sponsor(
+				Origin::signed(bob()),
+				vec![CallFilter {
+					module: encoded_call[0],
+					method: Some(encoded_call[1]),
+				}],
+				vec![charlie()],
+				Some(SpendLimit { amount: fee, period: 1 }),
+			));
+
+			let bob_balance = spending_balance(&bob());
+			let charlie_balance = spending_balance(&charlie());
+
+			Executive::initialize_block(&header());
+			assert!(Executive::apply_extrinsic(first_xt).is_ok());
+			assert_eq!(spending_balance(&bob()), bob_balance - fee);
+			assert_eq!(spending_balance(&charlie()), charlie_balance);
+
+			// The spend limit for the block is reached
+			assert!(Executive::apply_extrinsic(second_xt).is_ok());
+			assert_eq!(spending_balance(&bob()), bob_balance - fee);
+			assert_eq!(spending_balance(&charlie()), charlie_balance - fee);
+		});
+}
+
+#[test]
+fn sylo_payment_account_sponsors_sylo_calls() {
+	ExtBuilder::default()
+		.initial_balance(TransactionMaxWeightFee::get())
+		.build()
+		.execute_with(|| {
+			assert!(FeeSponsorship::open_sponsorships().is_empty());
+
+			assert_ok!(SyloPayment::set_payment_account(Origin::ROOT, bob()));
+			assert_ok!(SyloPayment::set_payment_account(Origin::ROOT, dave()));
+			assert_eq!(FeeSponsorship::sponsorships_of(&bob()).len(), 1);
+			assert!(FeeSponsorship::sponsorships_of(&dave()).is_empty());
+
+			let call = Call::SyloVault(sylo_vault::Call::upsert_value(b"key".to_vec(), b"value".to_vec()));
+			assert_eq!(
+				FeeSponsorship::sponsor_for(&charlie(), &call.encode(), 1).map(|(_, sponsor)| sponsor),
+				Some(bob())
+			);
+
+			// The next payment account takes over the sponsorship
+			assert_ok!(SyloPayment::revoke_payment_account_self(Origin::signed(bob())));
+			assert!(FeeSponsorship::sponsorships_of(&bob()).is_empty());
+			assert_eq!(FeeSponsorship::sponsorships_of(&dave()).len(), 1);
+
+			assert_ok!(SyloPayment::revoke_payment_account_self(Origin::signed(dave())));
+			assert!(FeeSponsorship::open_sponsorships().is_empty());
+			assert_eq!(FeeSponsorship::sponsor_for(&charlie(), &call.encode(), 1), None);
+		});
+}
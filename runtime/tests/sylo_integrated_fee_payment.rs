@@ -17,11 +17,13 @@
 
 use cennznet_primitives::types::{AccountId, Balance};
 use cennznet_runtime::{
-	constants::asset::*, sylo_e2ee, sylo_groups, sylo_inbox, sylo_response, sylo_vault, Call, CheckedExtrinsic,
-	Executive, GenericAsset, Origin, SyloPayment, TransactionMaxWeightFee,
+	constants::{asset::*, call_index},
+	sylo_e2ee, sylo_groups, sylo_inbox, sylo_response, sylo_vault, Call, CheckedExtrinsic, Executive, GenericAsset,
+	Origin, SyloPayment, TransactionMaxWeightFee, TransactionPayment,
 };
 use cennznet_testing::keyring::{bob, charlie, dave, signed_extra};
-use frame_support::{additional_traits::MultiCurrencyAccounting as MultiCurrency, assert_ok};
+use codec::Encode;
+use frame_support::{additional_traits::MultiCurrencyAccounting as MultiCurrency, assert_ok, weights::GetDispatchInfo};
 
 mod common;
 
@@ -63,6 +65,35 @@ fn non_sylo_call_is_not_paid_by_payment_account() {
 		});
 }
 
+#[test]
+fn call_indices_match_the_runtime() {
+	let calls = [
+		(
+			call_index::SYLO_GROUPS,
+			Call::SyloGroups(sylo_groups::Call::leave_group(Default::default(), None)),
+		),
+		(
+			call_index::SYLO_E2EE,
+			Call::SyloE2EE(sylo_e2ee::Call::register_device(1, vec![])),
+		),
+		(
+			call_index::SYLO_INBOX,
+			Call::SyloInbox(sylo_inbox::Call::delete_values(vec![])),
+		),
+		(
+			call_index::SYLO_RESPONSE,
+			Call::SyloResponse(sylo_response::Call::remove_response(Default::default())),
+		),
+		(
+			call_index::SYLO_VAULT,
+			Call::SyloVault(sylo_vault::Call::delete_values(vec![])),
+		),
+	];
+	for (index, call) in calls.iter() {
+		assert_eq!(call.encode()[0], *index);
+	}
+}
+
 #[test]
 fn sylo_e2ee_call_is_paid_by_payment_account() {
 	let call = Call::SyloE2EE(sylo_e2ee::Call::register_device(1, vec![]));
@@ -168,3 +199,31 @@ fn sylo_groups_call_is_paid_by_payment_account() {
 			assert_eq!(bob_balance_after_calls, bob_balance - call_fee);
 		});
 }
+
+#[test]
+fn sylo_call_fee_info_is_paid_by_payment_account() {
+	let call = Call::SyloVault(sylo_vault::Call::upsert_value(b"key".to_vec(), b"value".to_vec()));
+
+	ExtBuilder::default()
+		.initial_balance(TransactionMaxWeightFee::get())
+		.build()
+		.execute_with(|| {
+			assert_ok!(SyloPayment::set_payment_account(Origin::ROOT, bob()));
+
+			let xt = sign(CheckedExtrinsic {
+				signed: Some((charlie(), signed_extra(0, 0, None, None))),
+				function: call.clone(),
+			});
+			let fee_info = TransactionPayment::query_fee_info(
+				&call,
+				xt.get_dispatch_info(),
+				Some((charlie(), signed_extra(0, 0, None, None).6)),
+				xt.encode().len() as u32,
+			)
+			.unwrap();
+
+			assert_eq!(fee_info.payer, Some(bob()));
+			assert_eq!(fee_info.partial_fee, extrinsic_fee_for(&xt));
+			assert_eq!(fee_info.fee_exchange, None);
+		});
+}